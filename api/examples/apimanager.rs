@@ -1,9 +1,10 @@
 use space_traders_api::ApiManager;
+use space_traders_api::types::{WaypointQuery, WaypointType};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let manager = ApiManager::load_from_env("bear", "COSMIC".into()).await?;
 
-    println!("{:?}", manager.find_waypoint_type(1, |w| w.is_jump_gate()).await?);
+    println!("{:?}", manager.find_waypoint_type(1, WaypointQuery::new().of_type(WaypointType::JumpGate)).await?);
     Ok(())
 }
\ No newline at end of file
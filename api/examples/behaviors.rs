@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use space_traders_api::ApiManager;
+use space_traders_api::behaviors::{BehaviorRuntime, ShipBehavior, ShipContext, ShipState, StateStore, Transition};
+use space_traders_api::error;
+use space_traders_api::scheduler::{RateLimit, Scheduler};
+use space_traders_api::types::WaypointSymbol;
+
+/// Flies back and forth between two waypoints, docking at each.
+struct Shuttle {
+    stops: [WaypointSymbol; 2],
+}
+
+#[async_trait]
+impl ShipBehavior for Shuttle {
+    fn name(&self) -> &str {
+        "shuttle"
+    }
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> error::Result<Transition> {
+        match state {
+            ShipState::Idle => ctx.navigate(self.stops[0].clone()).await,
+            ShipState::Navigating { .. } => ctx.dock().await,
+            ShipState::Docked { waypoint } => {
+                let next = if *waypoint == self.stops[0] { &self.stops[1] } else { &self.stops[0] };
+                ctx.navigate(next.clone()).await
+            }
+            _ => ctx.sync().await,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ApiManager::load_from_env("bear", "COSMIC".into()).await?;
+    let ship = manager.api.list_ships().await?.remove(0);
+    let home = ship.nav.waypoint_symbol.clone();
+    let away = std::env::args().nth(1).map(|s| WaypointSymbol::new(&s)).expect("usage: behaviors <waypoint>")?;
+
+    let mut runtime = BehaviorRuntime::new(manager.api.clone(), Scheduler::new(RateLimit::default()), StateStore::open("ship-states.json")?);
+    runtime.spawn(ship.symbol, Box::new(Shuttle { stops: [away, home] }));
+
+    for (ship_symbol, result) in runtime.run().await {
+        println!("{} finished: {:?}", ship_symbol, result);
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use space_traders_api::ApiManager;
+use space_traders_api::behaviors::{BehaviorRuntime, MarketCache, MiningBehavior, MiningConfig, StateStore, SurveyStore};
+use space_traders_api::scheduler::{RateLimit, Scheduler};
+use space_traders_api::types::ShipRole;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ApiManager::load_from_env("bear", "COSMIC".into()).await?;
+    let markets = Arc::new(MarketCache::default());
+    let surveys = Arc::new(SurveyStore::default());
+
+    let mut runtime = BehaviorRuntime::new(manager.api.clone(), Scheduler::new(RateLimit::default()), StateStore::open("ship-states.json")?);
+    let mut stats = Vec::new();
+    for ship in manager.api.list_ships().await? {
+        if ship.registration.role != ShipRole::Excavator {
+            continue;
+        }
+
+        let behavior = MiningBehavior::new(MiningConfig::default(), markets.clone(), surveys.clone());
+        stats.push((ship.symbol.clone(), behavior.stats()));
+        runtime.spawn(ship.symbol, Box::new(behavior));
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(300)).await;
+            for (ship_symbol, stats) in stats.iter() {
+                let stats = stats.lock().unwrap();
+                println!("{}: {} credits, {:.0} credits/hour", ship_symbol, stats.credits_earned, stats.credits_per_hour(chrono::Utc::now()));
+            }
+        }
+    });
+
+    for (ship_symbol, result) in runtime.run().await {
+        println!("{} stopped: {:?}", ship_symbol, result);
+    }
+    Ok(())
+}
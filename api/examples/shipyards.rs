@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use space_traders_api::ApiManager;
+use space_traders_api::behaviors::ShipyardAdvisor;
+use space_traders_api::types::{ShipType, WaypointQuery, WaypointTraitSymbol};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let manager = ApiManager::load_from_env("bear", "COSMIC".into()).await?;
+    let credits = manager.api.get_agent().await?.credits;
+
+    let mut advisor = ShipyardAdvisor::new(HashMap::from([
+        (ShipType::ShipMiningDrone, 4_000.0),
+        (ShipType::ShipOreHound, 9_000.0),
+        (ShipType::ShipLightHauler, 12_000.0),
+    ]));
+    for waypoint_symbol in manager.find_waypoint_type(5, WaypointQuery::new().with_trait(WaypointTraitSymbol::Shipyard)).await? {
+        match manager.api.get_shipyard(waypoint_symbol.clone()).await {
+            Ok(shipyard) => advisor.add_shipyard(&shipyard, chrono::Utc::now()),
+            Err(e) => println!("skipping {}: {}", waypoint_symbol, e),
+        }
+    }
+
+    for recommendation in advisor.recommend(credits, 10_000, 0.0, chrono::Utc::now()) {
+        println!(
+            "{:?} at {} for {}: pays back in {}h",
+            recommendation.offer.ship_type,
+            recommendation.offer.waypoint_symbol,
+            recommendation.offer.price,
+            recommendation.payback.num_hours(),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::{error, SpaceTradersApi, types};
+
+impl SpaceTradersApi {
+    pub async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract> {
+        self.get_one(format!("my/contracts/{}", contract_id).as_str()).await
+    }
+
+    pub async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData> {
+        self.post_empty(format!("my/contracts/{}/accept", contract_id).as_str()).await
+    }
+
+    pub async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData> {
+        self.post(format!("my/contracts/{}/deliver", contract_id).as_str(), &types::DeliverContractRequest {
+            ship_symbol,
+            trade_symbol,
+            units,
+        }).await
+    }
+
+    pub async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData> {
+        self.post_empty(format!("my/contracts/{}/fulfill", contract_id).as_str()).await
+    }
+
+    /// Asks the faction at the ship's docked waypoint for a new contract.
+    pub async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract> {
+        self.post_empty::<types::NegotiateContractData>(format!("my/ships/{}/negotiate/contract", ship_symbol).as_str()).await
+            .map(|data| data.contract)
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{DecodeError, Result};
+use crate::{SpaceTradersApi, types};
+
+const SYSTEMS_DUMP: &str = "systems.json";
+
+/// How far a download has got, reported after each chunk arrives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownloadProgress {
+    pub bytes: u64,
+    /// The size of the whole download, if the server said.
+    pub total_bytes: Option<u64>,
+    /// How many items have been decoded so far.
+    pub decoded: usize,
+}
+
+impl SpaceTradersApi {
+    /// Every system and its waypoint references, from the server's
+    /// `systems.json` dump rather than thousands of `list_systems` pages.
+    pub async fn download_all_systems<P: FnMut(DownloadProgress)>(&self, progress: P) -> Result<Vec<types::System>> {
+        let mut systems = Vec::new();
+        self.download_all_systems_into(|system| systems.push(system), progress).await?;
+        Ok(systems)
+    }
+
+    /// Like `download_all_systems`, but hands each system to `sink` as soon
+    /// as it's decoded, so the whole universe is never held at once. Returns
+    /// how many systems there were.
+    pub async fn download_all_systems_into<S: FnMut(types::System), P: FnMut(DownloadProgress)>(&self, mut sink: S, mut progress: P) -> Result<usize> {
+        let method = reqwest::Method::GET;
+        let started = Instant::now();
+        let mut response = self.request(method.clone(), SYSTEMS_DUMP).send().await?;
+        crate::metrics::request(&method, SYSTEMS_DUMP, response.status().as_u16(), started.elapsed());
+        response = response.error_for_status()?;
+
+        let mut decoder = ArrayDecoder::default();
+        let mut current = DownloadProgress {
+            bytes: 0,
+            total_bytes: response.content_length(),
+            decoded: 0,
+        };
+        while let Some(chunk) = response.chunk().await? {
+            current.bytes += chunk.len() as u64;
+            decoder.feed(&chunk, |system| {
+                sink(system);
+                current.decoded += 1;
+            })?;
+            progress(current);
+        }
+        decoder.finish()?;
+
+        tracing::debug!(systems = current.decoded, bytes = current.bytes, elapsed_ms = started.elapsed().as_millis() as u64, "downloaded systems");
+        Ok(current.decoded)
+    }
+}
+
+/// Splits a JSON array that arrives in chunks into its elements, decoding
+/// each one as soon as it's complete, so only one is buffered at a time.
+#[derive(Debug, Default)]
+struct ArrayDecoder {
+    element: Vec<u8>,
+    /// 1 inside the array, more inside one of its elements.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    ended: bool,
+}
+
+impl ArrayDecoder {
+    fn feed<T: DeserializeOwned, F: FnMut(T)>(&mut self, bytes: &[u8], mut decoded: F) -> Result<()> {
+        for &byte in bytes {
+            // JSON's structural characters are ASCII, so they never turn up inside a multibyte character
+            if self.in_string {
+                self.element.push(byte);
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match (self.depth, byte) {
+                (_, b' ' | b'\t' | b'\r' | b'\n') if self.depth <= 1 && self.element.is_empty() => {}
+                (0, b'[') if !self.ended => self.depth = 1,
+                (0, _) => return Err(invalid(bytes, "expected a JSON array")),
+                (1, b',') => {
+                    if self.element.is_empty() {
+                        return Err(invalid(bytes, "expected an array element before `,`"));
+                    }
+                    decoded(self.decode()?);
+                }
+                (1, b']') => {
+                    if !self.element.is_empty() {
+                        decoded(self.decode()?);
+                    }
+                    self.depth = 0;
+                    self.ended = true;
+                }
+                (_, b'{' | b'[') => {
+                    self.depth += 1;
+                    self.element.push(byte);
+                }
+                (_, b'}' | b']') => {
+                    self.depth -= 1;
+                    self.element.push(byte);
+                }
+                (_, b'"') => {
+                    self.in_string = true;
+                    self.element.push(byte);
+                }
+                _ => self.element.push(byte),
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if the array was cut off before its end.
+    fn finish(&self) -> Result<()> {
+        match self.ended {
+            true => Ok(()),
+            false => Err(invalid(&self.element, "the array ended early")),
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&mut self) -> Result<T> {
+        let element = std::mem::take(&mut self.element);
+        serde_json::from_slice(&element).map_err(|e| DecodeError { message: String::from_utf8_lossy(&element).into_owned(), error: e }.into())
+    }
+}
+
+fn invalid(bytes: &[u8], reason: &str) -> crate::error::Error {
+    DecodeError {
+        message: String::from_utf8_lossy(bytes).into_owned(),
+        error: serde::de::Error::custom(reason),
+    }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::api::dump::ArrayDecoder;
+
+    #[test]
+    fn test_array_decoder() {
+        let systems = json!([
+            { "symbol": "X1-DF55", "waypoints": [{ "symbol": "X1-DF55-20250Z", "x": -1 }], "factions": [] },
+            { "symbol": "X1-AB12", "name": "a \"quoted\" ] , { name", "waypoints": [] },
+            [1, 2, [3]],
+            "ünïcode",
+        ]);
+        let text = format!(" \n{}\n", serde_json::to_string_pretty(&systems).unwrap());
+
+        // however the download is split into chunks, the same elements come out
+        for chunk_size in [1, 2, 7, 64, text.len()] {
+            let mut decoder = ArrayDecoder::default();
+            let mut decoded = Vec::<Value>::new();
+            for chunk in text.as_bytes().chunks(chunk_size) {
+                decoder.feed(chunk, |element| decoded.push(element)).unwrap();
+            }
+            decoder.finish().unwrap();
+            assert_eq!(Value::Array(decoded), systems, "chunks of {}", chunk_size);
+        }
+
+        let mut decoder = ArrayDecoder::default();
+        decoder.feed::<Value, _>(b"[{\"symbol\": \"X1-DF55\"}, {\"symb", |_| {}).unwrap();
+        assert!(decoder.finish().is_err());
+        assert!(ArrayDecoder::default().feed::<Value, _>(b"{\"data\": []}", |_| {}).is_err());
+        assert!(ArrayDecoder::default().feed::<Value, _>(b"[1,,2]", |_| {}).is_err());
+
+        let mut empty = ArrayDecoder::default();
+        empty.feed::<Value, _>(b"[ ]", |_| panic!("no elements")).unwrap();
+        empty.finish().unwrap();
+    }
+}
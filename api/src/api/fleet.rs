@@ -0,0 +1,76 @@
+use crate::{error, SpaceTradersApi, types};
+
+impl SpaceTradersApi {
+    pub async fn list_ships(&self) -> error::Result<Vec<types::Ship>> {
+        self.get_all("my/ships").await
+    }
+
+    pub async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData> {
+        self.post("my/ships", &types::PurchaseShipRequest {
+            ship_type,
+            waypoint_symbol,
+        }).await
+    }
+
+    pub async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship> {
+        self.get_one(format!("my/ships/{}", ship_symbol).as_str()).await
+    }
+
+    pub async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.post_empty::<types::ShipNavData>(format!("my/ships/{}/orbit", ship_symbol).as_str()).await
+            .map(|data| data.nav)
+    }
+
+    pub async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.post_empty::<types::ShipNavData>(format!("my/ships/{}/dock", ship_symbol).as_str()).await
+            .map(|data| data.nav)
+    }
+
+    pub async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData> {
+        self.post(format!("my/ships/{}/navigate", ship_symbol).as_str(), &types::NavigateRequest {
+            waypoint_symbol,
+        }).await
+    }
+
+    pub async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData> {
+        self.post_empty(format!("my/ships/{}/refuel", ship_symbol).as_str()).await
+    }
+
+    /// Returns `None` when the ship has no active cooldown.
+    pub async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>> {
+        let path = format!("my/ships/{}/cooldown", ship_symbol);
+        self.send_optional::<(), types::Cooldown>(reqwest::Method::GET, &path, None, None).await
+            .map(|result| result.map(|result| result.data))
+    }
+
+    pub async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData> {
+        self.post_empty(format!("my/ships/{}/survey", ship_symbol).as_str()).await
+    }
+
+    pub async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> error::Result<types::ExtractData> {
+        self.post(format!("my/ships/{}/extract", ship_symbol).as_str(), &types::ExtractRequest {
+            survey,
+        }).await
+    }
+
+    pub async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo> {
+        self.post::<_, types::JettisonData>(format!("my/ships/{}/jettison", ship_symbol).as_str(), &types::JettisonRequest {
+            symbol,
+            units,
+        }).await.map(|data| data.cargo)
+    }
+
+    pub async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData> {
+        self.post(format!("my/ships/{}/sell", ship_symbol).as_str(), &types::SellCargoRequest {
+            symbol,
+            units,
+        }).await
+    }
+
+    pub async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData> {
+        self.post(format!("my/ships/{}/purchase", ship_symbol).as_str(), &types::PurchaseCargoRequest {
+            symbol,
+            units,
+        }).await
+    }
+}
@@ -1,17 +1,36 @@
+mod contract;
+mod dump;
+mod fleet;
 mod system;
 
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::{field, Instrument};
+use crate::drift::{SchemaDrift, SessionRecorder};
+use crate::events::Event;
 use crate::types;
-use crate::error::{DecodeError, Result, Error};
+use crate::error::{DecodeError, Result, Error, UnsavedRegistration};
+
+pub use dump::DownloadProgress;
 
 const BASE_URL: &str = "https://api.spacetraders.io/v2";
 const MAX_PAGE_LIMIT: u32 = 20;
+const RATE_LIMITED: u32 = 429;
+const MAX_RETRIES: u32 = 3;
 
 #[derive(Debug)]
 pub struct SpaceTradersApi {
     client: reqwest::Client,
     token: String,
+    session: Option<Arc<SessionRecorder>>,
+    drift: Option<Arc<SchemaDrift>>,
+    // where the rate limits `send` retries are reported, see `SpaceTradersClient::report_rate_limits`
+    rate_limits: Mutex<Option<broadcast::Sender<Event>>>,
 }
 
 impl SpaceTradersApi {
@@ -19,51 +38,142 @@ impl SpaceTradersApi {
         SpaceTradersApi {
             client: reqwest::Client::new(),
             token: String::from(auth_token),
+            session: None,
+            drift: None,
+            rate_limits: Mutex::new(None),
         }
     }
 
+    /// Writes every response to `session` as it arrives.
+    pub fn recording(mut self, session: Arc<SessionRecorder>) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Strict mode: checks every response for fields the types drop or lack,
+    /// tallying them in `drift`.
+    pub fn strict(mut self, drift: Arc<SchemaDrift>) -> Self {
+        self.drift = Some(drift);
+        self
+    }
+
+    /// Where the rate limits `send` waits out are reported, replacing any
+    /// channel given before.
+    pub(crate) fn report_rate_limits(&self, events: broadcast::Sender<Event>) {
+        *self.rate_limits.lock().unwrap() = Some(events);
+    }
+
     fn authorization(&self) -> String {
         format!("Bearer {}", self.token)
     }
 
-    async fn get<R: DeserializeOwned>(&self, path: &str) -> Result<types::ApiSuccess<R>> {
-        self.handle_response(
-            self.client.get(format!("{}/{}", BASE_URL, path))
-                .header(reqwest::header::AUTHORIZATION, &self.authorization())
-                .send()
-                .await?
-        ).await
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, format!("{}/{}", BASE_URL, path))
+            .header(reqwest::header::AUTHORIZATION, &self.authorization())
+    }
+
+    /// Sends one request, waiting out and retrying rate limit errors up to
+    /// `MAX_RETRIES` times. Each retry is reported to `rate_limits` as it
+    /// happens; a rate limit that outlasts the retries is returned as an error.
+    async fn send<B: Serialize + ?Sized, R: DeserializeOwned + Serialize>(&self, method: reqwest::Method, path: &str, page: Option<u32>, body: Option<&B>) -> Result<types::ApiSuccess<R>> {
+        self.send_optional(method, path, page, body).await?.ok_or_else(|| {
+            Error::DecodeError(DecodeError {
+                message: format!("{}: no content", path),
+                error: serde::de::Error::custom("expected a response body"),
+            })
+        })
+    }
+
+    /// Like `send`, but `None` for a 204, which some endpoints answer with
+    /// when there's nothing to return.
+    async fn send_optional<B: Serialize + ?Sized, R: DeserializeOwned + Serialize>(&self, method: reqwest::Method, path: &str, page: Option<u32>, body: Option<&B>) -> Result<Option<types::ApiSuccess<R>>> {
+        let span = tracing::info_span!(
+            "request",
+            %method,
+            path,
+            page,
+            status = field::Empty,
+            latency_ms = field::Empty,
+            retries = field::Empty,
+        );
+
+        async {
+            let started = Instant::now();
+            let mut retries = 0;
+            let result = loop {
+                let mut request = self.request(method.clone(), path);
+                if let Some(body) = body {
+                    request = request.json(body);
+                }
+                let sent_at = Instant::now();
+                let response = request.send().await?;
+                let status = response.status().as_u16();
+                tracing::Span::current().record("status", status);
+                crate::metrics::request(&method, path, status, sent_at.elapsed());
+                if response.status() == reqwest::StatusCode::NO_CONTENT {
+                    break Ok(None);
+                }
+
+                let result = self.handle_response(&method, path, response).await.map(Some);
+                // counted whether it's retried or the caller gets it
+                if matches!(&result, Err(Error::ApiError(e)) if e.code == RATE_LIMITED) {
+                    crate::metrics::rate_limited();
+                }
+
+                match result {
+                    Err(Error::ApiError(e)) if e.code == RATE_LIMITED && retries < MAX_RETRIES => {
+                        let retry_after = e.data.as_ref()
+                            .and_then(|data| data.get("retryAfter"))
+                            .and_then(|retry_after| retry_after.as_f64())
+                            .unwrap_or(1.0);
+                        tracing::warn!(retry_after, "rate limited");
+                        if let Some(events) = self.rate_limits.lock().unwrap().as_ref() {
+                            let _ = events.send(Event::RateLimited { retry_after: Some(retry_after) });
+                        }
+                        tokio::time::sleep(Duration::from_secs_f64(retry_after)).await;
+                        retries += 1;
+                    }
+                    result => break result,
+                }
+            };
+
+            let span = tracing::Span::current();
+            span.record("latency_ms", started.elapsed().as_millis() as u64);
+            span.record("retries", retries);
+            if let Err(e) = &result {
+                tracing::debug!(error = %e, "request failed");
+            }
+            result
+        }.instrument(span).await
     }
 
-    async fn get_one<R: DeserializeOwned>(&self, path: &str) -> Result<R> {
+    async fn get<R: DeserializeOwned + Serialize>(&self, path: &str) -> Result<types::ApiSuccess<R>> {
+        self.send::<(), R>(reqwest::Method::GET, path, None, None).await
+    }
+
+    async fn get_one<R: DeserializeOwned + Serialize>(&self, path: &str) -> Result<R> {
         self.get::<R>(path).await.map(|result| result.data)
     }
 
-    async fn get_limit<R: DeserializeOwned>(&self, path: &str, page: Option<u32>, limit: Option<usize>) -> Result<Vec<R>> {
-        let page = match page {
-            Some(p) => p,
-            None => 1
-        };
+    async fn get_page<R: DeserializeOwned + Serialize>(&self, path: &str, page: u32) -> Result<types::ApiSuccess<R>> {
+        let path = paginate_path(path, page, MAX_PAGE_LIMIT);
+        self.send::<(), R>(reqwest::Method::GET, &path, Some(page), None).await
+    }
 
-        let limit = match limit {
-            Some(l) => l,
-            None => usize::MAX
-        };
+    async fn get_limit<R: DeserializeOwned + Serialize>(&self, path: &str, page: Option<u32>, limit: Option<usize>) -> Result<Vec<R>> {
+        let page = page.unwrap_or(1);
+        let limit = limit.unwrap_or(usize::MAX);
 
-        let response = self.get::<Vec<R>>(
-            paginate_path(path, page, MAX_PAGE_LIMIT).as_str()
-        ).await?;
+        let response = self.get_page::<Vec<R>>(path, page).await?;
 
         let mut results = response.data;
         if let Some(meta) = response.meta {
             let mut page: u32 = 2;
             let pages: u32 = meta.total / meta.limit + 1;
             while page < pages && results.len() < limit {
-                let next = self.get_one::<Vec<R>>(
-                    paginate_path(path, page.into(), MAX_PAGE_LIMIT).as_str()
-                ).await?;
+                let next = self.get_page::<Vec<R>>(path, page).await?;
 
-                results.extend(next);
+                results.extend(next.data);
                 page += 1;
             }
         }
@@ -71,46 +181,79 @@ impl SpaceTradersApi {
         Ok(results)
     }
 
-    async fn get_all<R: DeserializeOwned>(&self, path: &str) -> Result<Vec<R>> {
+    async fn get_all<R: DeserializeOwned + Serialize>(&self, path: &str) -> Result<Vec<R>> {
         self.get_limit(path, None, None).await
     }
 
-    async fn post<T: Serialize + ?Sized, R: DeserializeOwned>(&self, path: &str, request: &T) -> Result<R> {
-        self.handle_response(
-            self.client.post(format!("{}/{}", BASE_URL, path))
-                .header(reqwest::header::AUTHORIZATION, &self.authorization())
-                .json(&request)
-                .send()
-                .await?
-        ).await.map(|response| response.data)
+    async fn post<T: Serialize + ?Sized, R: DeserializeOwned + Serialize>(&self, path: &str, request: &T) -> Result<R> {
+        self.send(reqwest::Method::POST, path, None, Some(request)).await.map(|response| response.data)
     }
 
-    async fn handle_response<T: DeserializeOwned>(&self, response: reqwest::Response) -> Result<types::ApiSuccess<T>> {
+    async fn post_empty<R: DeserializeOwned + Serialize>(&self, path: &str) -> Result<R> {
+        self.post(path, &serde_json::json!({})).await
+    }
+
+    async fn handle_response<T: DeserializeOwned + Serialize>(&self, method: &reqwest::Method, path: &str, response: reqwest::Response) -> Result<types::ApiSuccess<T>> {
+        let status = response.status().as_u16();
         let response_text = response.text().await.map_err(Error::from)?;
+        if let Some(session) = &self.session {
+            session.record(method.as_str(), path, status, &response_text)?;
+        }
 
         match serde_json::from_str::<types::ApiResponse<T>>(&response_text) {
             Ok(response) => {
                 match response.result {
-                    types::ApiResult::Success(v) => Ok(types::ApiSuccess {
-                        data: v,
-                        meta: response.meta,
-                    }),
+                    types::ApiResult::Success(v) => {
+                        if let Some(drift) = &self.drift {
+                            // parsed a second time, as the typed value has already dropped what drifted
+                            if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&response_text) {
+                                drift.check(method.as_str(), path, &raw["data"], &v);
+                            }
+                        }
+                        Ok(types::ApiSuccess {
+                            data: v,
+                            meta: response.meta,
+                        })
+                    }
                     types::ApiResult::Error(e) => Err(e.into())
                 }
             }
-            Err(e) => Err(Error::DecodeError(DecodeError { message: response_text, error: e }.into()))
+            Err(e) => Err(Error::DecodeError(DecodeError { message: response_text, error: e }))
         }
     }
 
+    /// Registers a new agent and saves its registration to `credentials_path()`.
     pub async fn register(call_sign: &str, faction: types::FactionSymbol) -> Result<SpaceTradersApi> {
-        let mut api = SpaceTradersApi::new("");
-        let registration_data: types::RegistrationData = api.post("register", &types::RegistrationRequest {
+        let (registration_data, _) = SpaceTradersApi::register_and_save(call_sign, faction).await?;
+        Ok(SpaceTradersApi::new(&registration_data.token))
+    }
+
+    /// Like `register`, but returns the starting agent, contract, faction and
+    /// ship too, and where the registration was saved: `fallback_credentials_path()`
+    /// if `credentials_path()` can't be written. If neither can, the
+    /// registration comes back in an `Error::UnsavedRegistration`, since it
+    /// holds the only copy of the token.
+    pub async fn register_and_save(call_sign: &str, faction: types::FactionSymbol) -> Result<(types::RegistrationData, PathBuf)> {
+        let registration_data = SpaceTradersApi::register_agent(call_sign, faction).await?;
+        let error = match crate::save_credentials(crate::credentials_path(), &registration_data) {
+            Ok(()) => return Ok((registration_data, crate::credentials_path())),
+            Err(e) => e,
+        };
+        match crate::save_credentials(crate::fallback_credentials_path(), &registration_data) {
+            Ok(()) => Ok((registration_data, crate::fallback_credentials_path())),
+            Err(_) => Err(Error::UnsavedRegistration(UnsavedRegistration {
+                registration_data: Box::new(registration_data),
+                error: Box::new(error),
+            })),
+        }
+    }
+
+    /// Registers a new agent, returning its token rather than a client for it.
+    pub async fn register_agent(call_sign: &str, faction: types::FactionSymbol) -> Result<types::RegistrationData> {
+        SpaceTradersApi::new("").post("register", &types::RegistrationRequest {
             symbol: String::from(call_sign),
             faction,
-        }).await?;
-
-        api.token = registration_data.token;
-        Ok(api)
+        }).await
     }
 
     pub async fn get_agent(&self) -> Result<types::Agent> {
@@ -132,6 +275,26 @@ fn paginate_path(path: &str, page: u32, limit: u32) -> String {
         limit = MAX_PAGE_LIMIT;
     }
 
-    format!("{}?page={}&limit={}", path, page, limit)
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!("{}{}page={}&limit={}", path, separator, page, limit)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::events::{channel, Event, EventClient};
+    use crate::SpaceTradersApi;
+
+    #[test]
+    fn test_retries_reach_the_event_client() {
+        let api = Arc::new(SpaceTradersApi::new(""));
+        let client = EventClient::new(api.clone(), channel());
+        let mut events = client.subscribe();
+
+        // as `send` does before waiting out a rate limit
+        let rate_limits = api.rate_limits.lock().unwrap().clone().unwrap();
+        rate_limits.send(Event::RateLimited { retry_after: Some(2.0) }).unwrap();
+        assert!(matches!(events.try_recv(), Ok(Event::RateLimited { retry_after: Some(_) })));
+    }
+}
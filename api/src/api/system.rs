@@ -14,6 +14,19 @@ impl SpaceTradersApi {
         self.get_limit(format!("systems/{}/waypoints", system_symbol).as_str(), page, limit).await
     }
 
+    /// The waypoints `query` asks for, which the server filters by trait and type.
+    pub async fn query_system_waypoints(&self, system_symbol: types::SystemSymbol, query: &types::WaypointQuery) -> error::Result<Vec<types::Waypoint>> {
+        let mut path = format!("systems/{}/waypoints", system_symbol);
+        let query_string = query.query_string();
+        if !query_string.is_empty() {
+            path = format!("{}?{}", path, query_string);
+        }
+
+        let mut waypoints: Vec<types::Waypoint> = self.get_all(&path).await?;
+        waypoints.retain(|waypoint| query.matches(waypoint));
+        Ok(waypoints)
+    }
+
     pub async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint> {
         self.get_one(format!("systems/{}/waypoints/{}", waypoint_symbol.system(), waypoint_symbol).as_str()).await
     }
@@ -0,0 +1,376 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::behaviors::{COOLDOWN_CONFLICT, is_asteroid_field, MarketCache, no_waypoint, ShipBehavior, ShipContext, ShipState, SURVEY_ERRORS, SurveyStore, SystemWaypoints, Transition};
+use crate::error::{Error, Result};
+use crate::{SpaceTradersClient, types};
+
+/// Runs procurement contracts back to back: accepts one, buys the goods from
+/// the cheapest known market or mines them, delivers them in as many trips as
+/// it takes, fulfills it and negotiates the next.
+///
+/// Gives up with `Error::BehaviorError` rather than accept or keep buying for a
+/// contract whose goods cost more than it pays. What's been spent on each
+/// contract is kept in the ship's record, so the check survives a restart.
+#[derive(Debug)]
+pub struct ContractBehavior {
+    markets: Arc<MarketCache>,
+    surveys: Arc<SurveyStore>,
+    waypoints: SystemWaypoints,
+    contract: Option<types::Contract>,
+}
+
+impl ContractBehavior {
+    pub fn new(markets: Arc<MarketCache>, surveys: Arc<SurveyStore>) -> Self {
+        ContractBehavior {
+            markets,
+            surveys,
+            waypoints: SystemWaypoints::default(),
+            contract: None,
+        }
+    }
+
+    /// The contract being worked on, accepting or negotiating one if needed.
+    async fn load_contract(&mut self, ctx: &ShipContext) -> Result<types::Contract> {
+        if let Some(contract) = self.contract.as_ref().filter(|contract| !contract.fulfilled) {
+            return Ok(contract.clone());
+        }
+
+        let now = ctx.now();
+        let open = ctx.api().list_contracts().await?.into_iter()
+            .filter(|contract| !contract.fulfilled && contract.expiration > now)
+            .filter(|contract| contract.contract_type == types::ContractType::Procurement)
+            .max_by_key(|contract| contract.accepted);
+
+        let contract = match open {
+            Some(contract) if contract.accepted => contract,
+            Some(contract) => self.accept(ctx, contract).await?,
+            None => {
+                let contract = self.negotiate(ctx).await?;
+                self.accept(ctx, contract).await?
+            }
+        };
+        self.contract = Some(contract.clone());
+        Ok(contract)
+    }
+
+    async fn negotiate(&self, ctx: &ShipContext) -> Result<types::Contract> {
+        let ship = ctx.ship().await?;
+        if ship.nav.status != types::ShipNavStatus::Docked {
+            ctx.dock().await?;
+        }
+
+        ctx.api().negotiate_contract(ctx.ship_symbol.clone()).await
+    }
+
+    async fn accept(&self, ctx: &ShipContext, contract: types::Contract) -> Result<types::Contract> {
+        let estimate: u64 = contract.terms.deliver.iter()
+            .filter_map(|good| {
+                self.markets.best_purchase_price(&good.trade_symbol).map(|(_, price)| price * good.units_remaining())
+            })
+            .sum();
+        if estimate > contract.total_payment() {
+            return Err(Error::BehaviorError(format!(
+                "contract {} would cost about {} for a payment of {}", contract.id, estimate, contract.total_payment()
+            )));
+        }
+
+        Ok(ctx.api().accept_contract(&contract.id).await?.contract)
+    }
+
+    async fn idle(&mut self, ctx: &ShipContext) -> Result<Transition> {
+        let ship = ctx.ship().await?;
+        if ship.nav.status == types::ShipNavStatus::InTransit {
+            return Ok(Transition::from_nav(&ship.nav));
+        }
+        self.waypoints.load(ctx, &ship.nav.system_symbol).await?;
+
+        let contract = self.load_contract(ctx).await?;
+        let Some(delivery) = contract.next_delivery() else {
+            return self.fulfill(ctx, &contract).await;
+        };
+
+        let held = ship.cargo.units_of(&delivery.trade_symbol);
+        if held >= delivery.units_remaining() || (held > 0 && ship.cargo.is_full()) {
+            if ship.nav.waypoint_symbol == delivery.destination_symbol {
+                return Ok(Transition::Next(ShipState::Delivering { waypoint: delivery.destination_symbol.clone() }));
+            }
+            return ctx.navigate(delivery.destination_symbol.clone()).await;
+        }
+
+        if ship.cargo.is_full() {
+            // nothing in the hold is for the contract, so make room for what is
+            for item in ship.cargo.inventory.iter() {
+                ctx.api().jettison_cargo(ctx.ship_symbol.clone(), item.symbol.clone(), item.units).await?;
+            }
+        }
+
+        if let Some((market, _)) = self.markets.best_purchase_price(&delivery.trade_symbol) {
+            if ship.nav.waypoint_symbol == market {
+                return Ok(Transition::Next(ShipState::Purchasing { waypoint: market }));
+            }
+            return ctx.navigate(market).await;
+        }
+
+        let field = self.waypoints.nearest(&ship, is_asteroid_field)
+            .map(|waypoint| waypoint.reference.symbol.clone())
+            .ok_or_else(|| no_waypoint("asteroid field", &ship))?;
+        if field != ship.nav.waypoint_symbol {
+            return ctx.navigate(field).await;
+        }
+        if ship.nav.status == types::ShipNavStatus::Docked {
+            ctx.orbit().await?;
+        }
+        Ok(Transition::Next(ShipState::Extracting { waypoint: field }))
+    }
+
+    async fn arrived(&mut self, ctx: &ShipContext, destination: &types::WaypointSymbol) -> Result<Transition> {
+        let ship = ctx.ship().await?;
+        self.waypoints.load(ctx, &ship.nav.system_symbol).await?;
+        let contract = self.load_contract(ctx).await?;
+
+        let delivering = contract.next_delivery()
+            .map(|delivery| delivery.destination_symbol == *destination && ship.cargo.units_of(&delivery.trade_symbol) > 0)
+            .unwrap_or(false);
+
+        let waypoint = destination.clone();
+        Ok(Transition::Next(if delivering {
+            ShipState::Delivering { waypoint }
+        } else if self.waypoints.get(destination).map(is_asteroid_field).unwrap_or(false) {
+            ShipState::Extracting { waypoint }
+        } else {
+            ShipState::Purchasing { waypoint }
+        }))
+    }
+
+    async fn purchase(&mut self, ctx: &ShipContext, waypoint: &types::WaypointSymbol) -> Result<Transition> {
+        let contract = self.load_contract(ctx).await?;
+        let Some(delivery) = contract.next_delivery() else {
+            return Ok(Transition::Next(ShipState::Idle));
+        };
+
+        let ship = ctx.ship().await?;
+        if ship.nav.status != types::ShipNavStatus::Docked {
+            ctx.dock().await?;
+        }
+
+        let market = ctx.api().get_market(waypoint.clone()).await?;
+        self.markets.update(market.clone(), ctx.now());
+
+        let Some(trade_good) = market.trade_goods().iter().find(|trade_good| trade_good.symbol == delivery.trade_symbol) else {
+            // the cache now knows better, so the next plan will look elsewhere
+            return Ok(Transition::Next(ShipState::Idle));
+        };
+
+        let wanted = delivery.units_remaining().saturating_sub(ship.cargo.units_of(&delivery.trade_symbol));
+        let mut remaining = wanted.min(ship.cargo.capacity - ship.cargo.units);
+        while remaining > 0 {
+            let units = remaining.min(trade_good.trade_volume.max(1));
+            if ctx.spent(&contract.id) + units * trade_good.purchase_price > contract.total_payment() {
+                return Err(Error::BehaviorError(format!(
+                    "buying {} more {} would take contract {} past its payment of {}",
+                    units, delivery.trade_symbol, contract.id, contract.total_payment()
+                )));
+            }
+
+            let bought = ctx.api().purchase_cargo(ctx.ship_symbol.clone(), delivery.trade_symbol.clone(), units).await?;
+            ctx.spend(&contract.id, bought.transaction.total_price)?;
+            ctx.transactions.record(bought.transaction);
+            remaining -= units;
+        }
+        Ok(Transition::Next(ShipState::Idle))
+    }
+
+    async fn extract(&mut self, ctx: &ShipContext, waypoint: &types::WaypointSymbol) -> Result<Transition> {
+        let contract = self.load_contract(ctx).await?;
+        let Some(delivery) = contract.next_delivery() else {
+            return Ok(Transition::Next(ShipState::Idle));
+        };
+
+        let extracting = ShipState::Extracting { waypoint: waypoint.clone() };
+        let wanted = &delivery.trade_symbol;
+        let survey = self.surveys.best(waypoint, ctx.now(), |good| u64::from(good == wanted))
+            .filter(|survey| survey.deposits.iter().any(|deposit| deposit.symbol == *wanted));
+
+        let data = match ctx.api().extract_resources(ctx.ship_symbol.clone(), survey.clone()).await {
+            Ok(data) => data,
+            Err(Error::ApiError(e)) if e.code == COOLDOWN_CONFLICT => {
+                return Ok(match ctx.api().get_ship_cooldown(ctx.ship_symbol.clone()).await? {
+                    Some(cooldown) => Transition::after_cooldown(extracting, &cooldown),
+                    None => Transition::Next(extracting),
+                });
+            }
+            Err(Error::ApiError(e)) if survey.is_some() && SURVEY_ERRORS.contains(&e.code) => {
+                self.surveys.remove(&survey.unwrap().signature);
+                return Ok(Transition::Next(extracting));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let extracted = data.extraction.extraction_yield;
+        let mut cargo = data.cargo;
+        if extracted.symbol != *wanted {
+            cargo = ctx.api().jettison_cargo(ctx.ship_symbol.clone(), extracted.symbol, extracted.units).await?;
+        }
+
+        if cargo.is_full() || cargo.units_of(wanted) >= delivery.units_remaining() {
+            return Ok(Transition::Next(ShipState::Idle));
+        }
+        Ok(Transition::after_cooldown(extracting, &data.cooldown))
+    }
+
+    async fn deliver(&mut self, ctx: &ShipContext) -> Result<Transition> {
+        let contract = self.load_contract(ctx).await?;
+        let Some(delivery) = contract.next_delivery() else {
+            return self.fulfill(ctx, &contract).await;
+        };
+
+        let ship = ctx.ship().await?;
+        if ship.nav.status != types::ShipNavStatus::Docked {
+            ctx.dock().await?;
+        }
+
+        let units = ship.cargo.units_of(&delivery.trade_symbol).min(delivery.units_remaining());
+        if units > 0 {
+            let data = ctx.api().deliver_contract(&contract.id, ctx.ship_symbol.clone(), delivery.trade_symbol.clone(), units).await?;
+            self.contract = Some(data.contract.clone());
+
+            if data.contract.is_delivered() {
+                return self.fulfill(ctx, &data.contract).await;
+            }
+        }
+        Ok(Transition::Next(ShipState::Idle))
+    }
+
+    async fn fulfill(&mut self, ctx: &ShipContext, contract: &types::Contract) -> Result<Transition> {
+        ctx.api().fulfill_contract(&contract.id).await?;
+
+        ctx.forget_spent(&contract.id)?;
+        self.contract = None;
+        Ok(Transition::Next(ShipState::Idle))
+    }
+}
+
+#[async_trait]
+impl ShipBehavior for ContractBehavior {
+    fn name(&self) -> &str {
+        "contract"
+    }
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> Result<Transition> {
+        match state {
+            ShipState::Navigating { destination, .. } => self.arrived(ctx, destination).await,
+            ShipState::Purchasing { waypoint } => self.purchase(ctx, waypoint).await,
+            ShipState::Extracting { waypoint } => self.extract(ctx, waypoint).await,
+            ShipState::Delivering { .. } => self.deliver(ctx).await,
+            ShipState::Idle | ShipState::Docked { .. } | ShipState::Selling { .. } | ShipState::Scouting { .. } => {
+                self.idle(ctx).await
+            }
+            ShipState::Finished => Ok(Transition::Done),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::behaviors::{ContractBehavior, MarketCache, ShipBehavior, ShipContext, ShipRecord, ShipState, StateStore, SurveyStore, TransactionLog, Transition};
+    use crate::error::Error;
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::SpaceTradersClient;
+    use crate::types::{ShipSymbol, WaypointSymbol};
+
+    const MARKET: &str = "X1-DF55-69207D";
+    const HEADQUARTERS: &str = "X1-DF55-20250Z";
+
+    /// A ship at headquarters, and a market a short hop away selling iron ore at 40.
+    async fn context(fake: FakeClient, store: Arc<StateStore>) -> (ShipContext, Arc<FakeClient>, Arc<MarketCache>) {
+        let fake = Arc::new(fake
+            .waypoint(HEADQUARTERS, "PLANET", &[])
+            .waypoint(MARKET, "MOON", &["MARKETPLACE"])
+            .market(MARKET, &[("IRON_ORE", 40)])
+            .ship("FAKE-1", HEADQUARTERS, "IN_ORBIT"));
+        let ship_symbol = ShipSymbol::new("FAKE-1").unwrap();
+        if store.get(ship_symbol.as_str()).is_none() {
+            store.save(&ship_symbol, ShipRecord::new("contract")).unwrap();
+        }
+
+        let markets = Arc::new(MarketCache::default());
+        markets.update(fake.get_market(WaypointSymbol::new(MARKET).unwrap()).await.unwrap(), fake.now());
+        let ctx = ShipContext::new(ship_symbol, fake.clone(), Scheduler::new(RateLimit::default()), Arc::new(TransactionLog::default()), store);
+        (ctx, fake, markets)
+    }
+
+    /// Steps until the contract is fulfilled or the behavior fails.
+    async fn run(behavior: &mut ContractBehavior, ctx: &ShipContext, fake: &FakeClient) -> Result<usize, Error> {
+        let mut state = ShipState::Idle;
+        for steps in 1..=50 {
+            state = match behavior.step(ctx, &state).await? {
+                Transition::Next(state) | Transition::Wait(state, _) => state,
+                Transition::Done => break,
+            };
+            if fake.contracts.lock().unwrap()[0].fulfilled {
+                return Ok(steps);
+            }
+        }
+        panic!("the contract was never fulfilled")
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_procure_deliver_fulfill() {
+        // 80 units takes two trips in a 60 unit hold
+        let fake = FakeClient::new().contract("contract-1", "IRON_ORE", 80, HEADQUARTERS, (1_000, 9_000));
+        let (ctx, fake, markets) = context(fake, Arc::new(StateStore::in_memory())).await;
+        let mut behavior = ContractBehavior::new(markets, Arc::new(SurveyStore::default()));
+
+        run(&mut behavior, &ctx, &fake).await.unwrap();
+
+        let contract = fake.contracts.lock().unwrap()[0].clone();
+        assert!(contract.accepted && contract.fulfilled);
+        assert_eq!(contract.next_delivery().map(|delivery| delivery.units_remaining()), None);
+        assert!(fake.ships.lock().unwrap()[0].cargo.is_empty());
+
+        let bought: Vec<u64> = ctx.transactions.recent(10).iter().map(|transaction| transaction.units).collect();
+        assert_eq!(bought, vec![60, 20]);
+        // paid off, so there's nothing left to hold against the cap
+        assert_eq!(ctx.spent("contract-1"), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spending_cap() {
+        // 40 units at 40 is 1,600, well past what the contract pays
+        let fake = FakeClient::new().contract("contract-1", "IRON_ORE", 40, HEADQUARTERS, (100, 900));
+        fake.contracts.lock().unwrap()[0].accepted = true;
+        let (ctx, fake, markets) = context(fake, Arc::new(StateStore::in_memory())).await;
+        let mut behavior = ContractBehavior::new(markets, Arc::new(SurveyStore::default()));
+
+        match run(&mut behavior, &ctx, &fake).await {
+            Err(Error::BehaviorError(message)) => assert!(message.contains("past its payment of 1000"), "{}", message),
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(fake.ships.lock().unwrap()[0].cargo.is_empty());
+        assert_eq!(ctx.spent("contract-1"), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spending_survives_restart() {
+        let path = std::env::temp_dir().join(format!("space-traders-contract-{}.json", std::process::id()));
+        let ship_symbol = ShipSymbol::new("FAKE-1").unwrap();
+        let mut record = ShipRecord::new("contract");
+        record.spent.insert(String::from("contract-1"), 800);
+        StateStore::open(&path).unwrap().save(&ship_symbol, record).unwrap();
+
+        // 10 units at 40 would fit the payment, but not on top of what was spent before the restart
+        let fake = FakeClient::new().contract("contract-1", "IRON_ORE", 10, HEADQUARTERS, (100, 900));
+        fake.contracts.lock().unwrap()[0].accepted = true;
+        let (ctx, fake, markets) = context(fake, Arc::new(StateStore::open(&path).unwrap())).await;
+        let mut behavior = ContractBehavior::new(markets, Arc::new(SurveyStore::default()));
+
+        let result = run(&mut behavior, &ctx, &fake).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::BehaviorError(_))), "{:?}", result);
+        assert_eq!(ctx.spent("contract-1"), 800);
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::AbortHandle;
+
+use crate::behaviors::{ShipBehavior, ShipState, StateStore};
+use crate::scheduler::Scheduler;
+use crate::types;
+
+const MAX_TRANSACTIONS: usize = 500;
+const EVENT_BUFFER: usize = 256;
+
+/// Something the fleet did, for whoever is watching.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RuntimeEvent {
+    #[serde(rename_all = "camelCase")]
+    StateChanged {
+        ship_symbol: types::ShipSymbol,
+        behavior: String,
+        state: ShipState,
+        resume_at: Option<DateTime<Utc>>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Paused { ship_symbol: types::ShipSymbol },
+    #[serde(rename_all = "camelCase")]
+    Resumed { ship_symbol: types::ShipSymbol },
+    #[serde(rename_all = "camelCase")]
+    Reassigned { ship_symbol: types::ShipSymbol, behavior: String },
+    #[serde(rename_all = "camelCase")]
+    Finished { ship_symbol: types::ShipSymbol, error: Option<String> },
+    Transaction(types::MarketTransaction),
+}
+
+/// The market trades the fleet made, newest last.
+#[derive(Debug)]
+pub struct TransactionLog {
+    transactions: Mutex<VecDeque<types::MarketTransaction>>,
+    events: broadcast::Sender<RuntimeEvent>,
+}
+
+impl Default for TransactionLog {
+    fn default() -> Self {
+        TransactionLog::new(broadcast::channel(EVENT_BUFFER).0)
+    }
+}
+
+impl TransactionLog {
+    /// A log that also announces each trade on `events`.
+    pub fn new(events: broadcast::Sender<RuntimeEvent>) -> Self {
+        TransactionLog {
+            transactions: Mutex::new(VecDeque::new()),
+            events,
+        }
+    }
+
+    pub fn record(&self, transaction: types::MarketTransaction) {
+        let mut transactions = self.transactions.lock().unwrap();
+        transactions.push_back(transaction.clone());
+        if transactions.len() > MAX_TRANSACTIONS {
+            transactions.pop_front();
+        }
+        // nobody listening is fine
+        let _ = self.events.send(RuntimeEvent::Transaction(transaction));
+    }
+
+    /// Up to `limit` of the latest trades, newest last.
+    pub fn recent(&self, limit: usize) -> Vec<types::MarketTransaction> {
+        let transactions = self.transactions.lock().unwrap();
+        transactions.iter().skip(transactions.len().saturating_sub(limit)).cloned().collect()
+    }
+}
+
+/// What a ship in the runtime is up to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipStatus {
+    pub ship_symbol: types::ShipSymbol,
+    pub behavior: String,
+    pub paused: bool,
+    pub state: Option<ShipState>,
+    pub resume_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShipHandle {
+    pub(crate) behavior: String,
+    pub(crate) paused: watch::Sender<bool>,
+    pub(crate) abort: AbortHandle,
+}
+
+pub(crate) type Reassignment = (types::ShipSymbol, Box<dyn ShipBehavior>);
+
+/// A handle on a running `BehaviorRuntime`, for steering its ships from
+/// elsewhere in the process, e.g. a control server.
+#[derive(Debug, Clone)]
+pub struct FleetControl {
+    pub(crate) ships: Arc<Mutex<HashMap<types::ShipSymbol, ShipHandle>>>,
+    pub(crate) reassignments: mpsc::UnboundedSender<Reassignment>,
+    pub(crate) events: broadcast::Sender<RuntimeEvent>,
+    pub(crate) store: Arc<StateStore>,
+    pub(crate) transactions: Arc<TransactionLog>,
+    pub(crate) scheduler: Scheduler,
+}
+
+impl FleetControl {
+    /// Every running ship, with its last saved state.
+    pub fn ships(&self) -> Vec<ShipStatus> {
+        let ships = self.ships.lock().unwrap();
+        let mut statuses: Vec<ShipStatus> = ships.iter()
+            .map(|(ship_symbol, handle)| {
+                let record = self.store.get(ship_symbol.as_str()).filter(|record| record.behavior == handle.behavior);
+                ShipStatus {
+                    ship_symbol: ship_symbol.clone(),
+                    behavior: handle.behavior.clone(),
+                    paused: *handle.paused.borrow(),
+                    state: record.as_ref().map(|record| record.state.clone()),
+                    resume_at: record.and_then(|record| record.resume_at),
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.ship_symbol.cmp(&b.ship_symbol));
+        statuses
+    }
+
+    pub fn ship(&self, ship_symbol: &str) -> Option<ShipStatus> {
+        self.ships().into_iter().find(|status| status.ship_symbol == ship_symbol)
+    }
+
+    /// Holds the ship before its next step. `false` if it isn't running.
+    pub fn pause(&self, ship_symbol: &str) -> bool {
+        self.set_paused(ship_symbol, true)
+    }
+
+    pub fn resume(&self, ship_symbol: &str) -> bool {
+        self.set_paused(ship_symbol, false)
+    }
+
+    fn set_paused(&self, ship_symbol: &str, paused: bool) -> bool {
+        let ships = self.ships.lock().unwrap();
+        let Some((ship_symbol, handle)) = ships.get_key_value(ship_symbol) else {
+            return false;
+        };
+        if handle.paused.send_replace(paused) != paused {
+            let ship_symbol = ship_symbol.clone();
+            let _ = self.events.send(match paused {
+                true => RuntimeEvent::Paused { ship_symbol },
+                false => RuntimeEvent::Resumed { ship_symbol },
+            });
+        }
+        true
+    }
+
+    /// Stops whatever the ship is doing and starts `behavior` on it instead.
+    /// Takes effect once the runtime is next waited on.
+    pub fn reassign(&self, ship_symbol: types::ShipSymbol, behavior: Box<dyn ShipBehavior>) {
+        // the runtime is only gone once every ship has finished, and then there's nothing to reassign
+        let _ = self.reassignments.send((ship_symbol, behavior));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RuntimeEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn transactions(&self) -> &Arc<TransactionLog> {
+        &self.transactions
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+}
+
+pub(crate) fn event_channel() -> broadcast::Sender<RuntimeEvent> {
+    broadcast::channel(EVENT_BUFFER).0
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+
+use crate::types;
+
+#[derive(Debug, Clone)]
+pub struct CachedMarket {
+    pub market: types::Market,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// The last market data each ship saw, shared between behaviors so one ship's
+/// visit informs everyone's trading decisions.
+#[derive(Debug, Default)]
+pub struct MarketCache {
+    markets: RwLock<HashMap<types::WaypointSymbol, CachedMarket>>,
+}
+
+impl MarketCache {
+    /// Keeps the last prices seen rather than a later look from afar, which
+    /// only lists the goods traded.
+    pub fn update(&self, market: types::Market, observed_at: DateTime<Utc>) {
+        let mut markets = self.markets.write().unwrap();
+        if !market.is_live() && markets.get(&market.symbol).is_some_and(|cached| cached.market.is_live()) {
+            return;
+        }
+        markets.insert(market.symbol.clone(), CachedMarket {
+            market,
+            observed_at,
+        });
+    }
+
+    pub fn get(&self, waypoint_symbol: &types::WaypointSymbol) -> Option<CachedMarket> {
+        self.markets.read().unwrap().get(waypoint_symbol).cloned()
+    }
+
+    pub fn all(&self) -> Vec<CachedMarket> {
+        self.markets.read().unwrap().values().cloned().collect()
+    }
+
+    /// What the market at the waypoint last paid per unit of `good`.
+    pub fn sell_price(&self, waypoint_symbol: &types::WaypointSymbol, good: &types::MarketGoodSymbol) -> Option<u64> {
+        self.get(waypoint_symbol)?.market.trade_goods().iter()
+            .find(|trade_good| trade_good.symbol == *good)
+            .map(|trade_good| trade_good.sell_price)
+    }
+
+    /// The highest price any known market pays for `good`.
+    pub fn best_sell_price(&self, good: &types::MarketGoodSymbol) -> Option<(types::WaypointSymbol, u64)> {
+        self.best_by(good, |trade_good| trade_good.sell_price, |a, b| a > b)
+    }
+
+    /// The lowest price any known market charges for `good`.
+    pub fn best_purchase_price(&self, good: &types::MarketGoodSymbol) -> Option<(types::WaypointSymbol, u64)> {
+        self.best_by(good, |trade_good| trade_good.purchase_price, |a, b| a < b)
+    }
+
+    fn best_by(&self, good: &types::MarketGoodSymbol, price: fn(&types::MarketTradeGood) -> u64, better: fn(u64, u64) -> bool) -> Option<(types::WaypointSymbol, u64)> {
+        let mut best: Option<(types::WaypointSymbol, u64)> = None;
+        for cached in self.markets.read().unwrap().values() {
+            for trade_good in cached.market.trade_goods().iter().filter(|trade_good| trade_good.symbol == *good) {
+                let p = price(trade_good);
+                if best.as_ref().map(|(_, b)| better(p, *b)).unwrap_or(true) {
+                    best = Some((cached.market.symbol.clone(), p));
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::behaviors::markets::MarketCache;
+    use crate::types;
+
+    #[test]
+    fn test_keeps_live_prices() {
+        let symbol = types::WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+        let mut market = json!({
+            "symbol": symbol,
+            "imports": [],
+            "exports": [],
+            "exchange": [{ "symbol": "FUEL", "name": "Fuel", "description": "" }],
+        });
+        let remote: types::Market = serde_json::from_value(market.clone()).unwrap();
+        assert!(!remote.is_live());
+        assert!(remote.trade_goods().is_empty());
+
+        market["transactions"] = json!([]);
+        market["tradeGoods"] = json!([{ "symbol": "FUEL", "tradeVolume": 100, "supply": "MODERATE", "purchasePrice": 72, "sellPrice": 68 }]);
+        let live: types::Market = serde_json::from_value(market).unwrap();
+        assert!(live.is_live());
+
+        let markets = MarketCache::default();
+        let now = chrono::Utc::now();
+        markets.update(live, now);
+        markets.update(remote, now);
+        assert_eq!(markets.sell_price(&symbol, &types::MarketGoodSymbol::Fuel), Some(68));
+    }
+}
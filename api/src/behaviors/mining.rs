@@ -0,0 +1,359 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::behaviors::{COOLDOWN_CONFLICT, is_asteroid_field, MarketCache, no_waypoint, ShipBehavior, ShipContext, ShipState, SURVEY_ERRORS, SurveyStore, SystemWaypoints, Transition};
+use crate::error::{Error, Result};
+use crate::{SpaceTradersClient, types};
+
+#[derive(Debug, Clone)]
+pub struct MiningConfig {
+    /// Yields that sell for less than this per unit at the best known market are jettisoned.
+    pub min_unit_value: u64,
+    /// Whether the ship should survey for itself when there's no stored survey to use.
+    /// Turned off after the first failure, since most mining ships can't.
+    pub survey: bool,
+}
+
+impl Default for MiningConfig {
+    fn default() -> Self {
+        MiningConfig {
+            min_unit_value: 20,
+            survey: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MiningStats {
+    pub started_at: DateTime<Utc>,
+    pub credits_earned: u64,
+    pub units_extracted: u64,
+    pub units_jettisoned: u64,
+    pub units_sold: u64,
+}
+
+impl MiningStats {
+    pub fn credits_per_hour(&self, now: DateTime<Utc>) -> f64 {
+        let hours = (now - self.started_at).num_seconds() as f64 / 3600.0;
+        if hours <= 0.0 {
+            return 0.0;
+        }
+        self.credits_earned as f64 / hours
+    }
+}
+
+/// Mines the nearest asteroid field until the hold is full, then sells at
+/// whichever market in the system pays best for the load, and repeats.
+#[derive(Debug)]
+pub struct MiningBehavior {
+    config: MiningConfig,
+    markets: Arc<MarketCache>,
+    surveys: Arc<SurveyStore>,
+    stats: Arc<Mutex<MiningStats>>,
+    started: bool,
+    waypoints: SystemWaypoints,
+}
+
+impl MiningBehavior {
+    pub fn new(config: MiningConfig, markets: Arc<MarketCache>, surveys: Arc<SurveyStore>) -> Self {
+        MiningBehavior {
+            config,
+            markets,
+            surveys,
+            stats: Arc::new(Mutex::new(MiningStats {
+                started_at: Utc::now(),
+                credits_earned: 0,
+                units_extracted: 0,
+                units_jettisoned: 0,
+                units_sold: 0,
+            })),
+            started: false,
+            waypoints: SystemWaypoints::default(),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<Mutex<MiningStats>> {
+        self.stats.clone()
+    }
+
+    fn is_field(&self, waypoint_symbol: &types::WaypointSymbol) -> bool {
+        self.waypoints.get(waypoint_symbol).map(is_asteroid_field).unwrap_or(false)
+    }
+
+    /// The market with the best known revenue for the hold after travel, or
+    /// the nearest one if no market's prices are known yet.
+    fn best_market(&self, ship: &types::Ship) -> Option<types::WaypointSymbol> {
+        let here = &ship.nav.route.destination;
+        let revenues: Vec<(u64, &types::Waypoint)> = self.waypoints.iter()
+            .filter(|waypoint| waypoint.is_market())
+            .map(|waypoint| {
+                let revenue = ship.cargo.inventory.iter()
+                    .map(|item| item.units * self.markets.sell_price(&waypoint.reference.symbol, &item.symbol).unwrap_or(0))
+                    .sum();
+                (revenue, waypoint)
+            })
+            .collect();
+
+        if revenues.iter().all(|(revenue, _)| *revenue == 0) {
+            return self.waypoints.nearest(ship, types::Waypoint::is_market).map(|waypoint| waypoint.reference.symbol.clone());
+        }
+        revenues.into_iter()
+            .max_by(|(a, a_waypoint), (b, b_waypoint)| {
+                let a = *a as f64 - a_waypoint.reference.distance_to(here.x, here.y);
+                let b = *b as f64 - b_waypoint.reference.distance_to(here.x, here.y);
+                a.total_cmp(&b)
+            })
+            .map(|(_, waypoint)| waypoint.reference.symbol.clone())
+    }
+
+    fn unit_value(&self, good: &types::MarketGoodSymbol) -> Option<u64> {
+        self.markets.best_sell_price(good).map(|(_, price)| price)
+    }
+
+    async fn idle(&mut self, ctx: &ShipContext) -> Result<Transition> {
+        let ship = ctx.ship().await?;
+        if ship.nav.status == types::ShipNavStatus::InTransit {
+            return Ok(Transition::from_nav(&ship.nav));
+        }
+        self.waypoints.load(ctx, &ship.nav.system_symbol).await?;
+
+        if ship.cargo.is_full() {
+            let market = self.best_market(&ship).ok_or_else(|| no_waypoint("market", &ship))?;
+            if market == ship.nav.waypoint_symbol {
+                return Ok(Transition::Next(ShipState::Selling { waypoint: market }));
+            }
+            return ctx.navigate(market).await;
+        }
+
+        let field = self.waypoints.nearest(&ship, is_asteroid_field)
+            .map(|waypoint| waypoint.reference.symbol.clone())
+            .ok_or_else(|| no_waypoint("asteroid field", &ship))?;
+        if field != ship.nav.waypoint_symbol {
+            return ctx.navigate(field).await;
+        }
+        if ship.nav.status == types::ShipNavStatus::Docked {
+            ctx.orbit().await?;
+        }
+        Ok(Transition::Next(ShipState::Extracting { waypoint: field }))
+    }
+
+    async fn extract(&mut self, ctx: &ShipContext, waypoint: &types::WaypointSymbol) -> Result<Transition> {
+        let extracting = ShipState::Extracting { waypoint: waypoint.clone() };
+        let survey = self.surveys.best(waypoint, ctx.now(), |good| self.unit_value(good).unwrap_or(0));
+
+        if survey.is_none() && self.config.survey {
+            match ctx.api().create_survey(ctx.ship_symbol.clone()).await {
+                Ok(data) => {
+                    self.surveys.add(data.surveys);
+                    return Ok(Transition::after_cooldown(extracting, &data.cooldown));
+                }
+                // a cooldown will hold up extracting too, and that's handled below
+                Err(Error::ApiError(e)) if e.code == COOLDOWN_CONFLICT => {}
+                Err(Error::ApiError(_)) => self.config.survey = false,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let data = match ctx.api().extract_resources(ctx.ship_symbol.clone(), survey.clone()).await {
+            Ok(data) => data,
+            Err(Error::ApiError(e)) if e.code == COOLDOWN_CONFLICT => {
+                return Ok(match ctx.api().get_ship_cooldown(ctx.ship_symbol.clone()).await? {
+                    Some(cooldown) => Transition::after_cooldown(extracting, &cooldown),
+                    None => Transition::Next(extracting),
+                });
+            }
+            Err(Error::ApiError(e)) if survey.is_some() && SURVEY_ERRORS.contains(&e.code) => {
+                self.surveys.remove(&survey.unwrap().signature);
+                return Ok(Transition::Next(extracting));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let extracted = data.extraction.extraction_yield;
+        self.stats.lock().unwrap().units_extracted += extracted.units;
+
+        let mut cargo = data.cargo;
+        if self.unit_value(&extracted.symbol).map(|value| value < self.config.min_unit_value).unwrap_or(false) {
+            cargo = ctx.api().jettison_cargo(ctx.ship_symbol.clone(), extracted.symbol, extracted.units).await?;
+            self.stats.lock().unwrap().units_jettisoned += extracted.units;
+        }
+
+        if cargo.is_full() {
+            // navigating isn't held up by the extraction cooldown
+            return Ok(Transition::Next(ShipState::Idle));
+        }
+        Ok(Transition::after_cooldown(extracting, &data.cooldown))
+    }
+
+    async fn sell(&mut self, ctx: &ShipContext, waypoint: &types::WaypointSymbol) -> Result<Transition> {
+        let ship = ctx.ship().await?;
+        if ship.nav.status != types::ShipNavStatus::Docked {
+            ctx.dock().await?;
+        }
+
+        let market = ctx.api().get_market(waypoint.clone()).await?;
+        self.markets.update(market.clone(), ctx.now());
+
+        for item in ship.cargo.inventory.iter() {
+            let trade_good = market.trade_goods().iter().find(|trade_good| trade_good.symbol == item.symbol);
+            let Some(trade_good) = trade_good else {
+                // nothing else is known to buy it either, so it's only taking up space
+                if self.markets.best_sell_price(&item.symbol).is_none() {
+                    ctx.api().jettison_cargo(ctx.ship_symbol.clone(), item.symbol.clone(), item.units).await?;
+                    self.stats.lock().unwrap().units_jettisoned += item.units;
+                }
+                continue;
+            };
+
+            let mut remaining = item.units;
+            while remaining > 0 {
+                let units = remaining.min(trade_good.trade_volume.max(1));
+                let sold = ctx.api().sell_cargo(ctx.ship_symbol.clone(), item.symbol.clone(), units).await?;
+
+                let mut stats = self.stats.lock().unwrap();
+                stats.credits_earned += sold.transaction.total_price;
+                stats.units_sold += units;
+                remaining -= units;
+                ctx.transactions.record(sold.transaction);
+            }
+        }
+
+        if ship.fuel.current < ship.fuel.capacity && market.trade_goods().iter().any(|trade_good| trade_good.symbol == "FUEL") {
+            ctx.api().refuel_ship(ctx.ship_symbol.clone()).await?;
+        }
+        Ok(Transition::Next(ShipState::Idle))
+    }
+}
+
+#[async_trait]
+impl ShipBehavior for MiningBehavior {
+    fn name(&self) -> &str {
+        "mining"
+    }
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> Result<Transition> {
+        if !self.started {
+            // the game's clock, not ours, is what the hourly rate is measured in
+            self.stats.lock().unwrap().started_at = ctx.now();
+            self.started = true;
+        }
+
+        match state {
+            ShipState::Idle | ShipState::Docked { .. } | ShipState::Purchasing { .. } | ShipState::Delivering { .. } | ShipState::Scouting { .. } => {
+                self.idle(ctx).await
+            }
+            ShipState::Navigating { destination, .. } => {
+                if self.waypoints.is_empty() {
+                    // resumed mid-flight, so nothing is loaded yet
+                    let ship = ctx.ship().await?;
+                    self.waypoints.load(ctx, &ship.nav.system_symbol).await?;
+                }
+
+                if self.is_field(destination) {
+                    Ok(Transition::Next(ShipState::Extracting { waypoint: destination.clone() }))
+                } else {
+                    Ok(Transition::Next(ShipState::Selling { waypoint: destination.clone() }))
+                }
+            }
+            ShipState::Extracting { waypoint } => self.extract(ctx, waypoint).await,
+            ShipState::Selling { waypoint } => self.sell(ctx, waypoint).await,
+            ShipState::Finished => Ok(Transition::Done),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::behaviors::{MarketCache, MiningBehavior, MiningConfig, ShipBehavior, ShipContext, ShipState, StateStore, SurveyStore, TransactionLog, Transition};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::types::{ShipSymbol, WaypointSymbol};
+
+    const FIELD: &str = "X1-DF55-17335A";
+    const MARKET: &str = "X1-DF55-20250Z";
+
+    fn context(fake: FakeClient) -> (ShipContext, Arc<FakeClient>) {
+        let fake = Arc::new(fake
+            .waypoint(FIELD, "ASTEROID_FIELD", &[])
+            .waypoint(MARKET, "PLANET", &["MARKETPLACE"])
+            .market(MARKET, &[("IRON_ORE", 40), ("FUEL", 70)])
+            .ship("FAKE-1", FIELD, "IN_ORBIT")
+            .extracts("IRON_ORE", 30));
+        let ctx = ShipContext::new(
+            ShipSymbol::new("FAKE-1").unwrap(),
+            fake.clone(),
+            Scheduler::new(RateLimit::default()),
+            Arc::new(TransactionLog::default()),
+            Arc::new(StateStore::in_memory()),
+        );
+        (ctx, fake)
+    }
+
+    fn config() -> MiningConfig {
+        MiningConfig {
+            min_unit_value: 20,
+            survey: false,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_mine_sell_refuel() {
+        let (ctx, fake) = context(FakeClient::new());
+        fake.ships.lock().unwrap()[0].fuel.current = 900;
+        let mut behavior = MiningBehavior::new(config(), Arc::new(MarketCache::default()), Arc::new(SurveyStore::default()));
+        let field = WaypointSymbol::new(FIELD).unwrap();
+        let market = WaypointSymbol::new(MARKET).unwrap();
+
+        let extracting = ShipState::Extracting { waypoint: field.clone() };
+        assert_eq!(behavior.step(&ctx, &ShipState::Idle).await.unwrap(), Transition::Next(extracting.clone()));
+        assert!(matches!(behavior.step(&ctx, &extracting).await.unwrap(), Transition::Wait(ShipState::Extracting { .. }, _)));
+
+        // still cooling down from the last extraction, so it waits that out rather than failing
+        fake.fail("extract_resources", 4000);
+        assert!(matches!(behavior.step(&ctx, &extracting).await.unwrap(), Transition::Wait(ShipState::Extracting { .. }, _)));
+        assert_eq!(behavior.stats().lock().unwrap().units_extracted, 30);
+
+        assert_eq!(behavior.step(&ctx, &extracting).await.unwrap(), Transition::Next(ShipState::Idle));
+        let navigating = match behavior.step(&ctx, &ShipState::Idle).await.unwrap() {
+            Transition::Wait(state @ ShipState::Navigating { .. }, _) => state,
+            t => panic!("unexpected transition: {:?}", t)
+        };
+        let selling = ShipState::Selling { waypoint: market.clone() };
+        assert_eq!(behavior.step(&ctx, &navigating).await.unwrap(), Transition::Next(selling.clone()));
+        assert_eq!(behavior.step(&ctx, &selling).await.unwrap(), Transition::Next(ShipState::Idle));
+
+        let ship = fake.ships.lock().unwrap()[0].clone();
+        assert!(ship.cargo.is_empty());
+        assert_eq!(ship.fuel.current, ship.fuel.capacity);
+        let stats = behavior.stats().lock().unwrap().clone();
+        assert_eq!((stats.units_extracted, stats.units_sold, stats.credits_earned), (60, 60, 60 * 36));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spent_surveys() {
+        let (ctx, fake) = context(FakeClient::new());
+        let surveys = Arc::new(SurveyStore::default());
+        let mut behavior = MiningBehavior::new(config(), Arc::new(MarketCache::default()), surveys.clone());
+        let extracting = ShipState::Extracting { waypoint: WaypointSymbol::new(FIELD).unwrap() };
+
+        // exhausted, expired or otherwise unusable surveys are dropped and the ship tries again
+        for code in 4220..=4224 {
+            surveys.add(vec![serde_json::from_value(json!({
+                "signature": format!("SURVEY-{}", code),
+                "symbol": FIELD,
+                "deposits": [{ "symbol": "IRON_ORE" }],
+                "expiration": "2100-01-01T00:00:00Z",
+                "size": "MODERATE",
+            })).unwrap()]);
+            fake.fail("extract_resources", code);
+
+            assert_eq!(behavior.step(&ctx, &extracting).await.unwrap(), Transition::Next(extracting.clone()));
+            assert_eq!(surveys.len(), 0);
+        }
+        assert_eq!(behavior.stats().lock().unwrap().units_extracted, 0);
+    }
+}
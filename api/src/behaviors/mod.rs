@@ -0,0 +1,303 @@
+mod contracts;
+mod control;
+mod markets;
+mod mining;
+mod price_log;
+mod runtime;
+mod scouting;
+mod shipyards;
+mod store;
+mod surveys;
+mod waypoints;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error, SpaceTradersClient, types};
+use crate::scheduler::{Priority, ScheduledClient, Scheduler};
+
+pub use contracts::*;
+pub use control::{FleetControl, RuntimeEvent, ShipStatus, TransactionLog};
+pub use markets::*;
+pub use mining::*;
+pub use price_log::*;
+pub use runtime::*;
+pub use scouting::*;
+pub use shipyards::*;
+pub use store::*;
+pub use surveys::*;
+pub use waypoints::*;
+
+pub(crate) const COOLDOWN_CONFLICT: u32 = 4000;
+// verification, expiration, waypoint type, orbit and exhaustion errors
+pub(crate) const SURVEY_ERRORS: std::ops::RangeInclusive<u32> = 4220..=4224;
+
+/// Where a ship is in its automation cycle. Persisted between runs, so every
+/// variant carries enough to pick up again after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ShipState {
+    Idle,
+    Navigating {
+        destination: types::WaypointSymbol,
+        arrival: DateTime<Utc>,
+    },
+    Docked {
+        waypoint: types::WaypointSymbol,
+    },
+    Extracting {
+        waypoint: types::WaypointSymbol,
+    },
+    Selling {
+        waypoint: types::WaypointSymbol,
+    },
+    Purchasing {
+        waypoint: types::WaypointSymbol,
+    },
+    Delivering {
+        waypoint: types::WaypointSymbol,
+    },
+    Scouting {
+        waypoint: types::WaypointSymbol,
+    },
+    /// The behavior is done. The record is kept for what it spent.
+    Finished,
+}
+
+impl ShipState {
+    pub fn from_nav(nav: &types::ShipNav) -> Self {
+        match nav.status {
+            types::ShipNavStatus::InTransit => ShipState::Navigating {
+                destination: nav.route.destination.symbol.clone(),
+                arrival: nav.route.arrival,
+            },
+            types::ShipNavStatus::Docked => ShipState::Docked {
+                waypoint: nav.waypoint_symbol.clone(),
+            },
+            // a status this client doesn't know yet: the step re-reads the ship before acting
+            types::ShipNavStatus::InOrbit | types::ShipNavStatus::Unknown(_) => ShipState::Idle,
+        }
+    }
+}
+
+/// What a behavior wants the runtime to do after a step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    /// Move to the state and step again straight away.
+    Next(ShipState),
+    /// Move to the state, but don't step again until the given time.
+    Wait(ShipState, DateTime<Utc>),
+    /// The behavior has finished with this ship.
+    Done,
+}
+
+impl Transition {
+    /// Follows a nav response: in transit ships wait out their arrival time.
+    pub fn from_nav(nav: &types::ShipNav) -> Self {
+        match ShipState::from_nav(nav) {
+            ShipState::Navigating { destination, arrival } => Transition::Wait(
+                ShipState::Navigating { destination, arrival },
+                arrival,
+            ),
+            state => Transition::Next(state),
+        }
+    }
+
+    pub fn after_cooldown(state: ShipState, cooldown: &types::Cooldown) -> Self {
+        match cooldown.expiration {
+            Some(expiration) if cooldown.remaining_seconds > 0 => Transition::Wait(state, expiration),
+            _ => Transition::Next(state),
+        }
+    }
+}
+
+/// Everything a behavior gets to work with for a single ship.
+#[derive(Debug, Clone)]
+pub struct ShipContext {
+    pub ship_symbol: types::ShipSymbol,
+    api: ScheduledClient,
+    /// Where trades made for this ship are recorded.
+    pub transactions: Arc<TransactionLog>,
+    store: Arc<StateStore>,
+}
+
+impl ShipContext {
+    pub fn new(ship_symbol: types::ShipSymbol, api: Arc<dyn SpaceTradersClient>, scheduler: Scheduler, transactions: Arc<TransactionLog>, store: Arc<StateStore>) -> Self {
+        ShipContext {
+            api: ScheduledClient::new(api, scheduler, ship_symbol.as_str()),
+            ship_symbol,
+            transactions,
+            store,
+        }
+    }
+
+    /// The game, with every request waiting for this ship's turn.
+    pub fn api(&self) -> &ScheduledClient {
+        &self.api
+    }
+
+    /// The game's time, which is what every wait should be measured against.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.api.now()
+    }
+
+    /// Everything this ship's behavior has spent so far, across restarts.
+    pub fn spending(&self) -> BTreeMap<String, u64> {
+        self.store.get(self.ship_symbol.as_str()).map(|record| record.spent).unwrap_or_default()
+    }
+
+    /// What this ship's behavior has spent on `what` so far.
+    pub fn spent(&self, what: &str) -> u64 {
+        self.spending().get(what).copied().unwrap_or(0)
+    }
+
+    /// Records spending on `what` in the ship's stored record, returning the total so far.
+    pub fn spend(&self, what: &str, credits: u64) -> error::Result<u64> {
+        self.store.spend(self.ship_symbol.as_str(), what, credits)
+    }
+
+    pub fn forget_spent(&self, what: &str) -> error::Result<()> {
+        self.store.forget_spent(self.ship_symbol.as_str(), what)
+    }
+
+    pub async fn ship(&self) -> error::Result<types::Ship> {
+        self.api.get_ship(self.ship_symbol.clone()).await
+    }
+
+    /// Re-reads the ship and derives its state, for when the stored state can't be trusted.
+    pub async fn sync(&self) -> error::Result<Transition> {
+        Ok(Transition::from_nav(&self.ship().await?.nav))
+    }
+
+    /// Sets off for the waypoint. A ship that's already there arrives straight
+    /// away, as `Navigating` with its last arrival time, so behaviors handle it
+    /// the same way as the end of a flight.
+    pub async fn navigate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<Transition> {
+        let ship = self.ship().await?;
+        if ship.nav.waypoint_symbol == waypoint_symbol && ship.nav.status != types::ShipNavStatus::InTransit {
+            return Ok(Transition::Next(ShipState::Navigating {
+                destination: waypoint_symbol,
+                arrival: ship.nav.route.arrival,
+            }));
+        }
+
+        if ship.nav.status == types::ShipNavStatus::Docked {
+            self.api.at(Priority::Critical).orbit_ship(self.ship_symbol.clone()).await?;
+        }
+        let data = self.api.navigate_ship(self.ship_symbol.clone(), waypoint_symbol).await?;
+        Ok(Transition::from_nav(&data.nav))
+    }
+
+    pub async fn dock(&self) -> error::Result<Transition> {
+        let nav = self.api.dock_ship(self.ship_symbol.clone()).await?;
+        Ok(Transition::from_nav(&nav))
+    }
+
+    pub async fn orbit(&self) -> error::Result<Transition> {
+        let nav = self.api.orbit_ship(self.ship_symbol.clone()).await?;
+        Ok(Transition::from_nav(&nav))
+    }
+}
+
+/// A bot for one ship. The runtime calls `step` with the current state and
+/// applies the returned transition, persisting it before stepping again.
+#[async_trait]
+pub trait ShipBehavior: Send {
+    fn name(&self) -> &str;
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> error::Result<Transition>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::behaviors::{ShipContext, ShipRecord, ShipState, StateStore, TransactionLog, Transition};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::types::{ShipNav, ShipSymbol, WaypointSymbol};
+
+    fn nav(status: &str) -> ShipNav {
+        let waypoint = |symbol: &str| json!({
+            "symbol": symbol,
+            "type": "PLANET",
+            "systemSymbol": "X1-DF55",
+            "x": 0,
+            "y": 0,
+        });
+
+        serde_json::from_value(json!({
+            "systemSymbol": "X1-DF55",
+            "waypointSymbol": "X1-DF55-20250Z",
+            "route": {
+                "destination": waypoint("X1-DF55-20250Z"),
+                "departure": waypoint("X1-DF55-69207D"),
+                "departureTime": "2023-07-01T00:00:00Z",
+                "arrival": "2023-07-01T00:01:30Z",
+            },
+            "status": status,
+            "flightMode": "CRUISE",
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_transition_from_nav() {
+        let waypoint = WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+
+        match Transition::from_nav(&nav("IN_TRANSIT")) {
+            Transition::Wait(ShipState::Navigating { destination, arrival }, until) => {
+                assert_eq!(destination, waypoint);
+                assert_eq!(arrival, until);
+                assert_eq!(until.to_rfc3339(), "2023-07-01T00:01:30+00:00");
+            }
+            t => panic!("unexpected transition: {:?}", t)
+        }
+
+        assert_eq!(Transition::from_nav(&nav("DOCKED")), Transition::Next(ShipState::Docked { waypoint }));
+        assert_eq!(Transition::from_nav(&nav("IN_ORBIT")), Transition::Next(ShipState::Idle));
+    }
+
+    #[tokio::test]
+    async fn test_navigate_in_place() {
+        let ctx = ShipContext::new(
+            ShipSymbol::new("FAKE-1").unwrap(),
+            Arc::new(FakeClient::new().ship("FAKE-1", "X1-DF55-20250Z", "DOCKED")),
+            Scheduler::new(RateLimit::default()),
+            Arc::new(TransactionLog::default()),
+            Arc::new(StateStore::in_memory()),
+        );
+
+        // already there, so it arrives without a flight rather than going back to idle
+        let waypoint = WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+        match ctx.navigate(waypoint.clone()).await.unwrap() {
+            Transition::Next(ShipState::Navigating { destination, .. }) => assert_eq!(destination, waypoint),
+            t => panic!("unexpected transition: {:?}", t)
+        }
+    }
+
+    #[test]
+    fn test_store_resumes() {
+        let path = std::env::temp_dir().join(format!("space-traders-state-{}.json", std::process::id()));
+        let record = ShipRecord {
+            behavior: String::from("shuttle"),
+            state: ShipState::Navigating {
+                destination: WaypointSymbol::new("X1-DF55-20250Z").unwrap(),
+                arrival: "2023-07-01T00:01:30Z".parse().unwrap(),
+            },
+            resume_at: Some("2023-07-01T00:01:30Z".parse().unwrap()),
+            spent: BTreeMap::from([(String::from("contract-1"), 3_200)]),
+        };
+
+        StateStore::open(&path).unwrap().save(&ShipSymbol::new("BEAR-1").unwrap(), record.clone()).unwrap();
+        let reopened = StateStore::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.get("BEAR-1"), Some(record));
+        assert_eq!(reopened.get("BEAR-2"), None);
+    }
+}
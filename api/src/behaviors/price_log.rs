@@ -0,0 +1,90 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DecodeError, Result};
+use crate::types;
+
+/// One observed price, as written to the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PriceRecord {
+    #[serde(rename_all = "camelCase")]
+    Market {
+        observed_at: DateTime<Utc>,
+        waypoint_symbol: types::WaypointSymbol,
+        symbol: types::MarketGoodSymbol,
+        supply: types::SupplyType,
+        purchase_price: u64,
+        sell_price: u64,
+        trade_volume: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Ship {
+        observed_at: DateTime<Utc>,
+        waypoint_symbol: types::WaypointSymbol,
+        ship_type: types::ShipType,
+        purchase_price: u64,
+    },
+}
+
+/// An append-only file of every price the fleet has seen, one JSON record per line.
+#[derive(Debug)]
+pub struct PriceLog {
+    file: Mutex<File>,
+}
+
+impl PriceLog {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(PriceLog {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<PriceRecord>> {
+        let mut records = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            records.push(serde_json::from_str(&line).map_err(|e| DecodeError { message: line, error: e })?);
+        }
+        Ok(records)
+    }
+
+    pub fn record_market(&self, market: &types::Market, observed_at: DateTime<Utc>) -> Result<()> {
+        self.write(market.trade_goods().iter().map(|trade_good| PriceRecord::Market {
+            observed_at,
+            waypoint_symbol: market.symbol.clone(),
+            symbol: trade_good.symbol.clone(),
+            supply: trade_good.supply.clone(),
+            purchase_price: trade_good.purchase_price,
+            sell_price: trade_good.sell_price,
+            trade_volume: trade_good.trade_volume,
+        }))
+    }
+
+    pub fn record_shipyard(&self, shipyard: &types::Shipyard, observed_at: DateTime<Utc>) -> Result<()> {
+        self.write(shipyard.ships().iter().map(|ship| PriceRecord::Ship {
+            observed_at,
+            waypoint_symbol: shipyard.symbol.clone(),
+            ship_type: ship.ship_type.clone(),
+            purchase_price: ship.purchase_price,
+        }))
+    }
+
+    fn write<I: Iterator<Item = PriceRecord>>(&self, records: I) -> Result<()> {
+        let mut lines = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut lines, &record).unwrap();
+            lines.push(b'\n');
+        }
+
+        // one write per observation, so concurrent scouts don't interleave lines
+        self.file.lock().unwrap().write_all(&lines)?;
+        Ok(())
+    }
+}
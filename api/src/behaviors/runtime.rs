@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinSet;
+
+use crate::behaviors::control::{event_channel, Reassignment, ShipHandle};
+use crate::behaviors::{FleetControl, RuntimeEvent, ShipBehavior, ShipContext, ShipRecord, ShipState, StateStore, TransactionLog, Transition};
+use crate::{error, SpaceTradersClient, types};
+use crate::scheduler::Scheduler;
+
+/// Drives one task per ship, each stepping its behavior until it's done.
+#[derive(Debug)]
+pub struct BehaviorRuntime {
+    api: Arc<dyn SpaceTradersClient>,
+    scheduler: Scheduler,
+    store: Arc<StateStore>,
+    tasks: JoinSet<(types::ShipSymbol, error::Result<()>)>,
+    ships: Arc<Mutex<HashMap<types::ShipSymbol, ShipHandle>>>,
+    events: broadcast::Sender<RuntimeEvent>,
+    transactions: Arc<TransactionLog>,
+    reassignments: (mpsc::UnboundedSender<Reassignment>, mpsc::UnboundedReceiver<Reassignment>),
+}
+
+impl BehaviorRuntime {
+    /// Every ship's requests go through `scheduler`, which may be shared with other callers.
+    pub fn new(api: Arc<dyn SpaceTradersClient>, scheduler: Scheduler, store: StateStore) -> Self {
+        let events = event_channel();
+        BehaviorRuntime {
+            api,
+            scheduler,
+            store: Arc::new(store),
+            tasks: JoinSet::new(),
+            ships: Arc::new(Mutex::new(HashMap::new())),
+            transactions: Arc::new(TransactionLog::new(events.clone())),
+            events,
+            reassignments: mpsc::unbounded_channel(),
+        }
+    }
+
+    /// A handle for pausing, resuming and reassigning ships while the runtime runs.
+    pub fn control(&self) -> FleetControl {
+        FleetControl {
+            ships: self.ships.clone(),
+            reassignments: self.reassignments.0.clone(),
+            events: self.events.clone(),
+            store: self.store.clone(),
+            transactions: self.transactions.clone(),
+            scheduler: self.scheduler.clone(),
+        }
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    pub fn store(&self) -> &Arc<StateStore> {
+        &self.store
+    }
+
+    /// Starts `behavior` on the ship, resuming from the stored state if the
+    /// ship was last running a behavior with the same name that hadn't
+    /// finished. What the ship has spent carries over either way. Whatever
+    /// the ship was already running here is stopped.
+    pub fn spawn(&mut self, ship_symbol: types::ShipSymbol, behavior: Box<dyn ShipBehavior>) {
+        let ctx = ShipContext::new(ship_symbol.clone(), self.api.clone(), self.scheduler.clone(), self.transactions.clone(), self.store.clone());
+        let store = self.store.clone();
+        let events = self.events.clone();
+        let name = behavior.name().to_string();
+        let (paused, paused_rx) = watch::channel(false);
+
+        let task_symbol = ship_symbol.clone();
+        let abort = self.tasks.spawn(async move {
+            let result = drive(ctx, behavior, store, paused_rx, events).await;
+            (task_symbol, result)
+        });
+
+        let replaced = self.ships.lock().unwrap().insert(ship_symbol, ShipHandle { behavior: name, paused, abort });
+        if let Some(replaced) = replaced {
+            replaced.abort.abort();
+        }
+    }
+
+    /// Starts a behavior on each of the agent's ships that `behavior` gives
+    /// one for. It's passed the ship and the behavior the ship was last
+    /// running, if that hadn't finished. Returns the ships started.
+    pub async fn spawn_fleet<F>(&mut self, behavior: F) -> error::Result<Vec<types::ShipSymbol>>
+    where
+        F: Fn(&types::Ship, Option<&str>) -> Option<Box<dyn ShipBehavior>>,
+    {
+        let mut spawned = Vec::new();
+        for ship in self.api.list_ships().await? {
+            let stored = self.store.get(ship.symbol.as_str())
+                .filter(|record| record.state != ShipState::Finished)
+                .map(|record| record.behavior);
+            if let Some(behavior) = behavior(&ship, stored.as_deref()) {
+                self.spawn(ship.symbol.clone(), behavior);
+                spawned.push(ship.symbol);
+            }
+        }
+        Ok(spawned)
+    }
+
+    /// Waits for a ship to be reassigned through `control`, and starts it.
+    /// For carrying on once `join_next` has nothing left to run.
+    pub async fn next_reassignment(&mut self) {
+        // the runtime holds a sender itself, so the channel never closes
+        if let Some((ship_symbol, behavior)) = self.reassignments.1.recv().await {
+            self.reassign(ship_symbol, behavior);
+        }
+    }
+
+    /// Waits for the next ship to finish, returning `None` once none are
+    /// running. Ships reassigned through `control` are started meanwhile.
+    pub async fn join_next(&mut self) -> Option<(types::ShipSymbol, error::Result<()>)> {
+        loop {
+            while let Ok((ship_symbol, behavior)) = self.reassignments.1.try_recv() {
+                self.reassign(ship_symbol, behavior);
+            }
+            if self.tasks.is_empty() {
+                return None;
+            }
+
+            tokio::select! {
+                joined = self.tasks.join_next() => match joined? {
+                    Ok((ship_symbol, result)) => {
+                        let mut ships = self.ships.lock().unwrap();
+                        // unless the ship was reassigned just as it finished
+                        if ships.get(&ship_symbol).map(|handle| handle.abort.is_finished()).unwrap_or(false) {
+                            ships.remove(&ship_symbol);
+                        }
+                        drop(ships);
+                        let _ = self.events.send(RuntimeEvent::Finished {
+                            ship_symbol: ship_symbol.clone(),
+                            error: result.as_ref().err().map(|e| e.to_string()),
+                        });
+                        return Some((ship_symbol, result));
+                    }
+                    // a ship that was reassigned
+                    Err(e) if e.is_cancelled() => continue,
+                    Err(e) => std::panic::resume_unwind(e.into_panic()),
+                },
+                Some((ship_symbol, behavior)) = self.reassignments.1.recv() => {
+                    self.reassign(ship_symbol, behavior);
+                }
+            }
+        }
+    }
+
+    fn reassign(&mut self, ship_symbol: types::ShipSymbol, behavior: Box<dyn ShipBehavior>) {
+        let _ = self.events.send(RuntimeEvent::Reassigned {
+            ship_symbol: ship_symbol.clone(),
+            behavior: behavior.name().to_string(),
+        });
+        self.spawn(ship_symbol, behavior);
+    }
+
+    pub async fn run(mut self) -> Vec<(types::ShipSymbol, error::Result<()>)> {
+        let mut finished = Vec::new();
+        while let Some(result) = self.join_next().await {
+            finished.push(result);
+        }
+        finished
+    }
+}
+
+async fn drive(ctx: ShipContext, mut behavior: Box<dyn ShipBehavior>, store: Arc<StateStore>, mut paused: watch::Receiver<bool>, events: broadcast::Sender<RuntimeEvent>) -> error::Result<()> {
+    let mut record = match store.get(ctx.ship_symbol.as_str()) {
+        Some(record) if record.behavior == behavior.name() && record.state != ShipState::Finished => record,
+        // starting over, but what was spent still counts against the caps
+        stored => ShipRecord {
+            spent: stored.map(|stored| stored.spent).unwrap_or_default(),
+            ..ShipRecord::new(behavior.name())
+        },
+    };
+    // saved straight away, so there's a record for spending to go against
+    store.save(&ctx.ship_symbol, record.clone())?;
+
+    loop {
+        if let Some(resume_at) = record.resume_at {
+            sleep_until(&ctx, resume_at).await;
+        }
+        // the sender lives as long as the runtime knows about this ship
+        while *paused.borrow_and_update() {
+            if paused.changed().await.is_err() {
+                break;
+            }
+        }
+
+        let (state, resume_at) = match behavior.step(&ctx, &record.state).await? {
+            Transition::Next(state) => (state, None),
+            Transition::Wait(state, until) => (state, Some(until)),
+            Transition::Done => (ShipState::Finished, None),
+        };
+        // the step may have spent credits, which went straight to the store
+        let spent = store.get(ctx.ship_symbol.as_str()).map(|stored| stored.spent).unwrap_or_default();
+        record = ShipRecord { state, resume_at, spent, ..record };
+        store.save(&ctx.ship_symbol, record.clone())?;
+        let _ = events.send(RuntimeEvent::StateChanged {
+            ship_symbol: ctx.ship_symbol.clone(),
+            behavior: record.behavior.clone(),
+            state: record.state.clone(),
+            resume_at: record.resume_at,
+        });
+        if record.state == ShipState::Finished {
+            return Ok(());
+        }
+    }
+}
+
+async fn sleep_until(ctx: &ShipContext, time: DateTime<Utc>) {
+    // a time in the past fails the conversion, and there's nothing to wait for
+    if let Ok(duration) = (time - ctx.now()).to_std() {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use chrono::Duration;
+
+    use crate::behaviors::{BehaviorRuntime, ShipBehavior, ShipContext, ShipRecord, ShipState, StateStore, Transition};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::{error, types};
+
+    /// Waits where it is for an hour at a time.
+    struct Parked;
+
+    #[async_trait]
+    impl ShipBehavior for Parked {
+        fn name(&self) -> &str {
+            "parked"
+        }
+
+        async fn step(&mut self, ctx: &ShipContext, _state: &ShipState) -> error::Result<Transition> {
+            Ok(Transition::Wait(ShipState::Idle, ctx.now() + Duration::hours(1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spawn_fleet() {
+        let fake = FakeClient::new()
+            .ship("FAKE-1", "X1-DF55-20250Z", "DOCKED")
+            .ship("FAKE-2", "X1-DF55-20250Z", "DOCKED")
+            .ship("FAKE-3", "X1-DF55-20250Z", "DOCKED");
+        let store = StateStore::in_memory();
+        store.save(&types::ShipSymbol::new("FAKE-2").unwrap(), ShipRecord::new("parked")).unwrap();
+        let mut runtime = BehaviorRuntime::new(Arc::new(fake), Scheduler::new(RateLimit::default()), store);
+
+        // FAKE-1 picked out, FAKE-2 by what it was running, and FAKE-3 left alone
+        let spawned = runtime.spawn_fleet(|ship, stored| match (ship.symbol.as_str(), stored) {
+            ("FAKE-1", None) | (_, Some("parked")) => Some(Box::new(Parked) as Box<dyn ShipBehavior>),
+            _ => None,
+        }).await.unwrap();
+
+        assert_eq!(spawned.iter().map(|ship| ship.as_str()).collect::<Vec<_>>(), vec!["FAKE-1", "FAKE-2"]);
+        let running: Vec<String> = runtime.control().ships().iter().map(|status| status.ship_symbol.to_string()).collect();
+        assert_eq!(running, vec!["FAKE-1", "FAKE-2"]);
+    }
+}
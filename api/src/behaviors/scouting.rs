@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Duration;
+
+use crate::behaviors::{MarketCache, PriceLog, ShipBehavior, ShipContext, ShipState, Transition};
+use crate::error::Result;
+use crate::scheduler::Priority;
+use crate::{SpaceTradersClient, types};
+
+// a shipyard's listings are worth about as much as a well stocked market
+const SHIPYARD_VALUE: f64 = 10.0;
+
+/// A waypoint worth keeping a probe at.
+#[derive(Debug, Clone)]
+pub struct ScoutCandidate {
+    pub waypoint_symbol: types::WaypointSymbol,
+    pub market: bool,
+    pub shipyard: bool,
+    /// How much fresh data here is worth relative to the other candidates.
+    pub value: f64,
+}
+
+impl ScoutCandidate {
+    /// Markets are valued by how many goods they're known to trade, and
+    /// shipyards by a fixed amount. `None` if the waypoint is neither.
+    pub fn from_waypoint(waypoint: &types::Waypoint, markets: &MarketCache) -> Option<Self> {
+        let market = waypoint.is_market();
+        let shipyard = waypoint.is_shipyard();
+        if !market && !shipyard {
+            return None;
+        }
+
+        let mut value = 0.0;
+        if market {
+            value += markets.get(&waypoint.reference.symbol)
+                .map(|cached| cached.market.trade_goods().len().max(1) as f64)
+                .unwrap_or(1.0);
+        }
+        if shipyard {
+            value += SHIPYARD_VALUE;
+        }
+
+        Some(ScoutCandidate {
+            waypoint_symbol: waypoint.reference.symbol.clone(),
+            market,
+            shipyard,
+            value,
+        })
+    }
+
+    fn calls_per_refresh(&self) -> u32 {
+        self.market as u32 + self.shipyard as u32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoutBudget {
+    pub credits: u64,
+    pub probe_price: u64,
+    pub probes_owned: usize,
+    /// API calls per hour the scouts may spend between them.
+    pub calls_per_hour: f64,
+    /// The longest any post should go between refreshes.
+    pub max_staleness: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoutPost {
+    pub waypoint_symbol: types::WaypointSymbol,
+    pub market: bool,
+    pub shipyard: bool,
+    pub refresh_interval: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoutPlan {
+    /// Where to station probes, most valuable first.
+    pub posts: Vec<ScoutPost>,
+    pub probes_to_buy: usize,
+    pub calls_per_hour: f64,
+}
+
+impl ScoutPlan {
+    /// Pairs probes with posts; extra probes or posts are left out.
+    pub fn assign(&self, probes: &[types::ShipSymbol]) -> Vec<(types::ShipSymbol, ScoutPost)> {
+        probes.iter().cloned().zip(self.posts.iter().cloned()).collect()
+    }
+}
+
+/// Stations one probe at each of the most valuable candidates the budget
+/// affords. Posts refresh as often as `max_staleness` needs while the call
+/// budget allows it; past that, the calls are shared out by value, so the
+/// less valuable posts go longer between refreshes instead of going unwatched.
+pub fn plan_scouts(mut candidates: Vec<ScoutCandidate>, budget: &ScoutBudget) -> ScoutPlan {
+    candidates.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap_or(Ordering::Equal));
+
+    let affordable = match budget.probe_price {
+        0 => candidates.len(),
+        price => budget.probes_owned + (budget.credits / price) as usize,
+    };
+    candidates.truncate(affordable);
+
+    // each post's share of the calls is weighted by its value, and the most
+    // valuable are the first to reach `max_staleness`, leaving the rest more
+    let max_refreshes_per_hour = 3600.0 / budget.max_staleness.num_seconds().max(1) as f64;
+    let weight = |candidate: &ScoutCandidate| candidate.calls_per_refresh() as f64 * candidate.value.max(f64::MIN_POSITIVE);
+    let mut calls_left = budget.calls_per_hour.max(0.0);
+    let mut weight_left: f64 = candidates.iter().map(weight).sum();
+    let mut calls_per_hour = 0.0;
+
+    let posts = candidates.into_iter()
+        .map(|candidate| {
+            let calls = candidate.calls_per_refresh() as f64;
+            let share = calls_left * weight(&candidate) / weight_left;
+            let refreshes_per_hour = (share / calls).min(max_refreshes_per_hour);
+            calls_left -= refreshes_per_hour * calls;
+            weight_left -= weight(&candidate);
+            calls_per_hour += refreshes_per_hour * calls;
+
+            ScoutPost {
+                waypoint_symbol: candidate.waypoint_symbol,
+                market: candidate.market,
+                shipyard: candidate.shipyard,
+                refresh_interval: Duration::seconds((3600.0 / refreshes_per_hour).round().min(i32::MAX as f64) as i64),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    ScoutPlan {
+        probes_to_buy: posts.len().saturating_sub(budget.probes_owned),
+        calls_per_hour,
+        posts,
+    }
+}
+
+/// Parks a probe at its post and refreshes the market and shipyard there on
+/// a schedule, feeding the market cache and the price log.
+#[derive(Debug)]
+pub struct ScoutBehavior {
+    post: ScoutPost,
+    markets: Arc<MarketCache>,
+    log: Arc<PriceLog>,
+}
+
+impl ScoutBehavior {
+    pub fn new(post: ScoutPost, markets: Arc<MarketCache>, log: Arc<PriceLog>) -> Self {
+        ScoutBehavior {
+            post,
+            markets,
+            log,
+        }
+    }
+
+    async fn refresh(&self, ctx: &ShipContext) -> Result<Transition> {
+        let waypoint_symbol = &self.post.waypoint_symbol;
+
+        if self.post.market {
+            let market = ctx.api().at(Priority::Background).get_market(waypoint_symbol.clone()).await?;
+            let observed_at = ctx.now();
+            self.log.record_market(&market, observed_at)?;
+            self.markets.update(market, observed_at);
+        }
+
+        if self.post.shipyard {
+            let shipyard = ctx.api().at(Priority::Background).get_shipyard(waypoint_symbol.clone()).await?;
+            self.log.record_shipyard(&shipyard, ctx.now())?;
+        }
+
+        Ok(Transition::Wait(
+            ShipState::Scouting { waypoint: waypoint_symbol.clone() },
+            ctx.now() + self.post.refresh_interval,
+        ))
+    }
+}
+
+#[async_trait]
+impl ShipBehavior for ScoutBehavior {
+    fn name(&self) -> &str {
+        "scout"
+    }
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> Result<Transition> {
+        match state {
+            ShipState::Scouting { waypoint } if *waypoint == self.post.waypoint_symbol => self.refresh(ctx).await,
+            ShipState::Navigating { destination, .. } if *destination == self.post.waypoint_symbol => {
+                Ok(Transition::Next(ShipState::Scouting { waypoint: destination.clone() }))
+            }
+            _ => {
+                let ship = ctx.ship().await?;
+                if ship.nav.status == types::ShipNavStatus::InTransit {
+                    return Ok(Transition::from_nav(&ship.nav));
+                }
+                if ship.nav.waypoint_symbol == self.post.waypoint_symbol {
+                    return Ok(Transition::Next(ShipState::Scouting { waypoint: ship.nav.waypoint_symbol }));
+                }
+                ctx.navigate(self.post.waypoint_symbol.clone()).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::Duration;
+    use crate::behaviors::{plan_scouts, MarketCache, PriceLog, ScoutBehavior, ScoutBudget, ScoutCandidate, ScoutPost, ShipBehavior, ShipContext, ShipState, StateStore, TransactionLog, Transition};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::types::{MarketGoodSymbol, ShipSymbol, WaypointSymbol};
+
+    fn candidate(waypoint: &str, market: bool, shipyard: bool, value: f64) -> ScoutCandidate {
+        ScoutCandidate {
+            waypoint_symbol: WaypointSymbol::new(waypoint).unwrap(),
+            market,
+            shipyard,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_plan_scouts() {
+        let candidates = vec![
+            candidate("X1-DF55-00001A", true, false, 3.0),
+            candidate("X1-DF55-00002B", true, true, 20.0),
+            candidate("X1-DF55-00003C", true, false, 12.0),
+            candidate("X1-DF55-00004D", true, false, 1.0),
+        ];
+
+        // four probes are affordable, but only four calls every ten minutes fit the call budget,
+        // so the posts past that are refreshed less often
+        let plan = plan_scouts(candidates, &ScoutBudget {
+            credits: 100_000,
+            probe_price: 30_000,
+            probes_owned: 1,
+            calls_per_hour: 24.0,
+            max_staleness: Duration::minutes(10),
+        });
+
+        let posts: Vec<(String, Duration)> = plan.posts.iter().map(|post| (post.waypoint_symbol.to_string(), post.refresh_interval)).collect();
+        assert_eq!(posts, vec![
+            (String::from("X1-DF55-00002B"), Duration::minutes(10)),
+            (String::from("X1-DF55-00003C"), Duration::minutes(10)),
+            // what's left of the budget goes to the last two by value
+            (String::from("X1-DF55-00001A"), Duration::seconds(800)),
+            (String::from("X1-DF55-00004D"), Duration::minutes(40)),
+        ]);
+        assert_eq!(plan.probes_to_buy, 3);
+        assert!((plan.calls_per_hour - 24.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_scout_refresh() {
+        let waypoint = WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+        let fake = FakeClient::new().market("X1-DF55-20250Z", &[("IRON_ORE", 40), ("FUEL", 70)]);
+        let ctx = ShipContext::new(
+            ShipSymbol::new("FAKE-1").unwrap(),
+            Arc::new(fake),
+            Scheduler::new(RateLimit::default()),
+            Arc::new(TransactionLog::default()),
+            Arc::new(StateStore::in_memory()),
+        );
+
+        let path = std::env::temp_dir().join(format!("space-traders-scout-{}.jsonl", std::process::id()));
+        let markets = Arc::new(MarketCache::default());
+        let post = ScoutPost {
+            waypoint_symbol: waypoint.clone(),
+            market: true,
+            shipyard: false,
+            refresh_interval: Duration::minutes(10),
+        };
+        let mut behavior = ScoutBehavior::new(post, markets.clone(), Arc::new(PriceLog::open(&path).unwrap()));
+        let transition = behavior.step(&ctx, &ShipState::Scouting { waypoint: waypoint.clone() }).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(transition, Transition::Wait(ShipState::Scouting { .. }, _)));
+        assert_eq!(markets.sell_price(&waypoint, &MarketGoodSymbol::IronOre), Some(36));
+        assert_eq!(markets.best_purchase_price(&MarketGoodSymbol::Fuel), Some((waypoint, 70)));
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::behaviors::{PriceRecord, ShipBehavior, ShipContext, ShipState, Transition};
+use crate::error::Result;
+use crate::scheduler::Priority;
+use crate::{SpaceTradersClient, types};
+
+/// A ship type for sale at a shipyard, at the price last seen there.
+#[derive(Debug, Clone)]
+pub struct ShipOffer {
+    pub waypoint_symbol: types::WaypointSymbol,
+    pub ship_type: types::ShipType,
+    pub price: u64,
+    pub observed_at: DateTime<Utc>,
+    /// The full listing, when the offer came from a shipyard a ship was at.
+    pub ship: Option<types::ShipyardShip>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShipRecommendation {
+    pub offer: ShipOffer,
+    /// How long the ship takes to earn back its price.
+    pub payback: Duration,
+    /// Now if it's affordable, otherwise when fleet income should have covered it.
+    pub buy_at: DateTime<Utc>,
+}
+
+/// Ranks ship purchases by how quickly they pay for themselves.
+#[derive(Debug, Default)]
+pub struct ShipyardAdvisor {
+    offers: HashMap<(types::WaypointSymbol, types::ShipType), ShipOffer>,
+    income: HashMap<types::ShipType, f64>,
+}
+
+impl ShipyardAdvisor {
+    /// `income` is the expected credits per hour for each ship type worth buying.
+    pub fn new(income: HashMap<types::ShipType, f64>) -> Self {
+        ShipyardAdvisor {
+            offers: HashMap::new(),
+            income,
+        }
+    }
+
+    pub fn set_income(&mut self, ship_type: types::ShipType, credits_per_hour: f64) {
+        self.income.insert(ship_type, credits_per_hour);
+    }
+
+    /// Takes the listings and recent sales of a shipyard one of our ships is at.
+    pub fn add_shipyard(&mut self, shipyard: &types::Shipyard, observed_at: DateTime<Utc>) {
+        for transaction in shipyard.transactions() {
+            // the server puts the type of ship that was sold in `shipSymbol`
+            let Ok(ship_type) = serde_json::from_value(serde_json::Value::from(transaction.ship_symbol.as_str())) else {
+                continue;
+            };
+            self.add(ShipOffer {
+                waypoint_symbol: transaction.waypoint_symbol.clone(),
+                ship_type,
+                price: transaction.price,
+                observed_at: transaction.timestamp,
+                ship: None,
+            });
+        }
+
+        for ship in shipyard.ships() {
+            self.add(ShipOffer {
+                waypoint_symbol: shipyard.symbol.clone(),
+                ship_type: ship.ship_type.clone(),
+                price: ship.purchase_price,
+                observed_at,
+                ship: Some(ship.clone()),
+            });
+        }
+    }
+
+    /// Takes the ship prices scouts have logged.
+    pub fn add_price_log<'a, I: IntoIterator<Item = &'a PriceRecord>>(&mut self, records: I) {
+        for record in records {
+            if let PriceRecord::Ship { observed_at, waypoint_symbol, ship_type, purchase_price } = record {
+                self.add(ShipOffer {
+                    waypoint_symbol: waypoint_symbol.clone(),
+                    ship_type: ship_type.clone(),
+                    price: *purchase_price,
+                    observed_at: *observed_at,
+                    ship: None,
+                });
+            }
+        }
+    }
+
+    /// Keeps the newest offer per shipyard and ship type.
+    pub fn add(&mut self, offer: ShipOffer) {
+        let key = (offer.waypoint_symbol.clone(), offer.ship_type.clone());
+        match self.offers.get(&key) {
+            Some(existing) if existing.observed_at > offer.observed_at => {}
+            _ => {
+                self.offers.insert(key, offer);
+            }
+        }
+    }
+
+    /// The cheapest offer of each type we expect income from, fastest payback first.
+    /// Purchases can't dip into `reserve`, and the rest of the fleet earning
+    /// `income_per_hour` decides when an unaffordable ship will be affordable.
+    pub fn recommend(&self, credits: u64, reserve: u64, income_per_hour: f64, now: DateTime<Utc>) -> Vec<ShipRecommendation> {
+        let mut cheapest: HashMap<types::ShipType, &ShipOffer> = HashMap::new();
+        for offer in self.offers.values() {
+            let entry = cheapest.entry(offer.ship_type.clone()).or_insert(offer);
+            if offer.price < entry.price {
+                *entry = offer;
+            }
+        }
+
+        let mut recommendations: Vec<ShipRecommendation> = cheapest.into_values()
+            .filter_map(|offer| {
+                let income = self.income.get(&offer.ship_type).copied().filter(|income| *income > 0.0)?;
+                let shortfall = (offer.price + reserve).saturating_sub(credits);
+                let buy_at = if shortfall == 0 {
+                    now
+                } else if income_per_hour > 0.0 {
+                    now + hours(shortfall as f64 / income_per_hour)
+                } else {
+                    return None;
+                };
+
+                Some(ShipRecommendation {
+                    offer: offer.clone(),
+                    payback: hours(offer.price as f64 / income),
+                    buy_at,
+                })
+            })
+            .collect();
+
+        recommendations.sort_by_key(|recommendation| recommendation.payback);
+        recommendations
+    }
+}
+
+fn hours(hours: f64) -> Duration {
+    Duration::seconds((hours * 3600.0).ceil() as i64)
+}
+
+#[derive(Debug, Clone)]
+pub struct SpendingCaps {
+    /// The most to pay for any one ship.
+    pub max_price: u64,
+    /// The most to spend on ships in total.
+    pub max_total: u64,
+    /// Credits that must be left over after every purchase.
+    pub reserve: u64,
+    pub max_ships: usize,
+}
+
+// each ship bought is kept in the ship's spending under this prefix and its symbol
+const SHIP_PURCHASE: &str = "ship:";
+
+/// Takes a ship to whichever shipyard has the advisor's top pick and buys it,
+/// as long as the purchase stays within the spending caps. Purchases are kept
+/// in the buying ship's record, so the caps count ships bought before a restart.
+#[derive(Debug)]
+pub struct AutoBuyBehavior {
+    advisor: Arc<Mutex<ShipyardAdvisor>>,
+    caps: SpendingCaps,
+    income_per_hour: Arc<Mutex<f64>>,
+    check_interval: Duration,
+    purchases: Arc<Mutex<Vec<types::Ship>>>,
+}
+
+impl AutoBuyBehavior {
+    /// `income_per_hour` is the fleet's current earnings, kept up to date by the caller.
+    pub fn new(advisor: Arc<Mutex<ShipyardAdvisor>>, caps: SpendingCaps, income_per_hour: Arc<Mutex<f64>>) -> Self {
+        AutoBuyBehavior {
+            advisor,
+            caps,
+            income_per_hour,
+            check_interval: Duration::minutes(10),
+            purchases: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Ships bought since the behavior started, for the caller to put to work.
+    pub fn purchases(&self) -> Arc<Mutex<Vec<types::Ship>>> {
+        self.purchases.clone()
+    }
+
+    /// How many ships have been bought and what they cost in total.
+    fn bought(&self, ctx: &ShipContext) -> (usize, u64) {
+        ctx.spending().into_iter()
+            .filter(|(what, _)| what.starts_with(SHIP_PURCHASE))
+            .fold((0, 0), |(ships, spent), (_, price)| (ships + 1, spent + price))
+    }
+
+    fn within_caps(&self, ctx: &ShipContext, price: u64, credits: u64) -> bool {
+        let (ships, spent) = self.bought(ctx);
+        price <= self.caps.max_price
+            && spent + price <= self.caps.max_total
+            && credits >= price + self.caps.reserve
+            && ships < self.caps.max_ships
+    }
+
+    async fn plan(&self, ctx: &ShipContext) -> Result<Transition> {
+        let (ships, spent) = self.bought(ctx);
+        if ships >= self.caps.max_ships || spent >= self.caps.max_total {
+            return Ok(Transition::Done);
+        }
+
+        let credits = ctx.api().at(Priority::Background).get_agent().await?.credits;
+        let now = ctx.now();
+        let recheck = now + self.check_interval;
+
+        let income_per_hour = *self.income_per_hour.lock().unwrap();
+        let recommendations = self.advisor.lock().unwrap().recommend(credits, self.caps.reserve, income_per_hour, now);
+        let Some(recommendation) = recommendations.into_iter()
+            .find(|recommendation| recommendation.offer.price <= self.caps.max_price && spent + recommendation.offer.price <= self.caps.max_total) else {
+            return Ok(Transition::Wait(ShipState::Idle, recheck));
+        };
+
+        if recommendation.buy_at > now {
+            return Ok(Transition::Wait(ShipState::Idle, recommendation.buy_at.min(recheck)));
+        }
+
+        let waypoint_symbol = recommendation.offer.waypoint_symbol;
+        let ship = ctx.ship().await?;
+        if ship.nav.waypoint_symbol == waypoint_symbol && ship.nav.status != types::ShipNavStatus::InTransit {
+            return Ok(Transition::Next(ShipState::Purchasing { waypoint: waypoint_symbol }));
+        }
+        ctx.navigate(waypoint_symbol).await
+    }
+
+    async fn purchase(&mut self, ctx: &ShipContext, waypoint_symbol: &types::WaypointSymbol) -> Result<Transition> {
+        let shipyard = ctx.api().get_shipyard(waypoint_symbol.clone()).await?;
+        let credits = ctx.api().get_agent().await?.credits;
+
+        let now = ctx.now();
+        let income_per_hour = *self.income_per_hour.lock().unwrap();
+        let recommendation = {
+            let mut advisor = self.advisor.lock().unwrap();
+            advisor.add_shipyard(&shipyard, now);
+            advisor.recommend(credits, self.caps.reserve, income_per_hour, now).into_iter()
+                .find(|recommendation| recommendation.offer.waypoint_symbol == *waypoint_symbol)
+        };
+
+        // prices may have moved since the advice that brought us here
+        if let Some(recommendation) = recommendation.filter(|r| r.buy_at <= now && self.within_caps(ctx, r.offer.price, credits)) {
+            let data = ctx.api().purchase_ship(recommendation.offer.ship_type, waypoint_symbol.clone()).await?;
+            ctx.spend(&format!("{}{}", SHIP_PURCHASE, data.ship.symbol), data.transaction.price)?;
+            self.purchases.lock().unwrap().push(data.ship);
+        }
+        Ok(Transition::Next(ShipState::Idle))
+    }
+}
+
+#[async_trait]
+impl ShipBehavior for AutoBuyBehavior {
+    fn name(&self) -> &str {
+        "auto-buy"
+    }
+
+    async fn step(&mut self, ctx: &ShipContext, state: &ShipState) -> Result<Transition> {
+        match state {
+            ShipState::Purchasing { waypoint } => self.purchase(ctx, waypoint).await,
+            ShipState::Navigating { destination, .. } => {
+                Ok(Transition::Next(ShipState::Purchasing { waypoint: destination.clone() }))
+            }
+            _ => self.plan(ctx).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use chrono::Duration;
+    use crate::behaviors::{AutoBuyBehavior, BehaviorRuntime, ShipBehavior, ShipContext, ShipOffer, ShipRecord, ShipState, ShipyardAdvisor, SpendingCaps, StateStore, TransactionLog, Transition};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::types::{ShipSymbol, ShipType, WaypointSymbol};
+
+    fn offer(waypoint: &str, ship_type: ShipType, price: u64) -> ShipOffer {
+        ShipOffer {
+            waypoint_symbol: WaypointSymbol::new(waypoint).unwrap(),
+            ship_type,
+            price,
+            observed_at: "2023-07-01T00:00:00Z".parse().unwrap(),
+            ship: None,
+        }
+    }
+
+    #[test]
+    fn test_recommend() {
+        let mut advisor = ShipyardAdvisor::new(HashMap::from([
+            (ShipType::ShipMiningDrone, 5_000.0),
+            (ShipType::ShipLightHauler, 12_000.0),
+        ]));
+        advisor.add(offer("X1-DF55-00001A", ShipType::ShipMiningDrone, 80_000));
+        advisor.add(offer("X1-DF55-00002B", ShipType::ShipMiningDrone, 70_000));
+        advisor.add(offer("X1-DF55-00002B", ShipType::ShipLightHauler, 300_000));
+        advisor.add(offer("X1-DF55-00002B", ShipType::ShipProbe, 20_000));
+
+        let now = "2023-07-01T12:00:00Z".parse().unwrap();
+        let recommendations = advisor.recommend(100_000, 10_000, 20_000.0, now);
+        assert_eq!(recommendations.len(), 2);
+
+        let drone = &recommendations[0];
+        assert_eq!(drone.offer.ship_type, ShipType::ShipMiningDrone);
+        assert_eq!(drone.offer.waypoint_symbol.to_string(), "X1-DF55-00002B");
+        assert_eq!(drone.payback, Duration::hours(14));
+        assert_eq!(drone.buy_at, now);
+
+        let hauler = &recommendations[1];
+        assert_eq!(hauler.offer.ship_type, ShipType::ShipLightHauler);
+        assert_eq!(hauler.payback, Duration::hours(25));
+        assert_eq!(hauler.buy_at, now + Duration::hours(10) + Duration::minutes(30));
+    }
+
+    #[tokio::test]
+    async fn test_caps_survive_restart() {
+        let ship_symbol = ShipSymbol::new("FAKE-1").unwrap();
+        let store = Arc::new(StateStore::in_memory());
+        let mut record = ShipRecord::new("auto-buy");
+        record.spent.insert(String::from("ship:FAKE-2"), 70_000);
+        store.save(&ship_symbol, record).unwrap();
+
+        let ctx = ShipContext::new(ship_symbol, Arc::new(FakeClient::new()), Scheduler::new(RateLimit::default()), Arc::new(TransactionLog::default()), store);
+        let caps = SpendingCaps {
+            max_price: 100_000,
+            max_total: 500_000,
+            reserve: 10_000,
+            max_ships: 1,
+        };
+        let mut advisor = ShipyardAdvisor::new(HashMap::from([(ShipType::ShipMiningDrone, 5_000.0)]));
+        advisor.add(offer("X1-DF55-00002B", ShipType::ShipMiningDrone, 70_000));
+        let mut behavior = AutoBuyBehavior::new(Arc::new(Mutex::new(advisor)), caps, Arc::new(Mutex::new(0.0)));
+
+        // the ship bought before the restart still counts against the cap
+        assert_eq!(behavior.step(&ctx, &ShipState::Idle).await.unwrap(), Transition::Done);
+    }
+
+    #[tokio::test]
+    async fn test_caps_hold_after_finishing() {
+        const SHIPYARD: &str = "X1-DF55-00002B";
+        let fake = Arc::new(FakeClient::new()
+            .waypoint(SHIPYARD, "MOON", &["SHIPYARD"])
+            .shipyard(SHIPYARD, &[("SHIP_MINING_DRONE", 70_000)])
+            .ship("FAKE-1", SHIPYARD, "DOCKED"));
+        let caps = SpendingCaps {
+            max_price: 100_000,
+            max_total: 500_000,
+            reserve: 10_000,
+            max_ships: 1,
+        };
+        let auto_buy = || {
+            let mut advisor = ShipyardAdvisor::new(HashMap::from([(ShipType::ShipMiningDrone, 5_000.0)]));
+            advisor.add(offer(SHIPYARD, ShipType::ShipMiningDrone, 70_000));
+            AutoBuyBehavior::new(Arc::new(Mutex::new(advisor)), caps.clone(), Arc::new(Mutex::new(0.0)))
+        };
+
+        // buys up to the cap and finishes, then runs again as if after a restart
+        let path = std::env::temp_dir().join(format!("space-traders-auto-buy-{}.json", std::process::id()));
+        let mut bought = Vec::new();
+        for _ in 0..2 {
+            let mut runtime = BehaviorRuntime::new(fake.clone(), Scheduler::new(RateLimit::default()), StateStore::open(&path).unwrap());
+            let behavior = auto_buy();
+            let purchases = behavior.purchases();
+            runtime.spawn(ShipSymbol::new("FAKE-1").unwrap(), Box::new(behavior));
+            for (_, result) in runtime.run().await {
+                result.unwrap();
+            }
+            bought.push(purchases.lock().unwrap().len());
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bought, vec![1, 0]);
+        assert_eq!(fake.ships.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::behaviors::ShipState;
+use crate::error::{DecodeError, Error, Result};
+use crate::types;
+
+/// The persisted part of a running behavior.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipRecord {
+    pub behavior: String,
+    pub state: ShipState,
+    pub resume_at: Option<DateTime<Utc>>,
+    /// Credits the behavior has spent, by what it spent them on, so spending
+    /// caps still hold after a restart.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spent: BTreeMap<String, u64>,
+}
+
+impl ShipRecord {
+    /// A behavior starting out, with nothing spent yet.
+    pub fn new(behavior: &str) -> Self {
+        ShipRecord {
+            behavior: behavior.to_string(),
+            state: ShipState::Idle,
+            resume_at: None,
+            spent: BTreeMap::new(),
+        }
+    }
+}
+
+/// Ship states kept in a JSON file, rewritten on every transition.
+#[derive(Debug)]
+pub struct StateStore {
+    path: Option<PathBuf>,
+    records: Mutex<HashMap<types::ShipSymbol, ShipRecord>>,
+}
+
+impl StateStore {
+    /// A store that only lives as long as the process.
+    pub fn in_memory() -> Self {
+        StateStore {
+            path: None,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Opens the file at `path`, starting empty if it doesn't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| DecodeError {
+                message: path.display().to_string(),
+                error: e,
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(StateStore {
+            path: Some(path),
+            records: Mutex::new(records),
+        })
+    }
+
+    pub fn get(&self, ship_symbol: &str) -> Option<ShipRecord> {
+        self.records.lock().unwrap().get(ship_symbol).cloned()
+    }
+
+    pub fn all(&self) -> HashMap<types::ShipSymbol, ShipRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn save(&self, ship_symbol: &types::ShipSymbol, record: ShipRecord) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        records.insert(ship_symbol.clone(), record);
+        self.flush(&records)
+    }
+
+    /// Adds to what the ship's behavior has spent on `what`, returning the new total.
+    pub fn spend(&self, ship_symbol: &str, what: &str, credits: u64) -> Result<u64> {
+        let mut records = self.records.lock().unwrap();
+        let Some(record) = records.get_mut(ship_symbol) else {
+            return Err(Error::BehaviorError(format!("{} has no behavior to spend for", ship_symbol)));
+        };
+        let spent = record.spent.entry(what.to_string()).or_default();
+        *spent += credits;
+        let total = *spent;
+        self.flush(&records)?;
+        Ok(total)
+    }
+
+    /// Forgets what was spent on `what`, once it's been paid off.
+    pub fn forget_spent(&self, ship_symbol: &str, what: &str) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        if let Some(record) = records.get_mut(ship_symbol) {
+            record.spent.remove(what);
+        }
+        self.flush(&records)
+    }
+
+    pub fn remove(&self, ship_symbol: &str) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        records.remove(ship_symbol);
+        self.flush(&records)
+    }
+
+    fn flush(&self, records: &HashMap<types::ShipSymbol, ShipRecord>) -> Result<()> {
+        if let Some(path) = &self.path {
+            // write to the side and rename, so a crash mid-write can't lose every ship
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_vec_pretty(records).unwrap())?;
+            std::fs::rename(&tmp, path)?;
+        }
+        Ok(())
+    }
+}
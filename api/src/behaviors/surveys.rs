@@ -0,0 +1,101 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::types;
+
+/// Surveys shared between every ship mining the same fields.
+#[derive(Debug, Default)]
+pub struct SurveyStore {
+    surveys: Mutex<Vec<types::Survey>>,
+}
+
+impl SurveyStore {
+    pub fn add(&self, surveys: Vec<types::Survey>) {
+        self.surveys.lock().unwrap().extend(surveys);
+    }
+
+    pub fn remove(&self, signature: &str) {
+        self.surveys.lock().unwrap().retain(|survey| survey.signature != signature);
+    }
+
+    pub fn len(&self) -> usize {
+        self.surveys.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The unexpired survey of the waypoint with the highest average deposit
+    /// value, dropping any that have expired along the way.
+    pub fn best<F: Fn(&types::MarketGoodSymbol) -> u64>(&self, waypoint_symbol: &types::WaypointSymbol, now: DateTime<Utc>, value: F) -> Option<types::Survey> {
+        let mut surveys = self.surveys.lock().unwrap();
+        surveys.retain(|survey| survey.expiration > now);
+
+        surveys.iter()
+            .filter(|survey| survey.symbol == *waypoint_symbol && !survey.deposits.is_empty())
+            .map(|survey| {
+                let total: u64 = survey.deposits.iter().map(|deposit| value(&deposit.symbol)).sum();
+                (total, survey.deposits.len() as u64, survey)
+            })
+            // averages compared by cross-multiplying, since dividing would round small ones to 0
+            .max_by(|(a, a_len, _), (b, b_len, _)| (*a as u128 * *b_len as u128).cmp(&(*b as u128 * *a_len as u128)))
+            .map(|(_, _, survey)| survey.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::behaviors::SurveyStore;
+    use crate::types::{MarketGoodSymbol, Survey, WaypointSymbol};
+
+    fn survey(signature: &str, deposits: &[&str], expiration: &str) -> Survey {
+        serde_json::from_value(json!({
+            "signature": signature,
+            "symbol": "X1-DF55-17335A",
+            "deposits": deposits.iter().map(|symbol| json!({ "symbol": symbol })).collect::<Vec<_>>(),
+            "expiration": expiration,
+            "size": "MODERATE",
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_best_survey() {
+        let store = SurveyStore::default();
+        store.add(vec![
+            survey("ICE", &["ICE_WATER", "ICE_WATER", "QUARTZ_SAND"], "2023-07-02T00:00:00Z"),
+            survey("GOLD", &["GOLD_ORE", "ICE_WATER"], "2023-07-02T00:00:00Z"),
+            survey("EXPIRED", &["PLATINUM_ORE"], "2023-07-01T00:00:00Z"),
+        ]);
+
+        let value = |good: &MarketGoodSymbol| match good {
+            MarketGoodSymbol::PlatinumOre => 200,
+            MarketGoodSymbol::GoldOre => 100,
+            MarketGoodSymbol::QuartzSand => 20,
+            _ => 10,
+        };
+        let field = WaypointSymbol::new("X1-DF55-17335A").unwrap();
+        let best = store.best(&field, "2023-07-01T12:00:00Z".parse().unwrap(), value).unwrap();
+
+        assert_eq!(best.signature, "GOLD");
+        assert_eq!(store.len(), 2);
+        assert!(store.best(&WaypointSymbol::new("X1-DF55-20250Z").unwrap(), "2023-07-01T12:00:00Z".parse().unwrap(), value).is_none());
+    }
+
+    #[test]
+    fn test_best_mixed_survey() {
+        let store = SurveyStore::default();
+        store.add(vec![
+            survey("COPPER", &["COPPER_ORE", "ICE_WATER", "QUARTZ_SAND"], "2023-07-02T00:00:00Z"),
+            survey("ICE", &["ICE_WATER", "QUARTZ_SAND"], "2023-07-02T00:00:00Z"),
+        ]);
+
+        // scored the way the contract miner scores them, 1 for the wanted good
+        let field = WaypointSymbol::new("X1-DF55-17335A").unwrap();
+        let best = store.best(&field, "2023-07-01T12:00:00Z".parse().unwrap(), |good| u64::from(*good == MarketGoodSymbol::CopperOre));
+
+        assert_eq!(best.unwrap().signature, "COPPER");
+    }
+}
@@ -0,0 +1,50 @@
+use crate::behaviors::ShipContext;
+use crate::error::{Error, Result};
+use crate::{SpaceTradersClient, types};
+
+/// The waypoints of the system a ship is working in, fetched once per system.
+#[derive(Debug, Default)]
+pub struct SystemWaypoints {
+    waypoints: Vec<types::Waypoint>,
+}
+
+impl SystemWaypoints {
+    pub async fn load(&mut self, ctx: &ShipContext, system_symbol: &types::SystemSymbol) -> Result<()> {
+        if self.waypoints.first().map(|w| w.system_symbol == *system_symbol).unwrap_or(false) {
+            return Ok(());
+        }
+
+        self.waypoints = ctx.api().list_system_waypoints(system_symbol.clone(), None, None).await?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &types::Waypoint> {
+        self.waypoints.iter()
+    }
+
+    pub fn get(&self, waypoint_symbol: &types::WaypointSymbol) -> Option<&types::Waypoint> {
+        self.waypoints.iter().find(|waypoint| waypoint.reference.symbol == *waypoint_symbol)
+    }
+
+    /// The closest matching waypoint to where the ship is, or is headed.
+    pub fn nearest(&self, ship: &types::Ship, p: fn(&types::Waypoint) -> bool) -> Option<&types::Waypoint> {
+        let here = &ship.nav.route.destination;
+        self.waypoints.iter()
+            .filter(|waypoint| p(waypoint))
+            .min_by(|a, b| {
+                a.reference.distance_to(here.x, here.y).total_cmp(&b.reference.distance_to(here.x, here.y))
+            })
+    }
+}
+
+pub fn is_asteroid_field(waypoint: &types::Waypoint) -> bool {
+    waypoint.reference.waypoint_type == types::WaypointType::AsteroidField
+}
+
+pub(crate) fn no_waypoint(kind: &str, ship: &types::Ship) -> Error {
+    Error::BehaviorError(format!("no {} in system {}", kind, ship.nav.system_symbol))
+}
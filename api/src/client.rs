@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+
+use crate::events::Event;
+use crate::{error, SpaceTradersApi, types};
+
+/// Every endpoint of the game. `SpaceTradersApi` plays it for real; a
+/// simulator or a test fake can stand in for it.
+#[async_trait]
+pub trait SpaceTradersClient: Send + Sync + Debug {
+    /// The game's current time, which a simulator may run faster than the wall clock.
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    /// Publishes an `Event::RateLimited` to `events` for every rate limit the
+    /// client waits out and retries itself, since the caller never sees those.
+    fn report_rate_limits(&self, _events: broadcast::Sender<Event>) {}
+
+    async fn get_agent(&self) -> error::Result<types::Agent>;
+    async fn list_factions(&self) -> error::Result<Vec<types::Faction>>;
+
+    async fn list_contracts(&self) -> error::Result<Vec<types::Contract>>;
+    async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract>;
+    async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData>;
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData>;
+    async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData>;
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract>;
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::System>>;
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> error::Result<types::System>;
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::Waypoint>>;
+    /// The waypoints `query` asks for. Unless a client filters them itself,
+    /// this lists all of them and checks each against the query.
+    async fn query_system_waypoints(&self, system_symbol: types::SystemSymbol, query: &types::WaypointQuery) -> error::Result<Vec<types::Waypoint>> {
+        let mut waypoints = self.list_system_waypoints(system_symbol, None, None).await?;
+        waypoints.retain(|waypoint| query.matches(waypoint));
+        Ok(waypoints)
+    }
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint>;
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Market>;
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Shipyard>;
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::JumpGate>;
+
+    async fn list_ships(&self) -> error::Result<Vec<types::Ship>>;
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship>;
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData>;
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav>;
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav>;
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData>;
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData>;
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>>;
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData>;
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> error::Result<types::ExtractData>;
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo>;
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData>;
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData>;
+}
+
+// the inherent methods of the same names take precedence, so each of these
+// forwards to the real request rather than recursing
+#[async_trait]
+impl SpaceTradersClient for SpaceTradersApi {
+    fn report_rate_limits(&self, events: broadcast::Sender<Event>) {
+        self.report_rate_limits(events)
+    }
+
+    async fn get_agent(&self) -> error::Result<types::Agent> {
+        self.get_agent().await
+    }
+
+    async fn list_factions(&self) -> error::Result<Vec<types::Faction>> {
+        self.list_factions().await
+    }
+
+    async fn list_contracts(&self) -> error::Result<Vec<types::Contract>> {
+        self.list_contracts().await
+    }
+
+    async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract> {
+        self.get_contract(contract_id).await
+    }
+
+    async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData> {
+        self.accept_contract(contract_id).await
+    }
+
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData> {
+        self.deliver_contract(contract_id, ship_symbol, trade_symbol, units).await
+    }
+
+    async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData> {
+        self.fulfill_contract(contract_id).await
+    }
+
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract> {
+        self.negotiate_contract(ship_symbol).await
+    }
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::System>> {
+        self.list_systems(page, limit).await
+    }
+
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> error::Result<types::System> {
+        self.get_system(system_symbol).await
+    }
+
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::Waypoint>> {
+        self.list_system_waypoints(system_symbol, page, limit).await
+    }
+
+    async fn query_system_waypoints(&self, system_symbol: types::SystemSymbol, query: &types::WaypointQuery) -> error::Result<Vec<types::Waypoint>> {
+        self.query_system_waypoints(system_symbol, query).await
+    }
+
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint> {
+        self.get_waypoint(waypoint_symbol).await
+    }
+
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Market> {
+        self.get_market(waypoint_symbol).await
+    }
+
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Shipyard> {
+        self.get_shipyard(waypoint_symbol).await
+    }
+
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::JumpGate> {
+        self.get_jump_gate(waypoint_symbol).await
+    }
+
+    async fn list_ships(&self) -> error::Result<Vec<types::Ship>> {
+        self.list_ships().await
+    }
+
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship> {
+        self.get_ship(ship_symbol).await
+    }
+
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData> {
+        self.purchase_ship(ship_type, waypoint_symbol).await
+    }
+
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.orbit_ship(ship_symbol).await
+    }
+
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.dock_ship(ship_symbol).await
+    }
+
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData> {
+        self.navigate_ship(ship_symbol, waypoint_symbol).await
+    }
+
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData> {
+        self.refuel_ship(ship_symbol).await
+    }
+
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>> {
+        self.get_ship_cooldown(ship_symbol).await
+    }
+
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData> {
+        self.create_survey(ship_symbol).await
+    }
+
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> error::Result<types::ExtractData> {
+        self.extract_resources(ship_symbol, survey).await
+    }
+
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo> {
+        self.jettison_cargo(ship_symbol, symbol, units).await
+    }
+
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData> {
+        self.sell_cargo(ship_symbol, symbol, units).await
+    }
+
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData> {
+        self.purchase_cargo(ship_symbol, symbol, units).await
+    }
+}
@@ -0,0 +1,250 @@
+//! A local HTTP server for watching and steering a running fleet.
+//!
+//! | route | |
+//! |---|---|
+//! | `GET /ships`, `GET /ships/:ship` | what each ship's behavior is doing |
+//! | `POST /ships/:ship/pause`, `POST /ships/:ship/resume` | hold a ship before its next step, or let it carry on |
+//! | `POST /ships/:ship/behavior` | start another behavior on the ship, e.g. `{"behavior": "mining"}` |
+//! | `GET /contracts` | contracts and their delivery progress |
+//! | `GET /transactions?limit=` | the fleet's latest trades |
+//! | `POST /markets/:waypoint/refresh` | fetch a market into the cache now |
+//! | `GET /events` | everything the fleet does and what the game reports back, as server-sent events |
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::behaviors::{FleetControl, MarketCache, ShipBehavior, ShipStatus};
+use crate::scheduler::Priority;
+use crate::{error, ApiManager, SpaceTradersClient, types};
+
+const DEFAULT_TRANSACTIONS: usize = 50;
+
+/// Makes a behavior from its name, for reassigning `ship`. `None` if there's
+/// no behavior by that name.
+pub type BehaviorFactory = Arc<dyn Fn(&str, &types::ShipSymbol) -> Option<Box<dyn ShipBehavior>> + Send + Sync>;
+
+/// Everything the server works with: the manager for reads and game events,
+/// the runtime's control handle for commands, and the cache market refreshes
+/// go into. Build the runtime on `manager.client()` for its events to show up.
+pub struct ControlState<C: SpaceTradersClient> {
+    pub manager: Arc<ApiManager<C>>,
+    pub fleet: FleetControl,
+    pub markets: Arc<MarketCache>,
+    pub behaviors: BehaviorFactory,
+}
+
+impl<C: SpaceTradersClient> Clone for ControlState<C> {
+    fn clone(&self) -> Self {
+        ControlState {
+            manager: self.manager.clone(),
+            fleet: self.fleet.clone(),
+            markets: self.markets.clone(),
+            behaviors: self.behaviors.clone(),
+        }
+    }
+}
+
+/// Serves the control API on `addr` until the process exits. Bind it to
+/// localhost: there's no authentication.
+pub async fn serve<C: SpaceTradersClient + 'static>(addr: SocketAddr, state: ControlState<C>) -> error::Result<()> {
+    let server = axum::Server::try_bind(&addr).map_err(hyper_error)?;
+    server.serve(router(state).into_make_service()).await.map_err(hyper_error)
+}
+
+fn hyper_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> error::Error {
+    std::io::Error::other(e).into()
+}
+
+pub fn router<C: SpaceTradersClient + 'static>(state: ControlState<C>) -> Router {
+    Router::new()
+        .route("/ships", get(list_ships::<C>))
+        .route("/ships/:ship", get(get_ship::<C>))
+        .route("/ships/:ship/pause", post(pause_ship::<C>))
+        .route("/ships/:ship/resume", post(resume_ship::<C>))
+        .route("/ships/:ship/behavior", post(reassign_ship::<C>))
+        .route("/contracts", get(list_contracts::<C>))
+        .route("/transactions", get(list_transactions::<C>))
+        .route("/markets/:waypoint/refresh", post(refresh_market::<C>))
+        .route("/events", get(events::<C>))
+        .with_state(state)
+}
+
+/// An error as JSON, e.g. `{"error": "no ship BEAR-9"}`.
+struct ControlError(StatusCode, String);
+
+impl IntoResponse for ControlError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<error::Error> for ControlError {
+    fn from(e: error::Error) -> Self {
+        ControlError(StatusCode::BAD_GATEWAY, e.to_string())
+    }
+}
+
+type ControlResult<T> = Result<T, ControlError>;
+
+fn not_running(ship_symbol: &str) -> ControlError {
+    ControlError(StatusCode::NOT_FOUND, format!("no ship {} is running", ship_symbol))
+}
+
+async fn list_ships<C: SpaceTradersClient>(State(state): State<ControlState<C>>) -> Json<Vec<ShipStatus>> {
+    Json(state.fleet.ships())
+}
+
+async fn get_ship<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Path(ship_symbol): Path<String>) -> ControlResult<Json<ShipStatus>> {
+    state.fleet.ship(&ship_symbol).map(Json).ok_or_else(|| not_running(&ship_symbol))
+}
+
+async fn pause_ship<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Path(ship_symbol): Path<String>) -> ControlResult<StatusCode> {
+    match state.fleet.pause(&ship_symbol) {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(not_running(&ship_symbol)),
+    }
+}
+
+async fn resume_ship<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Path(ship_symbol): Path<String>) -> ControlResult<StatusCode> {
+    match state.fleet.resume(&ship_symbol) {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err(not_running(&ship_symbol)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Reassign {
+    behavior: String,
+}
+
+async fn reassign_ship<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Path(ship_symbol): Path<types::ShipSymbol>, Json(request): Json<Reassign>) -> ControlResult<StatusCode> {
+    let behavior = (state.behaviors)(&request.behavior, &ship_symbol)
+        .ok_or_else(|| ControlError(StatusCode::BAD_REQUEST, format!("no behavior called {}", request.behavior)))?;
+    state.fleet.reassign(ship_symbol, behavior);
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn list_contracts<C: SpaceTradersClient>(State(state): State<ControlState<C>>) -> ControlResult<Json<Vec<types::Contract>>> {
+    state.fleet.scheduler().acquire("control", Priority::Normal).await;
+    Ok(Json(state.manager.api.list_contracts().await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct Limit {
+    limit: Option<usize>,
+}
+
+async fn list_transactions<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Query(query): Query<Limit>) -> Json<Vec<types::MarketTransaction>> {
+    Json(state.fleet.transactions().recent(query.limit.unwrap_or(DEFAULT_TRANSACTIONS)))
+}
+
+async fn refresh_market<C: SpaceTradersClient>(State(state): State<ControlState<C>>, Path(waypoint): Path<String>) -> ControlResult<Json<types::Market>> {
+    let waypoint_symbol = types::WaypointSymbol::new(&waypoint)
+        .map_err(|e| ControlError(StatusCode::BAD_REQUEST, format!("{}: {}", waypoint, e)))?;
+
+    state.fleet.scheduler().acquire("control", Priority::Background).await;
+    let market = state.manager.api.get_market(waypoint_symbol).await?;
+    state.markets.update(market.clone(), state.manager.api.now());
+    Ok(Json(market))
+}
+
+async fn events<C: SpaceTradersClient>(State(state): State<ControlState<C>>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let fleet = sse_stream(state.fleet.subscribe());
+    let game = sse_stream(state.manager.subscribe());
+    Sse::new(futures_util::stream::select(fleet, game)).keep_alive(KeepAlive::default())
+}
+
+fn sse_stream<T: Serialize + Clone + Send + 'static>(events: broadcast::Receiver<T>) -> impl Stream<Item = Result<Event, Infallible>> {
+    futures_util::stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let data = serde_json::to_string(&event).unwrap();
+                    return Some((Ok(Event::default().data(data)), events));
+                }
+                // a slow client misses events rather than holding the fleet up
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use chrono::Duration;
+    use serde_json::{json, Value};
+
+    use crate::behaviors::{BehaviorRuntime, MarketCache, ShipBehavior, ShipContext, ShipState, StateStore, Transition};
+    use crate::control::{router, ControlState};
+    use crate::fake::FakeClient;
+    use crate::scheduler::{RateLimit, Scheduler};
+    use crate::{error, ApiManager, types};
+
+    /// Waits at a waypoint for an hour at a time.
+    struct Parked;
+
+    #[async_trait]
+    impl ShipBehavior for Parked {
+        fn name(&self) -> &str {
+            "parked"
+        }
+
+        async fn step(&mut self, ctx: &ShipContext, _state: &ShipState) -> error::Result<Transition> {
+            let waypoint = types::WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+            Ok(Transition::Wait(ShipState::Docked { waypoint }, ctx.now() + Duration::hours(1)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control() {
+        let fake = FakeClient::new().market("X1-DF55-20250Z", &[("IRON_ORE", 40)]);
+        let manager = Arc::new(ApiManager::new(Arc::new(fake)).await.unwrap());
+        let mut runtime = BehaviorRuntime::new(manager.client(), Scheduler::new(RateLimit::default()), StateStore::in_memory());
+        runtime.spawn(types::ShipSymbol::new("BEAR-1").unwrap(), Box::new(Parked));
+
+        let markets = Arc::new(MarketCache::default());
+        let state = ControlState {
+            manager,
+            fleet: runtime.control(),
+            markets: markets.clone(),
+            behaviors: Arc::new(|name: &str, _: &types::ShipSymbol| match name {
+                "parked" => Some(Box::new(Parked) as Box<dyn ShipBehavior>),
+                _ => None,
+            }),
+        };
+        let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(router(state).into_make_service());
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        tokio::spawn(runtime.run());
+
+        let client = reqwest::Client::new();
+        let response = client.post(format!("{}/ships/BEAR-1/pause", url)).send().await.unwrap();
+        assert_eq!(response.status(), 204);
+        let ships: Value = client.get(format!("{}/ships", url)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(ships[0]["shipSymbol"], "BEAR-1");
+        assert_eq!(ships[0]["paused"], true);
+
+        let response = client.post(format!("{}/ships/BEAR-9/resume", url)).send().await.unwrap();
+        assert_eq!(response.status(), 404);
+        let response = client.post(format!("{}/ships/BEAR-1/behavior", url)).json(&json!({ "behavior": "mining" })).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+
+        let response = client.post(format!("{}/markets/X1-DF55-20250Z/refresh", url)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(markets.sell_price(&types::WaypointSymbol::new("X1-DF55-20250Z").unwrap(), &types::MarketGoodSymbol::IronOre), Some(36));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{DecodeError, Result};
+use crate::types;
+
+/// Where registration data is kept: `SPACE_TRADERS_CREDENTIALS` if it's
+/// set, otherwise `space-traders/credentials.json` in the user's config
+/// directory.
+pub fn credentials_path() -> PathBuf {
+    if let Ok(path) = std::env::var("SPACE_TRADERS_CREDENTIALS") {
+        return PathBuf::from(path);
+    }
+    let config = std::env::var("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+    config.join("space-traders").join("credentials.json")
+}
+
+// older versions saved a single registration rather than one per agent
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CredentialsFile {
+    Agents(BTreeMap<String, types::RegistrationData>),
+    Single(Box<types::RegistrationData>),
+}
+
+/// Where registrations are saved when `credentials_path()` can't be written:
+/// `space-traders-credentials.json` in the working directory.
+pub fn fallback_credentials_path() -> PathBuf {
+    PathBuf::from("space-traders-credentials.json")
+}
+
+/// The registration saved for `call_sign` at `credentials_path()`, or else
+/// at `fallback_credentials_path()`, with the path it was found at.
+pub fn find_credentials(call_sign: &str) -> Result<Option<(types::RegistrationData, PathBuf)>> {
+    for path in [credentials_path(), fallback_credentials_path()] {
+        if let Some(registration_data) = load_credentials(&path, call_sign)? {
+            return Ok(Some((registration_data, path)));
+        }
+    }
+    Ok(None)
+}
+
+/// The symbols of the agents saved at either `credentials_path()` or
+/// `fallback_credentials_path()`.
+pub fn find_saved_agents() -> Result<Vec<String>> {
+    let mut agents = saved_agents(credentials_path())?;
+    agents.extend(saved_agents(fallback_credentials_path())?);
+    agents.sort();
+    agents.dedup();
+    Ok(agents)
+}
+
+/// Adds the registration to those saved at `path`, keyed by the agent's
+/// symbol, readable only by the current user. Other agents' tokens are kept.
+pub fn save_credentials<P: AsRef<Path>>(path: P, registration_data: &types::RegistrationData) -> Result<()> {
+    let path = path.as_ref();
+    let mut agents = read_credentials(path)?;
+    agents.insert(registration_data.agent.symbol.clone(), registration_data.clone());
+    write_private(path, &serde_json::to_vec_pretty(&agents).unwrap())
+}
+
+/// The registration saved at `path` for `call_sign`, or `None` if it hasn't
+/// been saved.
+pub fn load_credentials<P: AsRef<Path>>(path: P, call_sign: &str) -> Result<Option<types::RegistrationData>> {
+    Ok(read_credentials(path.as_ref())?
+        .into_iter()
+        .find(|(symbol, _)| symbol.eq_ignore_ascii_case(call_sign))
+        .map(|(_, registration_data)| registration_data))
+}
+
+/// The symbols of the agents with registrations saved at `path`.
+pub fn saved_agents<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    Ok(read_credentials(path.as_ref())?.into_keys().collect())
+}
+
+fn read_credentials(path: &Path) -> Result<BTreeMap<String, types::RegistrationData>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let file = serde_json::from_str(&contents).map_err(|e| DecodeError {
+        message: path.display().to_string(),
+        error: e,
+    })?;
+    Ok(match file {
+        CredentialsFile::Agents(agents) => agents,
+        CredentialsFile::Single(registration_data) => BTreeMap::from([(registration_data.agent.symbol.clone(), *registration_data)]),
+    })
+}
+
+/// Replaces the file at `path` with `contents`, with its permissions set to
+/// 0600 before anything is written. Tokens can't be revoked, so they mustn't
+/// be left readable by other users even briefly.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // write to the side and rename, so a crash mid-write can't lose the token
+    let tmp = path.with_extension("tmp");
+    let _ = std::fs::remove_file(&tmp);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Error, UnsavedRegistration};
+    use crate::{load_credentials, save_credentials, types};
+
+    #[test]
+    fn test_credentials_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("space-traders-credentials-{}", std::process::id()))
+            .join("credentials.json");
+        assert!(load_credentials(&path, "BEAR").unwrap().is_none());
+
+        let response: types::ApiSuccess<types::RegistrationData> = serde_json::from_str(include_str!("../tests/fixtures/register.json")).unwrap();
+        let registration_data = response.data;
+        save_credentials(&path, &registration_data).unwrap();
+
+        // registering another agent keeps the first one's token
+        let mut other = registration_data.clone();
+        other.agent.symbol = String::from("OTTER");
+        other.token = String::from("otter-token");
+        save_credentials(&path, &other).unwrap();
+        let loaded = load_credentials(&path, "bear").unwrap().unwrap();
+        let loaded_other = load_credentials(&path, "OTTER").unwrap().unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded.token, registration_data.token);
+        assert_eq!(loaded.agent.symbol, "BEAR");
+        assert_eq!(loaded.contract.id, "cljw4yqbw0003s60ccwu7qv5s");
+        assert_eq!(loaded.ship.registration.role, types::ShipRole::Command);
+        assert_eq!(loaded_other.token, "otter-token");
+    }
+
+    #[test]
+    fn test_single_registration_file() {
+        let path = std::env::temp_dir().join(format!("space-traders-credentials-single-{}.json", std::process::id()));
+        let response: types::ApiSuccess<types::RegistrationData> = serde_json::from_str(include_str!("../tests/fixtures/register.json")).unwrap();
+        std::fs::write(&path, serde_json::to_vec(&response.data).unwrap()).unwrap();
+        let loaded = load_credentials(&path, "BEAR").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap().token, response.data.token);
+    }
+
+    #[test]
+    fn test_unsaved_registration_hides_token() {
+        let response: types::ApiSuccess<types::RegistrationData> = serde_json::from_str(include_str!("../tests/fixtures/register.json")).unwrap();
+        let token = response.data.token.clone();
+        let error = Error::UnsavedRegistration(UnsavedRegistration {
+            registration_data: Box::new(response.data),
+            error: Box::new(std::io::Error::from(std::io::ErrorKind::PermissionDenied).into()),
+        });
+
+        assert!(error.to_string().starts_with("registered BEAR"));
+        assert!(!error.to_string().contains(&token));
+        assert!(!format!("{:?}", error).contains(&token));
+    }
+}
@@ -0,0 +1,370 @@
+//! Finds where the server and the types have drifted apart, e.g. after a new
+//! season: fields a response has that decoding drops, fields the types
+//! expect that a response leaves out, and symbols the types have no variant for.
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::{DecodeError, Result};
+use crate::types;
+
+// put in front of a `symbols!` enum's `Unknown(..)` value while it's serialized for `diff`
+const UNKNOWN_SYMBOL: &str = "\u{0}unknown:";
+
+thread_local! {
+    static MARK_UNKNOWN: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DriftKind {
+    /// The response has a field the types don't model, so decoding drops it.
+    Unknown,
+    /// The types have a field the response left out, e.g. an `Option` that's always `None`.
+    Missing,
+    /// The response doesn't decode at all; the field is serde's reason why.
+    Invalid,
+    /// The response has a symbol its enum has no variant for, so it decoded as `Unknown(..)`.
+    UnknownSymbol,
+}
+
+/// A field that drifted on an endpoint, and how many responses it drifted in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDrift {
+    pub endpoint: String,
+    pub field: String,
+    pub kind: DriftKind,
+    pub seen: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    /// How many responses were checked, by endpoint.
+    pub checked: BTreeMap<String, u64>,
+    pub fields: Vec<FieldDrift>,
+}
+
+impl DriftReport {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    checked: BTreeMap<String, u64>,
+    fields: BTreeMap<(String, String, DriftKind), u64>,
+}
+
+/// Strict mode: compares each response's raw `data` against its decoded value
+/// serialized again, and keeps a tally of the differences per endpoint.
+#[derive(Debug, Default)]
+pub struct SchemaDrift {
+    tally: Mutex<Tally>,
+}
+
+impl SchemaDrift {
+    pub fn new() -> Self {
+        SchemaDrift::default()
+    }
+
+    pub fn check<T: Serialize>(&self, method: &str, path: &str, raw: &Value, typed: &T) {
+        let drifts = match marked_value(typed) {
+            Ok(typed) => {
+                let mut drifts = Vec::new();
+                diff("data", raw, &typed, &mut drifts);
+                drifts
+            }
+            Err(e) => vec![(e.to_string(), DriftKind::Invalid)],
+        };
+        self.tally(method, path, drifts);
+    }
+
+    /// Decodes a recorded response as the type its endpoint returns, and checks it.
+    /// Returns false for errors and endpoints the client doesn't call.
+    pub fn check_recorded(&self, response: &RecordedResponse) -> bool {
+        let Some(data) = response.body.get("data") else {
+            return false;
+        };
+        let Some(retype) = retyper(&response.method, &crate::metrics::endpoint(&response.path)) else {
+            return false;
+        };
+        let drifts = match retype(data) {
+            Ok(typed) => {
+                let mut drifts = Vec::new();
+                diff("data", data, &typed, &mut drifts);
+                drifts
+            }
+            Err(e) => vec![(e.to_string(), DriftKind::Invalid)],
+        };
+        self.tally(&response.method, &response.path, drifts);
+        true
+    }
+
+    pub fn report(&self) -> DriftReport {
+        let tally = self.tally.lock().unwrap();
+        DriftReport {
+            checked: tally.checked.clone(),
+            fields: tally.fields.iter()
+                .map(|((endpoint, field, kind), seen)| FieldDrift {
+                    endpoint: endpoint.clone(),
+                    field: field.clone(),
+                    kind: *kind,
+                    seen: *seen,
+                })
+                .collect(),
+        }
+    }
+
+    fn tally(&self, method: &str, path: &str, drifts: Vec<(String, DriftKind)>) {
+        let endpoint = format!("{} {}", method, crate::metrics::endpoint(path));
+        let mut tally = self.tally.lock().unwrap();
+        *tally.checked.entry(endpoint.clone()).or_default() += 1;
+        // counted once per response, however many array elements drifted
+        for (field, kind) in drifts.into_iter().collect::<BTreeSet<_>>() {
+            let seen = tally.fields.entry((endpoint.clone(), field.clone(), kind)).or_default();
+            if *seen == 0 {
+                tracing::warn!(endpoint, field, ?kind, "schema drift");
+            }
+            *seen += 1;
+        }
+    }
+}
+
+/// Collects the fields that differ between `raw` and `typed`, as paths like
+/// `data.tradeGoods[].activity`. Array elements share one path so that a
+/// field missing from every good is reported once.
+pub(crate) fn diff(path: &str, raw: &Value, typed: &Value, drifts: &mut Vec<(String, DriftKind)>) {
+    match (raw, typed) {
+        (Value::Object(raw), Value::Object(typed)) => {
+            for (key, raw_value) in raw {
+                let field = format!("{}.{}", path, key);
+                match typed.get(key) {
+                    Some(typed_value) => diff(&field, raw_value, typed_value, drifts),
+                    None => drifts.push((field, DriftKind::Unknown)),
+                }
+            }
+            for key in typed.keys().filter(|key| !raw.contains_key(*key)) {
+                drifts.push((format!("{}.{}", path, key), DriftKind::Missing));
+            }
+        }
+        (Value::Array(raw), Value::Array(typed)) => {
+            let field = format!("{}[]", path);
+            for (raw, typed) in raw.iter().zip(typed.iter()) {
+                diff(&field, raw, typed, drifts);
+            }
+        }
+        (Value::String(raw), Value::String(typed)) if typed.strip_prefix(UNKNOWN_SYMBOL) == Some(raw.as_str()) => {
+            drifts.push((path.to_string(), DriftKind::UnknownSymbol));
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn retyped<T: DeserializeOwned + Serialize>(data: &Value) -> serde_json::Result<Value> {
+    marked_value(&T::deserialize(data)?)
+}
+
+/// `typed` as JSON, with its `Unknown(..)` symbols marked for `diff` to find.
+fn marked_value<T: Serialize>(typed: &T) -> serde_json::Result<Value> {
+    MARK_UNKNOWN.with(|mark| mark.set(true));
+    let value = serde_json::to_value(typed);
+    MARK_UNKNOWN.with(|mark| mark.set(false));
+    value
+}
+
+/// Serializes a symbol a `symbols!` enum has no variant for: as it is,
+/// except while strict mode is serializing a response to compare it.
+pub(crate) fn serialize_unknown<S: Serializer>(symbol: &str, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    if MARK_UNKNOWN.with(Cell::get) {
+        serializer.serialize_str(&format!("{}{}", UNKNOWN_SYMBOL, symbol))
+    } else {
+        serializer.serialize_str(symbol)
+    }
+}
+
+/// How to decode the data of each endpoint the client calls, by method and
+/// `metrics::endpoint` path.
+fn retyper(method: &str, endpoint: &str) -> Option<fn(&Value) -> serde_json::Result<Value>> {
+    Some(match (method, endpoint) {
+        ("POST", "register") => retyped::<types::RegistrationData>,
+        ("GET", "my/agent") => retyped::<types::Agent>,
+        ("GET", "factions") => retyped::<Vec<types::Faction>>,
+        ("GET", "my/contracts") => retyped::<Vec<types::Contract>>,
+        ("GET", "my/contracts/:id") => retyped::<types::Contract>,
+        ("POST", "my/contracts/:id/accept") => retyped::<types::AcceptContractData>,
+        ("POST", "my/contracts/:id/deliver") => retyped::<types::DeliverContractData>,
+        ("POST", "my/contracts/:id/fulfill") => retyped::<types::FulfillContractData>,
+        ("GET", "systems") => retyped::<Vec<types::System>>,
+        ("GET", "systems/:id") => retyped::<types::System>,
+        ("GET", "systems/:id/waypoints") => retyped::<Vec<types::Waypoint>>,
+        ("GET", "systems/:id/waypoints/:id") => retyped::<types::Waypoint>,
+        ("GET", "systems/:id/waypoints/:id/market") => retyped::<types::Market>,
+        ("GET", "systems/:id/waypoints/:id/shipyard") => retyped::<types::Shipyard>,
+        ("GET", "systems/:id/waypoints/:id/jump-gate") => retyped::<types::JumpGate>,
+        ("GET", "my/ships") => retyped::<Vec<types::Ship>>,
+        ("POST", "my/ships") => retyped::<types::PurchaseShipData>,
+        ("GET", "my/ships/:id") => retyped::<types::Ship>,
+        ("POST", "my/ships/:id/orbit") | ("POST", "my/ships/:id/dock") => retyped::<types::ShipNavData>,
+        ("POST", "my/ships/:id/navigate") => retyped::<types::NavigateData>,
+        ("POST", "my/ships/:id/refuel") => retyped::<types::RefuelData>,
+        ("GET", "my/ships/:id/cooldown") => retyped::<types::Cooldown>,
+        ("POST", "my/ships/:id/survey") => retyped::<types::CreateSurveyData>,
+        ("POST", "my/ships/:id/extract") => retyped::<types::ExtractData>,
+        ("POST", "my/ships/:id/jettison") => retyped::<types::JettisonData>,
+        ("POST", "my/ships/:id/sell") => retyped::<types::SellCargoData>,
+        ("POST", "my/ships/:id/purchase") => retyped::<types::PurchaseCargoData>,
+        ("POST", "my/ships/:id/negotiate/contract") => retyped::<types::NegotiateContractData>,
+        _ => return None,
+    })
+}
+
+/// A response as the server sent it, as written to a session file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub body: Value,
+}
+
+const REDACTED: &str = "REDACTED";
+
+/// An append-only file of every response the client receives, one JSON
+/// record per line, to check for drift later.
+#[derive(Debug)]
+pub struct SessionRecorder {
+    file: Mutex<File>,
+}
+
+impl SessionRecorder {
+    /// Appends to the file at `path`, creating it readable only by the current
+    /// user, like the credentials.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(path)?;
+        Ok(SessionRecorder {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<RecordedResponse>> {
+        let mut responses = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            responses.push(serde_json::from_str(&line).map_err(|e| DecodeError { message: line, error: e })?);
+        }
+        Ok(responses)
+    }
+
+    /// Appends a response, with any token in it (i.e. a registration's) redacted.
+    pub(crate) fn record(&self, method: &str, path: &str, status: u16, body: &str) -> Result<()> {
+        // kept as text if the server sent something other than JSON
+        let mut body = serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string()));
+        if let Some(token) = body.pointer_mut("/data/token") {
+            *token = Value::from(REDACTED);
+        }
+        let response = RecordedResponse {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body,
+        };
+        let mut line = serde_json::to_vec(&response).unwrap();
+        line.push(b'\n');
+        self.file.lock().unwrap().write_all(&line)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::drift::{diff, DriftKind, RecordedResponse, SchemaDrift, SessionRecorder};
+
+    #[test]
+    fn test_drift() {
+        let drift = SchemaDrift::new();
+        let market = json!({
+            "symbol": "X1-DF55-20250Z",
+            "exports": [],
+            "imports": [],
+            "exchange": [{ "symbol": "FUEL", "name": "Fuel", "description": "" }],
+            "transactions": [],
+            "tradeGoods": [
+                { "symbol": "FUEL", "tradeVolume": 100, "supply": "ABUNDANT", "activity": "STRONG", "purchasePrice": 70, "sellPrice": 68 },
+                { "symbol": "ICE_WATER", "tradeVolume": 100, "supply": "HIGH", "activity": "WEAK", "purchasePrice": 12, "sellPrice": 10 },
+            ],
+        });
+        for _ in 0..2 {
+            assert!(drift.check_recorded(&RecordedResponse {
+                method: String::from("GET"),
+                path: String::from("systems/X1-DF55/waypoints/X1-DF55-20250Z/market"),
+                status: 200,
+                body: json!({ "data": market }),
+            }));
+        }
+        assert!(drift.check_recorded(&RecordedResponse {
+            method: String::from("GET"),
+            path: String::from("systems/X1-DF55/waypoints/X1-DF55-20250Z/market"),
+            status: 200,
+            body: json!({ "data": { "symbol": "X1-DF55-20250Z" } }),
+        }));
+        assert!(!drift.check_recorded(&RecordedResponse {
+            method: String::from("GET"),
+            path: String::from("my/agent"),
+            status: 401,
+            body: json!({ "error": { "message": "no token", "code": 401 } }),
+        }));
+
+        let report = drift.report();
+        assert_eq!(report.checked.get("GET systems/:id/waypoints/:id/market"), Some(&3));
+        let fields: Vec<(&str, DriftKind, u64)> = report.fields.iter()
+            .map(|field| (field.field.as_str(), field.kind, field.seen))
+            .collect();
+        assert!(fields.contains(&("data.tradeGoods[].activity", DriftKind::Unknown, 2)), "{:?}", fields);
+        assert!(fields.contains(&("missing field `imports`", DriftKind::Invalid, 1)), "{:?}", fields);
+        // there's no `SupplyType` for HIGH
+        assert!(fields.contains(&("data.tradeGoods[].supply", DriftKind::UnknownSymbol, 2)), "{:?}", fields);
+        assert!(!fields.iter().any(|(field, _, _)| field.starts_with("data.exchange")));
+
+        let mut drifts = Vec::new();
+        diff("data", &json!({ "symbol": "X1-DF55" }), &json!({ "symbol": "X1-DF55", "chart": null }), &mut drifts);
+        assert_eq!(drifts, vec![(String::from("data.chart"), DriftKind::Missing)]);
+    }
+
+    #[test]
+    fn test_session_redacts_tokens() {
+        let path = std::env::temp_dir().join(format!("space-traders-session-{}.jsonl", std::process::id()));
+        let session = SessionRecorder::open(&path).unwrap();
+        session.record("POST", "register", 201, r#"{"data": {"token": "eyJhbGciOi", "agent": {"symbol": "BEAR"}}}"#).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let responses = SessionRecorder::read(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!contents.contains("eyJhbGciOi"));
+        assert_eq!(responses[0].body["data"]["token"], "REDACTED");
+        assert_eq!(responses[0].body["data"]["agent"]["symbol"], "BEAR");
+    }
+}
@@ -20,11 +20,40 @@ impl Display for DecodeError {
 
 impl error::Error for DecodeError {}
 
+/// An agent was registered, but its token couldn't be saved anywhere, and
+/// an agent can't be registered twice. The registration is only here now.
+pub struct UnsavedRegistration {
+    pub registration_data: Box<types::RegistrationData>,
+    pub error: Box<Error>,
+}
+
+impl std::fmt::Debug for UnsavedRegistration {
+    // without the token
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnsavedRegistration")
+            .field("agent", &self.registration_data.agent.symbol)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for UnsavedRegistration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "registered {} but could not save its token: {}", self.registration_data.agent.symbol, self.error)
+    }
+}
+
+impl error::Error for UnsavedRegistration {}
+
 #[derive(Debug)]
 pub enum Error {
     ApiError(types::ApiError),
     DecodeError(DecodeError),
     HttpError(reqwest::Error),
+    IoError(std::io::Error),
+    /// A behavior can't carry on, e.g. because there's nowhere to go.
+    BehaviorError(String),
+    UnsavedRegistration(UnsavedRegistration),
 }
 
 impl Display for Error {
@@ -33,6 +62,9 @@ impl Display for Error {
             Error::ApiError(e) => write!(f, "{}", e),
             Error::HttpError(e) => write!(f, "{}", e),
             Error::DecodeError(e) => write!(f, "{}", e),
+            Error::IoError(e) => write!(f, "{}", e),
+            Error::BehaviorError(message) => f.write_str(message),
+            Error::UnsavedRegistration(e) => write!(f, "{}", e),
         }
     }
 }
@@ -43,6 +75,9 @@ impl error::Error for Error {
             Error::ApiError(e) => Some(e),
             Error::HttpError(e) => Some(e),
             Error::DecodeError(e) => Some(e),
+            Error::IoError(e) => Some(e),
+            Error::BehaviorError(_) => None,
+            Error::UnsavedRegistration(e) => Some(e),
         }
     }
 }
@@ -64,3 +99,9 @@ impl From<reqwest::Error> for Error {
         Error::HttpError(e)
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::IoError(e)
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::behaviors::SURVEY_ERRORS;
+use crate::error::Error;
+use crate::{error, SpaceTradersClient, types};
+
+const EVENT_BUFFER: usize = 256;
+const RATE_LIMITED: u32 = 429;
+
+/// Something that happened in the game, as seen through an `EventClient`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Event {
+    #[serde(rename_all = "camelCase")]
+    ShipArrived {
+        ship_symbol: types::ShipSymbol,
+        waypoint_symbol: types::WaypointSymbol,
+        arrival: DateTime<Utc>,
+    },
+    CargoSold(types::MarketTransaction),
+    #[serde(rename_all = "camelCase")]
+    ContractFulfilled { contract_id: String, payment: u64 },
+    /// `change` is relative to the last balance the client saw.
+    #[serde(rename_all = "camelCase")]
+    CreditsChanged { credits: u64, change: i64 },
+    #[serde(rename_all = "camelCase")]
+    CooldownExpired { ship_symbol: types::ShipSymbol, expiration: DateTime<Utc> },
+    #[serde(rename_all = "camelCase")]
+    SurveyExhausted { signature: String, waypoint_symbol: types::WaypointSymbol },
+    /// The server turned a request away; `retry_after` is in seconds.
+    #[serde(rename_all = "camelCase")]
+    RateLimited { retry_after: Option<f64> },
+}
+
+/// A channel for events, with room for a slow subscriber to fall a little behind.
+pub fn channel() -> broadcast::Sender<Event> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Wraps a client, publishing an `Event` for what its responses say happened
+/// and keeping the game's `metrics` up to date.
+/// Arrivals and cooldowns are published when they're due, by the client's clock.
+#[derive(Debug)]
+pub struct EventClient<C: SpaceTradersClient> {
+    inner: Arc<C>,
+    events: broadcast::Sender<Event>,
+    credits: Mutex<Option<u64>>,
+}
+
+impl<C: SpaceTradersClient> EventClient<C> {
+    pub fn new(inner: Arc<C>, events: broadcast::Sender<Event>) -> Self {
+        inner.report_rate_limits(events.clone());
+        EventClient {
+            inner,
+            events,
+            credits: Mutex::new(None),
+        }
+    }
+
+    pub fn inner(&self) -> &Arc<C> {
+        &self.inner
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: Event) {
+        // nobody listening is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Publishes `event` at `time`, or now if that's already passed.
+    fn publish_at(&self, time: DateTime<Utc>, event: Event) {
+        let events = self.events.clone();
+        let delay = (time - self.inner.now()).to_std().unwrap_or_default();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = events.send(event);
+        });
+    }
+
+    /// Notes the agent's balance, publishing the change if it's moved since last time.
+    pub(crate) fn observe_agent(&self, agent: &types::Agent) {
+        crate::metrics::credits(agent.credits);
+        let previous = self.credits.lock().unwrap().replace(agent.credits);
+        match previous {
+            Some(previous) if previous != agent.credits => self.publish(Event::CreditsChanged {
+                credits: agent.credits,
+                change: agent.credits as i64 - previous as i64,
+            }),
+            _ => {}
+        }
+    }
+
+    fn observe_cooldown(&self, cooldown: &types::Cooldown) {
+        if let Some(expiration) = cooldown.expiration {
+            self.publish_at(expiration, Event::CooldownExpired { ship_symbol: cooldown.ship_symbol.clone(), expiration });
+        }
+    }
+
+    fn observe<T>(&self, result: error::Result<T>) -> error::Result<T> {
+        if let Err(Error::ApiError(e)) = &result {
+            if e.code == RATE_LIMITED {
+                let retry_after = e.data.as_ref().and_then(|data| data.get("retryAfter")).and_then(|r| r.as_f64());
+                self.publish(Event::RateLimited { retry_after });
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<C: SpaceTradersClient> SpaceTradersClient for EventClient<C> {
+    fn now(&self) -> DateTime<Utc> {
+        self.inner.now()
+    }
+
+    async fn get_agent(&self) -> error::Result<types::Agent> {
+        let agent = self.observe(self.inner.get_agent().await)?;
+        self.observe_agent(&agent);
+        Ok(agent)
+    }
+
+    async fn list_factions(&self) -> error::Result<Vec<types::Faction>> {
+        self.observe(self.inner.list_factions().await)
+    }
+
+    async fn list_contracts(&self) -> error::Result<Vec<types::Contract>> {
+        self.observe(self.inner.list_contracts().await)
+    }
+
+    async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract> {
+        self.observe(self.inner.get_contract(contract_id).await)
+    }
+
+    async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData> {
+        let data = self.observe(self.inner.accept_contract(contract_id).await)?;
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData> {
+        self.observe(self.inner.deliver_contract(contract_id, ship_symbol, trade_symbol, units).await)
+    }
+
+    async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData> {
+        let data = self.observe(self.inner.fulfill_contract(contract_id).await)?;
+        self.publish(Event::ContractFulfilled {
+            contract_id: data.contract.id.clone(),
+            payment: data.contract.total_payment(),
+        });
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract> {
+        self.observe(self.inner.negotiate_contract(ship_symbol).await)
+    }
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::System>> {
+        self.observe(self.inner.list_systems(page, limit).await)
+    }
+
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> error::Result<types::System> {
+        self.observe(self.inner.get_system(system_symbol).await)
+    }
+
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::Waypoint>> {
+        self.observe(self.inner.list_system_waypoints(system_symbol, page, limit).await)
+    }
+
+    async fn query_system_waypoints(&self, system_symbol: types::SystemSymbol, query: &types::WaypointQuery) -> error::Result<Vec<types::Waypoint>> {
+        self.observe(self.inner.query_system_waypoints(system_symbol, query).await)
+    }
+
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint> {
+        self.observe(self.inner.get_waypoint(waypoint_symbol).await)
+    }
+
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Market> {
+        self.observe(self.inner.get_market(waypoint_symbol).await)
+    }
+
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Shipyard> {
+        self.observe(self.inner.get_shipyard(waypoint_symbol).await)
+    }
+
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::JumpGate> {
+        self.observe(self.inner.get_jump_gate(waypoint_symbol).await)
+    }
+
+    async fn list_ships(&self) -> error::Result<Vec<types::Ship>> {
+        let ships = self.observe(self.inner.list_ships().await)?;
+        crate::metrics::ships(&ships);
+        Ok(ships)
+    }
+
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship> {
+        self.observe(self.inner.get_ship(ship_symbol).await)
+    }
+
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData> {
+        let data = self.observe(self.inner.purchase_ship(ship_type, waypoint_symbol).await)?;
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.observe(self.inner.orbit_ship(ship_symbol).await)
+    }
+
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.observe(self.inner.dock_ship(ship_symbol).await)
+    }
+
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData> {
+        let data = self.observe(self.inner.navigate_ship(ship_symbol.clone(), waypoint_symbol).await)?;
+        let route = &data.nav.route;
+        self.publish_at(route.arrival, Event::ShipArrived {
+            ship_symbol,
+            waypoint_symbol: route.destination.symbol.clone(),
+            arrival: route.arrival,
+        });
+        Ok(data)
+    }
+
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData> {
+        let data = self.observe(self.inner.refuel_ship(ship_symbol).await)?;
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>> {
+        self.observe(self.inner.get_ship_cooldown(ship_symbol).await)
+    }
+
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData> {
+        let data = self.observe(self.inner.create_survey(ship_symbol).await)?;
+        self.observe_cooldown(&data.cooldown);
+        Ok(data)
+    }
+
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> error::Result<types::ExtractData> {
+        let exhausted = survey.as_ref().map(|survey| Event::SurveyExhausted {
+            signature: survey.signature.clone(),
+            waypoint_symbol: survey.symbol.clone(),
+        });
+        let result = self.observe(self.inner.extract_resources(ship_symbol, survey).await);
+        match (&result, exhausted) {
+            (Ok(data), _) => {
+                crate::metrics::extracted(&data.extraction.extraction_yield);
+                self.observe_cooldown(&data.cooldown);
+            }
+            (Err(Error::ApiError(e)), Some(exhausted)) if SURVEY_ERRORS.contains(&e.code) => self.publish(exhausted),
+            _ => {}
+        }
+        result
+    }
+
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo> {
+        self.observe(self.inner.jettison_cargo(ship_symbol, symbol, units).await)
+    }
+
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData> {
+        let data = self.observe(self.inner.sell_cargo(ship_symbol, symbol, units).await)?;
+        crate::metrics::traded(&data.transaction);
+        self.publish(Event::CargoSold(data.transaction.clone()));
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData> {
+        let data = self.observe(self.inner.purchase_cargo(ship_symbol, symbol, units).await)?;
+        crate::metrics::traded(&data.transaction);
+        self.observe_agent(&data.agent);
+        Ok(data)
+    }
+}
@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::{error, SpaceTradersClient, types};
+
+/// Answers from canned values instead of the server. Endpoints it has no
+/// values for fail with a 404, as the server would for an unknown symbol.
+///
+/// Ships move, dock, mine and trade much as they would on the server, except
+/// that they arrive as soon as they set off and cooldowns never run out.
+#[derive(Debug)]
+pub struct FakeClient {
+    pub agent: types::Agent,
+    pub factions: Vec<types::Faction>,
+    pub waypoints: Vec<types::Waypoint>,
+    pub markets: Vec<types::Market>,
+    pub shipyards: Vec<types::Shipyard>,
+    pub ships: Mutex<Vec<types::Ship>>,
+    pub contracts: Mutex<Vec<types::Contract>>,
+    /// What every extraction yields.
+    pub extraction: Option<types::ExtractionYield>,
+    cooldowns: Mutex<HashMap<types::ShipSymbol, types::Cooldown>>,
+    failures: Mutex<Vec<(&'static str, u32)>>,
+}
+
+impl FakeClient {
+    /// An agent in X1-DF55, with one faction and no waypoints or markets yet.
+    pub fn new() -> Self {
+        FakeClient {
+            agent: serde_json::from_value(json!({
+                "accountId": "fake-account",
+                "symbol": "FAKE",
+                "headquarters": "X1-DF55-20250Z",
+                "credits": 150000,
+                "startingFaction": "COSMIC",
+            })).unwrap(),
+            factions: vec![serde_json::from_value(json!({
+                "symbol": "COSMIC",
+                "name": "Cosmic Engineers",
+                "description": "",
+                "headquarters": "X1-DF55-20250Z",
+                "traits": [],
+                "isRecruiting": true,
+            })).unwrap()],
+            waypoints: Vec::new(),
+            markets: Vec::new(),
+            shipyards: Vec::new(),
+            ships: Mutex::new(Vec::new()),
+            contracts: Mutex::new(Vec::new()),
+            extraction: None,
+            cooldowns: Mutex::new(HashMap::new()),
+            failures: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn waypoint(mut self, symbol: &str, waypoint_type: &str, traits: &[&str]) -> Self {
+        let symbol = types::WaypointSymbol::new(symbol).unwrap();
+        self.waypoints.push(serde_json::from_value(json!({
+            "symbol": symbol,
+            "type": waypoint_type,
+            "systemSymbol": symbol.system_symbol(),
+            "x": 0,
+            "y": 0,
+            "orbitals": [],
+            "traits": traits.iter()
+                .map(|t| json!({ "symbol": t, "name": t, "description": "" }))
+                .collect::<Vec<_>>(),
+        })).unwrap());
+        self
+    }
+
+    /// A market selling each good at `price` and buying it back for a little less.
+    pub fn market(mut self, symbol: &str, goods: &[(&str, u64)]) -> Self {
+        self.markets.push(serde_json::from_value(json!({
+            "symbol": symbol,
+            "imports": [],
+            "exports": [],
+            "exchange": goods.iter()
+                .map(|(good, _)| json!({ "symbol": good, "name": good, "description": "" }))
+                .collect::<Vec<_>>(),
+            "transactions": [],
+            "tradeGoods": goods.iter()
+                .map(|(good, price)| json!({
+                    "symbol": good,
+                    "tradeVolume": 100,
+                    "supply": "MODERATE",
+                    "purchasePrice": price,
+                    "sellPrice": price - price / 10,
+                }))
+                .collect::<Vec<_>>(),
+        })).unwrap());
+        self
+    }
+
+    /// A shipyard selling each type of ship at `price`, listed as if one of our ships was there.
+    pub fn shipyard(mut self, symbol: &str, ships: &[(&str, u64)]) -> Self {
+        self.shipyards.push(serde_json::from_value(json!({
+            "symbol": symbol,
+            "shipTypes": ships.iter().map(|(ship_type, _)| json!({ "type": ship_type })).collect::<Vec<_>>(),
+            "transactions": [],
+            "ships": ships.iter()
+                .map(|(ship_type, price)| {
+                    let mut ship = frigate();
+                    ship["type"] = json!(ship_type);
+                    ship["name"] = json!(ship_type);
+                    ship["description"] = json!("");
+                    ship["purchasePrice"] = json!(price);
+                    ship
+                })
+                .collect::<Vec<_>>(),
+            "modificationsFee": 0,
+        })).unwrap());
+        self
+    }
+
+    /// A command frigate at `waypoint`, with an empty hold and full tanks.
+    pub fn ship(self, symbol: &str, waypoint: &str, status: &str) -> Self {
+        let ship = new_ship(symbol, &types::WaypointSymbol::new(waypoint).unwrap(), status, "COMMAND");
+        self.ships.lock().unwrap().push(ship);
+        self
+    }
+
+    /// A procurement contract that hasn't been accepted yet.
+    pub fn contract(self, id: &str, good: &str, units: u64, destination: &str, payment: (u64, u64)) -> Self {
+        self.contracts.lock().unwrap().push(serde_json::from_value(json!({
+            "id": id,
+            "factionSymbol": "COSMIC",
+            "type": "PROCUREMENT",
+            "terms": {
+                "deadline": "2100-01-01T00:00:00Z",
+                "payment": { "onAccepted": payment.0, "onFulfilled": payment.1 },
+                "deliver": [{
+                    "tradeSymbol": good,
+                    "destinationSymbol": destination,
+                    "unitsRequired": units,
+                    "unitsFulfilled": 0,
+                }],
+            },
+            "accepted": false,
+            "fulfilled": false,
+            "expiration": "2100-01-01T00:00:00Z",
+        })).unwrap());
+        self
+    }
+
+    pub fn extracts(mut self, symbol: &str, units: u64) -> Self {
+        self.extraction = Some(types::ExtractionYield {
+            symbol: symbol.parse().unwrap(),
+            units,
+        });
+        self
+    }
+
+    /// Makes the next call to `endpoint` fail with the API error `code`.
+    pub fn fail(&self, endpoint: &'static str, code: u32) {
+        self.failures.lock().unwrap().push((endpoint, code));
+    }
+
+    fn check(&self, endpoint: &'static str) -> error::Result<()> {
+        let mut failures = self.failures.lock().unwrap();
+        match failures.iter().position(|(failing, _)| *failing == endpoint) {
+            Some(index) => {
+                let (_, code) = failures.remove(index);
+                Err(error::Error::ApiError(types::ApiError {
+                    message: format!("{} failed", endpoint),
+                    code,
+                    data: None,
+                }))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn with_contract<T>(&self, contract_id: &str, f: impl FnOnce(&mut types::Contract) -> error::Result<T>) -> error::Result<T> {
+        match self.contracts.lock().unwrap().iter_mut().find(|contract| contract.id == contract_id) {
+            Some(contract) => f(contract),
+            None => not_found(contract_id),
+        }
+    }
+
+    fn with_ship<T>(&self, ship_symbol: &types::ShipSymbol, f: impl FnOnce(&mut types::Ship) -> error::Result<T>) -> error::Result<T> {
+        match self.ships.lock().unwrap().iter_mut().find(|ship| ship.symbol == *ship_symbol) {
+            Some(ship) => f(ship),
+            None => not_found(ship_symbol),
+        }
+    }
+
+    fn trade(&self, ship: &types::Ship, symbol: &types::MarketGoodSymbol, trade_type: &str, units: u64) -> error::Result<types::MarketTransaction> {
+        let waypoint_symbol = &ship.nav.waypoint_symbol;
+        let Some(trade_good) = self.markets.iter()
+            .filter(|market| market.symbol == *waypoint_symbol)
+            .find_map(|market| market.trade_goods().iter().find(|trade_good| trade_good.symbol == *symbol)) else {
+            return not_found(symbol);
+        };
+
+        let price_per_unit = if trade_type == "SELL" { trade_good.sell_price } else { trade_good.purchase_price };
+        Ok(serde_json::from_value(json!({
+            "waypointSymbol": waypoint_symbol,
+            "shipSymbol": ship.symbol,
+            "tradeSymbol": symbol,
+            "type": trade_type,
+            "units": units,
+            "pricePerUnit": price_per_unit,
+            "totalPrice": price_per_unit * units,
+            "timestamp": self.now(),
+        })).unwrap())
+    }
+
+    fn systems(&self) -> Vec<types::System> {
+        let mut systems: Vec<types::System> = Vec::new();
+        for waypoint in self.waypoints.iter() {
+            match systems.iter_mut().find(|system| system.symbol == waypoint.system_symbol) {
+                Some(system) => system.waypoints.push(waypoint.reference.clone()),
+                None => systems.push(types::System {
+                    symbol: waypoint.system_symbol.clone(),
+                    sector_symbol: waypoint.system_symbol.sector_symbol(),
+                    system_type: types::SystemType::RedStar,
+                    x: 0,
+                    y: 0,
+                    waypoints: vec![waypoint.reference.clone()],
+                    factions: Vec::new(),
+                }),
+            }
+        }
+        systems
+    }
+}
+
+/// The frame, reactor, engine, modules and mounts of a frigate.
+fn frigate() -> serde_json::Value {
+    json!({
+        "frame": {
+            "symbol": "FRAME_FRIGATE", "name": "Frigate", "description": "", "moduleSlots": 8,
+            "mountingPoints": 5, "fuelCapacity": 1200, "requirements": {},
+        },
+        "reactor": {
+            "symbol": "REACTOR_FISSION_I", "name": "Fission Reactor I", "description": "", "powerOutput": 31,
+            "requirements": {},
+        },
+        "engine": { "symbol": "ENGINE_ION_DRIVE_II", "name": "Ion Drive II", "description": "", "speed": 30, "requirements": {} },
+        "modules": [],
+        "mounts": [],
+    })
+}
+
+/// A frigate at `waypoint`, with an empty hold and full tanks.
+fn new_ship(symbol: &str, waypoint: &types::WaypointSymbol, status: &str, role: &str) -> types::Ship {
+    let route_waypoint = json!({
+        "symbol": waypoint,
+        "type": "PLANET",
+        "systemSymbol": waypoint.system_symbol(),
+        "x": 0,
+        "y": 0,
+    });
+    let mut ship = frigate();
+    ship.as_object_mut().unwrap().extend(json!({
+        "symbol": symbol,
+        "registration": { "name": symbol, "factionSymbol": "COSMIC", "role": role },
+        "nav": {
+            "systemSymbol": waypoint.system_symbol(),
+            "waypointSymbol": waypoint,
+            "route": {
+                "destination": route_waypoint,
+                "departure": route_waypoint,
+                "departureTime": "2023-07-01T00:00:00Z",
+                "arrival": "2023-07-01T00:00:00Z",
+            },
+            "status": status,
+            "flightMode": "CRUISE",
+        },
+        "crew": { "current": 59, "required": 59, "capacity": 80, "rotation": "STRICT", "morale": 100, "wages": 0 },
+        "cargo": { "capacity": 60, "units": 0, "inventory": [] },
+        "fuel": { "current": 1200, "capacity": 1200 },
+    }).as_object().unwrap().clone());
+    serde_json::from_value(ship).unwrap()
+}
+
+fn load(cargo: &mut types::ShipCargo, symbol: &types::MarketGoodSymbol, units: u64) {
+    match cargo.inventory.iter_mut().find(|item| item.symbol == *symbol) {
+        Some(item) => item.units += units,
+        None => cargo.inventory.push(types::ShipCargoItem {
+            symbol: symbol.clone(),
+            name: symbol.to_string(),
+            description: String::new(),
+            units,
+        }),
+    }
+    cargo.units += units;
+}
+
+fn unload(cargo: &mut types::ShipCargo, symbol: &types::MarketGoodSymbol, units: u64) -> error::Result<()> {
+    let Some(item) = cargo.inventory.iter_mut().find(|item| item.symbol == *symbol && item.units >= units) else {
+        return not_found(symbol);
+    };
+    item.units -= units;
+    cargo.units -= units;
+    cargo.inventory.retain(|item| item.units > 0);
+    Ok(())
+}
+
+fn not_found<T>(what: impl std::fmt::Display) -> error::Result<T> {
+    Err(error::Error::ApiError(types::ApiError {
+        message: format!("{} not found", what),
+        code: 404,
+        data: None,
+    }))
+}
+
+#[async_trait]
+impl SpaceTradersClient for FakeClient {
+    async fn get_agent(&self) -> error::Result<types::Agent> {
+        Ok(self.agent.clone())
+    }
+
+    async fn list_factions(&self) -> error::Result<Vec<types::Faction>> {
+        Ok(self.factions.clone())
+    }
+
+    async fn list_contracts(&self) -> error::Result<Vec<types::Contract>> {
+        Ok(self.contracts.lock().unwrap().clone())
+    }
+
+    async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract> {
+        self.with_contract(contract_id, |contract| Ok(contract.clone()))
+    }
+
+    async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData> {
+        self.with_contract(contract_id, |contract| {
+            contract.accepted = true;
+            Ok(types::AcceptContractData { agent: self.agent.clone(), contract: contract.clone() })
+        })
+    }
+
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData> {
+        self.check("deliver_contract")?;
+        let cargo = self.with_ship(&ship_symbol, |ship| {
+            unload(&mut ship.cargo, &trade_symbol, units)?;
+            Ok(ship.cargo.clone())
+        })?;
+        self.with_contract(contract_id, |contract| {
+            let Some(delivery) = contract.terms.deliver.iter_mut().find(|delivery| delivery.trade_symbol == trade_symbol) else {
+                return not_found(&trade_symbol);
+            };
+            delivery.units_fulfilled += units;
+            Ok(types::DeliverContractData { contract: contract.clone(), cargo })
+        })
+    }
+
+    async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData> {
+        self.with_contract(contract_id, |contract| {
+            if !contract.is_delivered() {
+                return not_found(format!("delivered contract {}", contract_id));
+            }
+            contract.fulfilled = true;
+            Ok(types::FulfillContractData { agent: self.agent.clone(), contract: contract.clone() })
+        })
+    }
+
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract> {
+        not_found(ship_symbol)
+    }
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::System>> {
+        let limit = limit.unwrap_or(20);
+        let skip = (page.unwrap_or(1).max(1) as usize - 1) * limit;
+        Ok(self.systems().into_iter().skip(skip).take(limit).collect())
+    }
+
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> error::Result<types::System> {
+        match self.systems().into_iter().find(|system| system.symbol == system_symbol) {
+            Some(system) => Ok(system),
+            None => not_found(system_symbol),
+        }
+    }
+
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, _page: Option<u32>, _limit: Option<usize>) -> error::Result<Vec<types::Waypoint>> {
+        Ok(self.waypoints.iter().filter(|waypoint| waypoint.system_symbol == system_symbol).cloned().collect())
+    }
+
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint> {
+        match self.waypoints.iter().find(|waypoint| waypoint.reference.symbol == waypoint_symbol) {
+            Some(waypoint) => Ok(waypoint.clone()),
+            None => not_found(waypoint_symbol),
+        }
+    }
+
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Market> {
+        match self.markets.iter().find(|market| market.symbol == waypoint_symbol) {
+            Some(market) => Ok(market.clone()),
+            None => not_found(waypoint_symbol),
+        }
+    }
+
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Shipyard> {
+        match self.shipyards.iter().find(|shipyard| shipyard.symbol == waypoint_symbol) {
+            Some(shipyard) => Ok(shipyard.clone()),
+            None => not_found(waypoint_symbol),
+        }
+    }
+
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::JumpGate> {
+        not_found(waypoint_symbol)
+    }
+
+    async fn list_ships(&self) -> error::Result<Vec<types::Ship>> {
+        Ok(self.ships.lock().unwrap().clone())
+    }
+
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship> {
+        match self.ships.lock().unwrap().iter().find(|ship| ship.symbol == ship_symbol) {
+            Some(ship) => Ok(ship.clone()),
+            None => not_found(ship_symbol),
+        }
+    }
+
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData> {
+        let Some(price) = self.shipyards.iter()
+            .filter(|shipyard| shipyard.symbol == waypoint_symbol)
+            .find_map(|shipyard| shipyard.ships().iter().find(|ship| ship.ship_type == ship_type))
+            .map(|ship| ship.purchase_price) else {
+            return not_found(ship_type);
+        };
+
+        let mut ships = self.ships.lock().unwrap();
+        let ship = new_ship(&format!("{}-{:X}", self.agent.symbol, ships.len() + 1), &waypoint_symbol, "DOCKED", "EXCAVATOR");
+        ships.push(ship.clone());
+        Ok(types::PurchaseShipData {
+            agent: self.agent.clone(),
+            ship,
+            transaction: serde_json::from_value(json!({
+                "waypointSymbol": waypoint_symbol,
+                "shipSymbol": ship_type,
+                "price": price,
+                "agentSymbol": self.agent.symbol,
+                "timestamp": self.now(),
+            })).unwrap(),
+        })
+    }
+
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.with_ship(&ship_symbol, |ship| {
+            ship.nav.status = types::ShipNavStatus::InOrbit;
+            Ok(ship.nav.clone())
+        })
+    }
+
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.with_ship(&ship_symbol, |ship| {
+            ship.nav.status = types::ShipNavStatus::Docked;
+            Ok(ship.nav.clone())
+        })
+    }
+
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData> {
+        let Some(destination) = self.waypoints.iter().find(|waypoint| waypoint.reference.symbol == waypoint_symbol) else {
+            return not_found(waypoint_symbol);
+        };
+        let now = self.now();
+        self.with_ship(&ship_symbol, |ship| {
+            let route = &mut ship.nav.route;
+            route.departure = route.destination.clone();
+            route.destination = serde_json::from_value(json!({
+                "symbol": destination.reference.symbol,
+                "type": destination.reference.waypoint_type,
+                "systemSymbol": destination.system_symbol,
+                "x": destination.reference.x,
+                "y": destination.reference.y,
+            })).unwrap();
+            route.departure_time = now;
+            route.arrival = now;
+            ship.nav.waypoint_symbol = waypoint_symbol;
+            ship.nav.status = types::ShipNavStatus::InOrbit;
+
+            // it's already there, but the response is the one for setting off
+            let mut nav = ship.nav.clone();
+            nav.status = types::ShipNavStatus::InTransit;
+            Ok(types::NavigateData { fuel: ship.fuel.clone(), nav })
+        })
+    }
+
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData> {
+        self.check("refuel_ship")?;
+        self.with_ship(&ship_symbol, |ship| {
+            ship.fuel.current = ship.fuel.capacity;
+            Ok(types::RefuelData { agent: self.agent.clone(), fuel: ship.fuel.clone() })
+        })
+    }
+
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>> {
+        self.with_ship(&ship_symbol, |_| Ok(()))?;
+        Ok(self.cooldowns.lock().unwrap().get(&ship_symbol).cloned())
+    }
+
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData> {
+        not_found(ship_symbol)
+    }
+
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, _survey: Option<types::Survey>) -> error::Result<types::ExtractData> {
+        self.check("extract_resources")?;
+        let Some(extraction) = self.extraction.clone() else {
+            return not_found(ship_symbol);
+        };
+        let cooldown: types::Cooldown = serde_json::from_value(json!({
+            "shipSymbol": ship_symbol,
+            "totalSeconds": 70,
+            "remainingSeconds": 70,
+            "expiration": self.now() + chrono::Duration::seconds(70),
+        })).unwrap();
+        self.cooldowns.lock().unwrap().insert(ship_symbol.clone(), cooldown.clone());
+
+        self.with_ship(&ship_symbol, |ship| {
+            let units = extraction.units.min(ship.cargo.capacity - ship.cargo.units);
+            load(&mut ship.cargo, &extraction.symbol, units);
+            Ok(types::ExtractData {
+                cooldown,
+                extraction: types::Extraction {
+                    ship_symbol: ship_symbol.clone(),
+                    extraction_yield: types::ExtractionYield { symbol: extraction.symbol.clone(), units },
+                },
+                cargo: ship.cargo.clone(),
+            })
+        })
+    }
+
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo> {
+        self.with_ship(&ship_symbol, |ship| {
+            unload(&mut ship.cargo, &symbol, units)?;
+            Ok(ship.cargo.clone())
+        })
+    }
+
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData> {
+        self.check("sell_cargo")?;
+        self.with_ship(&ship_symbol, |ship| {
+            let transaction = self.trade(ship, &symbol, "SELL", units)?;
+            unload(&mut ship.cargo, &symbol, units)?;
+            Ok(types::SellCargoData { agent: self.agent.clone(), cargo: ship.cargo.clone(), transaction })
+        })
+    }
+
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData> {
+        self.check("purchase_cargo")?;
+        self.with_ship(&ship_symbol, |ship| {
+            let transaction = self.trade(ship, &symbol, "PURCHASE", units)?;
+            load(&mut ship.cargo, &symbol, units);
+            Ok(types::PurchaseCargoData { agent: self.agent.clone(), cargo: ship.cargo.clone(), transaction })
+        })
+    }
+}
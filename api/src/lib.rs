@@ -1,7 +1,22 @@
-pub use crate::api::SpaceTradersApi;
+pub use crate::api::{DownloadProgress, SpaceTradersApi};
+pub use crate::client::SpaceTradersClient;
+pub use crate::credentials::{credentials_path, fallback_credentials_path, find_credentials, find_saved_agents, load_credentials, save_credentials, saved_agents};
 pub use crate::manager::ApiManager;
+pub use crate::registry::{AgentAccount, AgentRegistry};
 
+pub mod behaviors;
+#[cfg(feature = "control")]
+pub mod control;
+pub mod drift;
 pub mod error;
+pub mod events;
+pub mod metrics;
+pub mod scheduler;
 pub mod types;
 mod api;
+mod client;
+mod credentials;
+#[cfg(test)]
+mod fake;
 mod manager;
+mod registry;
@@ -1,69 +1,201 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
-use crate::{error, SpaceTradersApi, types};
+use tokio::sync::broadcast;
+use crate::events::{self, Event, EventClient};
+use crate::{error, SpaceTradersApi, SpaceTradersClient, types};
 
 #[derive(Debug)]
-pub struct ApiManager {
-    pub api: SpaceTradersApi,
+pub struct ApiManager<C: SpaceTradersClient = SpaceTradersApi> {
+    pub api: Arc<C>,
+    client: Arc<EventClient<C>>,
     factions: HashMap<types::FactionSymbol, types::Faction>,
     user_agent: Option<types::Agent>,
+    contracts: Vec<types::Contract>,
+    ships: Vec<types::Ship>,
 }
 
 impl ApiManager {
+    /// Plays with `AUTH_TOKEN` if it's set, then with the token saved when
+    /// `call_sign` was registered, and otherwise registers it.
     pub async fn load_from_env(call_sign: &str, faction: types::FactionSymbol) -> error::Result<Self> {
-        let api = match std::env::var("AUTH_TOKEN") {
-            Ok(auth_token) => {
-                SpaceTradersApi::new(&auth_token)
-            }
-            Err(_) => {
-                SpaceTradersApi::register(call_sign, faction).await?
+        if let Ok(auth_token) = std::env::var("AUTH_TOKEN") {
+            return ApiManager::new(Arc::new(SpaceTradersApi::new(&auth_token))).await;
+        }
+
+        let api = match crate::find_credentials(call_sign)? {
+            Some((registration_data, _)) => SpaceTradersApi::new(&registration_data.token),
+            None => {
+                let (registration_data, _) = SpaceTradersApi::register_and_save(call_sign, faction).await?;
+                let api = SpaceTradersApi::new(&registration_data.token);
+                return ApiManager::seeded(Arc::new(api), registration_data).await;
             }
         };
 
+        ApiManager::new(Arc::new(api)).await
+    }
+}
+
+impl<C: SpaceTradersClient> ApiManager<C> {
+    /// Manages any client, e.g. a fake or a simulator, loading the agent,
+    /// factions, contracts and ships from it.
+    pub async fn new(api: Arc<C>) -> error::Result<Self> {
         let mut m = ApiManager {
+            client: Arc::new(EventClient::new(api.clone(), events::channel())),
             api,
             factions: HashMap::new(),
             user_agent: None,
+            contracts: Vec::new(),
+            ships: Vec::new(),
         };
 
         m.hydrate().await?;
         Ok(m)
     }
 
+    /// Manages a freshly registered agent, starting from the agent, contract
+    /// and command ship the server registered it with rather than fetching them.
+    pub async fn seeded(api: Arc<C>, registration_data: types::RegistrationData) -> error::Result<Self> {
+        let client = Arc::new(EventClient::new(api.clone(), events::channel()));
+        client.observe_agent(&registration_data.agent);
+        let mut m = ApiManager {
+            api,
+            client,
+            factions: HashMap::from([(registration_data.faction.symbol.clone(), registration_data.faction)]),
+            user_agent: Some(registration_data.agent),
+            contracts: vec![registration_data.contract],
+            ships: vec![registration_data.ship],
+        };
+
+        m.hydrate().await?;
+        Ok(m)
+    }
+
+    /// The manager's client, which publishes to `subscribe` as it goes. Calls
+    /// made straight on `api` aren't seen.
+    pub fn client(&self) -> Arc<EventClient<C>> {
+        self.client.clone()
+    }
+
+    /// Events from the manager's client, from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.client.subscribe()
+    }
+
+    pub fn agent(&self) -> Option<&types::Agent> {
+        self.user_agent.as_ref()
+    }
+
+    /// The contracts as of when the manager was made.
+    pub fn contracts(&self) -> &[types::Contract] {
+        &self.contracts
+    }
+
+    /// The ships as of when the manager was made.
+    pub fn ships(&self) -> &[types::Ship] {
+        &self.ships
+    }
+
+    pub fn command_ship(&self) -> Option<&types::Ship> {
+        self.ships.iter().find(|ship| ship.registration.role == types::ShipRole::Command)
+    }
+
     pub fn faction_symbols(&self) -> Vec<types::FactionSymbol> {
         self.factions.keys().cloned().collect()
     }
 
+    #[tracing::instrument(skip_all)]
     async fn hydrate(&mut self) -> error::Result<()> {
-        self.user_agent = Some(self.api.get_agent().await?);
+        if self.user_agent.is_none() {
+            self.user_agent = Some(self.client.get_agent().await?);
+        }
+        // whether or not the manager was seeded, it starts out knowing the same things
+        if self.contracts.is_empty() {
+            self.contracts = self.client.list_contracts().await?;
+        }
+        if self.ships.is_empty() {
+            self.ships = self.client.list_ships().await?;
+        }
 
-        for faction in self.api.list_factions().await?.into_iter() {
+        for faction in self.client.list_factions().await?.into_iter() {
             self.factions.insert(faction.symbol.clone(), faction);
         }
         Ok(())
     }
 
-    pub async fn find_waypoint_type(&self, limit: usize, p: fn(&types::Waypoint) -> bool) -> error::Result<Vec<types::WaypointSymbol>> {
+    /// Up to `limit` waypoints matching `query`, searching system by system.
+    #[tracing::instrument(skip(self, query))]
+    pub async fn find_waypoint_type(&self, limit: usize, query: types::WaypointQuery) -> error::Result<Vec<types::WaypointSymbol>> {
         let mut waypoints = Vec::<types::WaypointSymbol>::new();
 
         let mut page = 1;
         while waypoints.len() < limit {
-            for system in self.api.list_systems(Some(page), Some(20)).await? {
-                for waypoint in self.api.list_system_waypoints(system.symbol.clone(), None, None).await? {
-                    if p(&waypoint) {
-                        waypoints.push(waypoint.reference.symbol.clone())
-                    }
+            let systems = self.client.list_systems(Some(page), Some(20)).await?;
+            if systems.is_empty() {
+                break;
+            }
+            for system in systems {
+                for waypoint in self.client.query_system_waypoints(system.symbol.clone(), &query).await? {
+                    waypoints.push(waypoint.reference.symbol.clone());
 
                     if waypoints.len() >= limit {
                         return Ok(waypoints);
                     }
                 }
                 tokio::time::sleep(Duration::from_millis(500)).await;
-                println!("checked system {}, {} matching waypoints found", system.symbol, waypoints.len());
+                tracing::debug!(system = %system.symbol, found = waypoints.len(), "checked system");
             }
             page += 1;
         }
 
         Ok(waypoints)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::{ApiManager, types};
+    use crate::fake::FakeClient;
+    use crate::types::{WaypointQuery, WaypointTraitSymbol, WaypointType};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_find_waypoint_type() {
+        let fake = FakeClient::new()
+            .waypoint("X1-DF55-20250Z", "PLANET", &["MARKETPLACE"])
+            .waypoint("X1-DF55-69207D", "JUMP_GATE", &[])
+            .waypoint("X1-AB12-00001A", "ASTEROID_FIELD", &["MARKETPLACE"])
+            .ship("FAKE-1", "X1-DF55-20250Z", "DOCKED");
+        let manager = ApiManager::new(Arc::new(fake)).await.unwrap();
+
+        assert_eq!(manager.agent().unwrap().symbol, "FAKE");
+        assert_eq!(manager.command_ship().unwrap().symbol, "FAKE-1");
+        assert_eq!(manager.faction_symbols(), vec!["COSMIC"]);
+
+        let gates = manager.find_waypoint_type(5, WaypointQuery::new().of_type(WaypointType::JumpGate)).await.unwrap();
+        assert_eq!(gates.iter().map(|w| w.to_string()).collect::<Vec<_>>(), vec!["X1-DF55-69207D"]);
+        let markets = manager.find_waypoint_type(1, WaypointQuery::new().with_trait(WaypointTraitSymbol::Marketplace)).await.unwrap();
+        assert_eq!(markets.len(), 1);
+
+        // the filter only narrows what the server's filters return
+        let query = WaypointQuery::new()
+            .with_trait(WaypointTraitSymbol::Marketplace)
+            .of_type(WaypointType::AsteroidField)
+            .filter(|w| w.reference.x == 0);
+        assert_eq!(query.query_string(), "traits=MARKETPLACE&type=ASTEROID_FIELD");
+        let fields = manager.find_waypoint_type(5, query).await.unwrap();
+        assert_eq!(fields.iter().map(|w| w.to_string()).collect::<Vec<_>>(), vec!["X1-AB12-00001A"]);
+    }
+
+    #[tokio::test]
+    async fn test_seeded() {
+        let response: types::ApiSuccess<types::RegistrationData> = serde_json::from_str(include_str!("../tests/fixtures/register.json")).unwrap();
+        let manager = ApiManager::seeded(Arc::new(FakeClient::new()), response.data).await.unwrap();
+
+        // the registered agent wins over the fake's, and the faction list adds to the starting faction
+        assert_eq!(manager.agent().unwrap().symbol, "BEAR");
+        assert_eq!(manager.faction_symbols().len(), 1);
+        assert_eq!(manager.contracts()[0].terms.deliver[0].trade_symbol, "ALUMINUM_ORE");
+        assert_eq!(manager.command_ship().unwrap().symbol, "BEAR-1");
+    }
+}
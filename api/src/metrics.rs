@@ -0,0 +1,100 @@
+//! Counters, gauges and histograms for whichever `metrics` recorder the
+//! program installs, e.g. a Prometheus exporter. Without one they cost nothing.
+
+use std::time::Duration;
+
+use ::metrics::{counter, decrement_gauge, describe_counter, describe_gauge, describe_histogram, gauge, histogram, increment_counter, increment_gauge, Unit};
+
+use crate::scheduler::Priority;
+use crate::types;
+
+const REQUESTS: &str = "space_traders_requests_total";
+const REQUEST_DURATION: &str = "space_traders_request_duration_seconds";
+const RATE_LIMITED: &str = "space_traders_rate_limited_total";
+const SCHEDULER_WAIT: &str = "space_traders_scheduler_wait_seconds";
+const CREDITS: &str = "space_traders_credits";
+const SHIPS: &str = "space_traders_ships";
+const EXTRACTED: &str = "space_traders_extracted_units_total";
+const TRADE_PROFIT: &str = "space_traders_trade_profit_credits";
+
+// path segments that are followed by a symbol or id
+const COLLECTIONS: [&str; 5] = ["ships", "systems", "waypoints", "contracts", "factions"];
+
+/// Describes every metric to the installed recorder, so it can give them help text and units.
+pub fn describe() {
+    describe_counter!(REQUESTS, "Requests made to the API, by method, endpoint and status");
+    describe_histogram!(REQUEST_DURATION, Unit::Seconds, "How long API requests took, by method and endpoint");
+    describe_counter!(RATE_LIMITED, "Requests the API turned away for going over the rate limit");
+    describe_histogram!(SCHEDULER_WAIT, Unit::Seconds, "How long requests waited for a turn from the scheduler, by priority");
+    describe_gauge!(CREDITS, "The agent's credits, as last seen");
+    describe_gauge!(SHIPS, "Ships by navigation status, as of the last fleet listing");
+    describe_counter!(EXTRACTED, Unit::Count, "Units extracted, by good");
+    describe_gauge!(TRADE_PROFIT, "Credits from selling cargo, less credits spent buying it");
+}
+
+pub(crate) fn request(method: &reqwest::Method, path: &str, status: u16, elapsed: Duration) {
+    let endpoint = endpoint(path);
+    increment_counter!(REQUESTS, "method" => method.to_string(), "endpoint" => endpoint.clone(), "status" => status.to_string());
+    histogram!(REQUEST_DURATION, elapsed.as_secs_f64(), "method" => method.to_string(), "endpoint" => endpoint);
+}
+
+pub(crate) fn rate_limited() {
+    increment_counter!(RATE_LIMITED);
+}
+
+pub(crate) fn scheduler_wait(priority: Priority, waited: Duration) {
+    histogram!(SCHEDULER_WAIT, waited.as_secs_f64(), "priority" => format!("{:?}", priority));
+}
+
+pub(crate) fn credits(credits: u64) {
+    gauge!(CREDITS, credits as f64);
+}
+
+pub(crate) fn ships(ships: &[types::Ship]) {
+    for (status, label) in [
+        (types::ShipNavStatus::InTransit, "IN_TRANSIT"),
+        (types::ShipNavStatus::InOrbit, "IN_ORBIT"),
+        (types::ShipNavStatus::Docked, "DOCKED"),
+    ] {
+        let count = ships.iter().filter(|ship| ship.nav.status == status).count();
+        gauge!(SHIPS, count as f64, "status" => label);
+    }
+}
+
+pub(crate) fn extracted(extraction_yield: &types::ExtractionYield) {
+    counter!(EXTRACTED, extraction_yield.units, "good" => extraction_yield.symbol.to_string());
+}
+
+pub(crate) fn traded(transaction: &types::MarketTransaction) {
+    match transaction.trade_type {
+        types::TradeType::Sell => increment_gauge!(TRADE_PROFIT, transaction.total_price as f64),
+        types::TradeType::Purchase => decrement_gauge!(TRADE_PROFIT, transaction.total_price as f64),
+        types::TradeType::Unknown(_) => {}
+    }
+}
+
+/// The path with its symbols and ids taken out, so that there's one label
+/// per endpoint rather than per ship, e.g. `my/ships/:id/navigate`.
+pub(crate) fn endpoint(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or_default();
+    let mut segments: Vec<&str> = path.split('/').collect();
+    for i in 1..segments.len() {
+        if COLLECTIONS.contains(&segments[i - 1]) {
+            segments[i] = ":id";
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::endpoint;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("my/ships/BEAR-1/navigate"), "my/ships/:id/navigate");
+        assert_eq!(endpoint("systems/X1-DF55/waypoints/X1-DF55-20250Z/market"), "systems/:id/waypoints/:id/market");
+        assert_eq!(endpoint("my/contracts?page=2&limit=20"), "my/contracts");
+        assert_eq!(endpoint("my/agent"), "my/agent");
+    }
+}
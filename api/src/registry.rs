@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::write_private;
+use crate::error::{DecodeError, Result};
+use crate::{SpaceTradersApi, types};
+
+/// An agent we hold the token for, and the reset it was registered in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentAccount {
+    pub symbol: String,
+    pub faction: types::FactionSymbol,
+    pub token: String,
+    pub reset_date: NaiveDate,
+}
+
+/// Every agent we run, kept in a JSON config file. Tokens only last until
+/// the server resets, so each account remembers which reset it belongs to.
+#[derive(Debug)]
+pub struct AgentRegistry {
+    path: PathBuf,
+    accounts: Vec<AgentAccount>,
+}
+
+impl AgentRegistry {
+    /// Opens the file at `path`, starting empty if it doesn't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let accounts = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| DecodeError {
+                message: path.display().to_string(),
+                error: e,
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(AgentRegistry {
+            path,
+            accounts,
+        })
+    }
+
+    pub fn accounts(&self) -> &[AgentAccount] {
+        &self.accounts
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&AgentAccount> {
+        self.accounts.iter().find(|account| account.symbol == symbol)
+    }
+
+    /// A client playing as `symbol`, if we have its token.
+    pub fn api(&self, symbol: &str) -> Option<SpaceTradersApi> {
+        self.get(symbol).map(|account| SpaceTradersApi::new(&account.token))
+    }
+
+    /// A client for every agent registered in the reset of `reset_date`.
+    pub fn apis(&self, reset_date: NaiveDate) -> Vec<(String, SpaceTradersApi)> {
+        self.accounts.iter()
+            .filter(|account| account.reset_date == reset_date)
+            .map(|account| (account.symbol.clone(), SpaceTradersApi::new(&account.token)))
+            .collect()
+    }
+
+    /// Accounts from before the reset of `reset_date`, whose tokens no longer work.
+    pub fn stale(&self, reset_date: NaiveDate) -> Vec<&AgentAccount> {
+        self.accounts.iter().filter(|account| account.reset_date < reset_date).collect()
+    }
+
+    /// Adds the account, replacing any other under the same symbol, and saves.
+    pub fn add(&mut self, account: AgentAccount) -> Result<()> {
+        self.accounts.retain(|a| a.symbol != account.symbol);
+        self.accounts.push(account);
+        self.save()
+    }
+
+    /// Registers a new agent and keeps its token.
+    pub async fn register(&mut self, call_sign: &str, faction: types::FactionSymbol, reset_date: NaiveDate) -> Result<SpaceTradersApi> {
+        let registration_data = SpaceTradersApi::register_agent(call_sign, faction.clone()).await?;
+        let api = SpaceTradersApi::new(&registration_data.token);
+        self.add(AgentAccount {
+            symbol: registration_data.agent.symbol,
+            faction,
+            token: registration_data.token,
+            reset_date,
+        })?;
+        Ok(api)
+    }
+
+    /// Registers each of `agents`, carrying on past the ones that fail, e.g.
+    /// because the call sign is taken.
+    pub async fn register_batch(&mut self, agents: &[(&str, types::FactionSymbol)], reset_date: NaiveDate) -> Vec<(String, Result<SpaceTradersApi>)> {
+        let mut results = Vec::new();
+        for (call_sign, faction) in agents.iter() {
+            if !results.is_empty() {
+                // registering shares the rate limit of 2 requests a second
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            let result = self.register(call_sign, faction.clone(), reset_date).await;
+            results.push((call_sign.to_string(), result));
+        }
+        results
+    }
+
+    /// Registers every stale account again under the same symbol and faction.
+    pub async fn reregister(&mut self, reset_date: NaiveDate) -> Vec<(String, Result<SpaceTradersApi>)> {
+        let stale: Vec<(String, types::FactionSymbol)> = self.stale(reset_date).into_iter()
+            .map(|account| (account.symbol.clone(), account.faction.clone()))
+            .collect();
+        let agents: Vec<(&str, types::FactionSymbol)> = stale.iter()
+            .map(|(symbol, faction)| (symbol.as_str(), faction.clone()))
+            .collect();
+        self.register_batch(&agents, reset_date).await
+    }
+
+    fn save(&self) -> Result<()> {
+        write_private(&self.path, &serde_json::to_vec_pretty(&self.accounts).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crate::{AgentAccount, AgentRegistry, types};
+
+    fn account(symbol: &str, reset_date: NaiveDate) -> AgentAccount {
+        AgentAccount {
+            symbol: String::from(symbol),
+            faction: types::FactionSymbol::Cosmic,
+            token: format!("token-{}", symbol),
+            reset_date,
+        }
+    }
+
+    #[test]
+    fn test_registry_resumes() {
+        let path = std::env::temp_dir().join(format!("space-traders-agents-{}.json", std::process::id()));
+        let last_reset = NaiveDate::from_ymd_opt(2023, 6, 24).unwrap();
+        let reset = NaiveDate::from_ymd_opt(2023, 7, 8).unwrap();
+
+        let mut registry = AgentRegistry::open(&path).unwrap();
+        registry.add(account("BEAR", last_reset)).unwrap();
+        registry.add(account("WOLF", reset)).unwrap();
+        registry.add(account("BEAR", reset)).unwrap();
+        registry.add(account("OWL", last_reset)).unwrap();
+
+        let reopened = AgentRegistry::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.accounts().len(), 3);
+        assert_eq!(reopened.get("BEAR"), Some(&account("BEAR", reset)));
+        assert!(reopened.api("FOX").is_none());
+        assert_eq!(reopened.apis(reset).len(), 2);
+        assert_eq!(reopened.stale(reset), vec![&account("OWL", last_reset)]);
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::events::Event;
+use crate::{error, SpaceTradersClient, types};
+
+/// How urgently a request should go out. Higher priorities always go first;
+/// within a priority, owners take turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Market scans, scouting polls and anything else that can wait.
+    Background,
+    /// Reads a ship needs to make its next decision.
+    Normal,
+    /// Buying and selling cargo.
+    Trade,
+    /// Navigation and jumps that a schedule depends on.
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        // the server allows 2 requests per second, plus a burst pool of 10
+        RateLimit {
+            per_second: 2.0,
+            burst: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SchedulerMetrics {
+    pub queue_depth: usize,
+    pub depth_by_priority: BTreeMap<Priority, usize>,
+    pub depth_by_owner: HashMap<String, usize>,
+    pub granted: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+/// Hands out turns at the API's rate limit to everything sharing one token.
+/// Cloning gives another handle to the same queue.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    requests: mpsc::UnboundedSender<Waiter>,
+    metrics: Arc<Mutex<SchedulerMetrics>>,
+}
+
+impl Scheduler {
+    /// Starts the dispatcher, which runs until every handle is dropped.
+    pub fn new(limit: RateLimit) -> Self {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let metrics = Arc::new(Mutex::new(SchedulerMetrics::default()));
+        tokio::spawn(dispatch(rx, TokenBucket::new(limit), metrics.clone()));
+
+        Scheduler {
+            requests,
+            metrics,
+        }
+    }
+
+    /// Waits for a turn to make one request on behalf of `owner`, usually a ship symbol.
+    pub async fn acquire(&self, owner: &str, priority: Priority) {
+        let (grant, granted) = oneshot::channel();
+        let waiter = Waiter {
+            owner: owner.to_string(),
+            priority,
+            queued_at: Instant::now(),
+            grant,
+        };
+
+        // the dispatcher only stops once every handle, including this one, is gone
+        if self.requests.send(waiter).is_ok() {
+            let _ = granted.await;
+        }
+    }
+
+    pub fn metrics(&self) -> SchedulerMetrics {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.metrics.lock().unwrap().queue_depth
+    }
+}
+
+/// A client that waits for its owner's turn before every request, so nothing
+/// sent through it can skip the queue. Trades wait at `Priority::Trade` and
+/// navigation at `Priority::Critical`; everything else at `Priority::Normal`
+/// unless `at` says otherwise.
+#[derive(Debug, Clone)]
+pub struct ScheduledClient {
+    inner: Arc<dyn SpaceTradersClient>,
+    scheduler: Scheduler,
+    owner: String,
+    priority: Option<Priority>,
+}
+
+impl ScheduledClient {
+    pub fn new(inner: Arc<dyn SpaceTradersClient>, scheduler: Scheduler, owner: &str) -> Self {
+        ScheduledClient {
+            inner,
+            scheduler,
+            owner: owner.to_string(),
+            priority: None,
+        }
+    }
+
+    /// The same client, with every request waiting at `priority` instead.
+    pub fn at(&self, priority: Priority) -> Self {
+        ScheduledClient {
+            priority: Some(priority),
+            ..self.clone()
+        }
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    async fn turn(&self, priority: Priority) {
+        self.scheduler.acquire(&self.owner, self.priority.unwrap_or(priority)).await
+    }
+}
+
+#[async_trait]
+impl SpaceTradersClient for ScheduledClient {
+    fn now(&self) -> DateTime<Utc> {
+        self.inner.now()
+    }
+
+    fn report_rate_limits(&self, events: broadcast::Sender<Event>) {
+        self.inner.report_rate_limits(events);
+    }
+
+    async fn get_agent(&self) -> error::Result<types::Agent> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_agent().await
+    }
+
+    async fn list_factions(&self) -> error::Result<Vec<types::Faction>> {
+        self.turn(Priority::Normal).await;
+        self.inner.list_factions().await
+    }
+
+    async fn list_contracts(&self) -> error::Result<Vec<types::Contract>> {
+        self.turn(Priority::Normal).await;
+        self.inner.list_contracts().await
+    }
+
+    async fn get_contract(&self, contract_id: &str) -> error::Result<types::Contract> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_contract(contract_id).await
+    }
+
+    async fn accept_contract(&self, contract_id: &str) -> error::Result<types::AcceptContractData> {
+        self.turn(Priority::Normal).await;
+        self.inner.accept_contract(contract_id).await
+    }
+
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::DeliverContractData> {
+        self.turn(Priority::Trade).await;
+        self.inner.deliver_contract(contract_id, ship_symbol, trade_symbol, units).await
+    }
+
+    async fn fulfill_contract(&self, contract_id: &str) -> error::Result<types::FulfillContractData> {
+        self.turn(Priority::Trade).await;
+        self.inner.fulfill_contract(contract_id).await
+    }
+
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Contract> {
+        self.turn(Priority::Normal).await;
+        self.inner.negotiate_contract(ship_symbol).await
+    }
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::System>> {
+        self.turn(Priority::Normal).await;
+        self.inner.list_systems(page, limit).await
+    }
+
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> error::Result<types::System> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_system(system_symbol).await
+    }
+
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, page: Option<u32>, limit: Option<usize>) -> error::Result<Vec<types::Waypoint>> {
+        self.turn(Priority::Normal).await;
+        self.inner.list_system_waypoints(system_symbol, page, limit).await
+    }
+
+    async fn query_system_waypoints(&self, system_symbol: types::SystemSymbol, query: &types::WaypointQuery) -> error::Result<Vec<types::Waypoint>> {
+        self.turn(Priority::Normal).await;
+        self.inner.query_system_waypoints(system_symbol, query).await
+    }
+
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Waypoint> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_waypoint(waypoint_symbol).await
+    }
+
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Market> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_market(waypoint_symbol).await
+    }
+
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::Shipyard> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_shipyard(waypoint_symbol).await
+    }
+
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::JumpGate> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_jump_gate(waypoint_symbol).await
+    }
+
+    async fn list_ships(&self) -> error::Result<Vec<types::Ship>> {
+        self.turn(Priority::Normal).await;
+        self.inner.list_ships().await
+    }
+
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::Ship> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_ship(ship_symbol).await
+    }
+
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::PurchaseShipData> {
+        self.turn(Priority::Trade).await;
+        self.inner.purchase_ship(ship_type, waypoint_symbol).await
+    }
+
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.turn(Priority::Normal).await;
+        self.inner.orbit_ship(ship_symbol).await
+    }
+
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::ShipNav> {
+        self.turn(Priority::Normal).await;
+        self.inner.dock_ship(ship_symbol).await
+    }
+
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> error::Result<types::NavigateData> {
+        self.turn(Priority::Critical).await;
+        self.inner.navigate_ship(ship_symbol, waypoint_symbol).await
+    }
+
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::RefuelData> {
+        self.turn(Priority::Normal).await;
+        self.inner.refuel_ship(ship_symbol).await
+    }
+
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> error::Result<Option<types::Cooldown>> {
+        self.turn(Priority::Normal).await;
+        self.inner.get_ship_cooldown(ship_symbol).await
+    }
+
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> error::Result<types::CreateSurveyData> {
+        self.turn(Priority::Normal).await;
+        self.inner.create_survey(ship_symbol).await
+    }
+
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> error::Result<types::ExtractData> {
+        self.turn(Priority::Normal).await;
+        self.inner.extract_resources(ship_symbol, survey).await
+    }
+
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::ShipCargo> {
+        self.turn(Priority::Normal).await;
+        self.inner.jettison_cargo(ship_symbol, symbol, units).await
+    }
+
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::SellCargoData> {
+        self.turn(Priority::Trade).await;
+        self.inner.sell_cargo(ship_symbol, symbol, units).await
+    }
+
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> error::Result<types::PurchaseCargoData> {
+        self.turn(Priority::Trade).await;
+        self.inner.purchase_cargo(ship_symbol, symbol, units).await
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    owner: String,
+    priority: Priority,
+    queued_at: Instant,
+    grant: oneshot::Sender<()>,
+}
+
+/// Waiters of one priority, with owners served round-robin.
+#[derive(Debug, Default)]
+struct Lane {
+    turns: VecDeque<String>,
+    waiting: HashMap<String, VecDeque<Waiter>>,
+}
+
+impl Lane {
+    fn push(&mut self, waiter: Waiter) {
+        let queue = self.waiting.entry(waiter.owner.clone()).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(waiter.owner.clone());
+        }
+        queue.push_back(waiter);
+    }
+
+    fn pop(&mut self) -> Option<Waiter> {
+        let owner = self.turns.pop_front()?;
+        let queue = self.waiting.get_mut(&owner)?;
+        let waiter = queue.pop_front();
+
+        if queue.is_empty() {
+            self.waiting.remove(&owner);
+        } else {
+            self.turns.push_back(owner);
+        }
+        waiter
+    }
+}
+
+// while background work is waiting, at least one turn in this many goes to it
+const BACKGROUND_SHARE: u32 = 10;
+
+#[derive(Debug, Default)]
+struct Queue {
+    lanes: BTreeMap<Priority, Lane>,
+    /// Turns granted ahead of waiting background work since it last had one.
+    background_skipped: u32,
+}
+
+impl Queue {
+    fn push(&mut self, waiter: Waiter) {
+        self.lanes.entry(waiter.priority).or_default().push(waiter);
+    }
+
+    fn pop(&mut self) -> Option<Waiter> {
+        let background = self.lanes.get_mut(&Priority::Background);
+        let waiting = background.is_some();
+        let waiter = background
+            .filter(|_| self.background_skipped + 1 >= BACKGROUND_SHARE)
+            .and_then(Lane::pop)
+            .or_else(|| self.lanes.values_mut().rev().find_map(|lane| lane.pop()));
+
+        match &waiter {
+            Some(waiter) if waiting && waiter.priority != Priority::Background => self.background_skipped += 1,
+            _ => self.background_skipped = 0,
+        }
+        self.lanes.retain(|_, lane| !lane.turns.is_empty());
+        waiter
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lanes.is_empty()
+    }
+
+    fn record_depth(&self, metrics: &mut SchedulerMetrics) {
+        metrics.depth_by_priority.clear();
+        metrics.depth_by_owner.clear();
+        for (priority, lane) in &self.lanes {
+            for (owner, waiting) in &lane.waiting {
+                *metrics.depth_by_priority.entry(*priority).or_default() += waiting.len();
+                *metrics.depth_by_owner.entry(owner.clone()).or_default() += waiting.len();
+            }
+        }
+        metrics.queue_depth = metrics.depth_by_priority.values().sum();
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled_at = now;
+    }
+
+    async fn take(&mut self) {
+        loop {
+            self.refill();
+            if self.tokens >= 1.0 {
+                self.tokens -= 1.0;
+                return;
+            }
+            tokio::time::sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.limit.per_second)).await;
+        }
+    }
+
+    fn give_back(&mut self) {
+        self.tokens += 1.0;
+    }
+}
+
+async fn dispatch(mut requests: mpsc::UnboundedReceiver<Waiter>, mut bucket: TokenBucket, metrics: Arc<Mutex<SchedulerMetrics>>) {
+    let mut queue = Queue::default();
+
+    loop {
+        if queue.is_empty() {
+            match requests.recv().await {
+                Some(waiter) => queue.push(waiter),
+                None => return,
+            }
+        }
+        bucket.take().await;
+
+        // anything that arrived while waiting for a token competes for it too
+        while let Ok(waiter) = requests.try_recv() {
+            queue.push(waiter);
+        }
+
+        let mut granted = false;
+        while let Some(waiter) = queue.pop() {
+            let waited = waiter.queued_at.elapsed();
+            // a dropped receiver means the caller gave up, so the turn goes to the next one
+            if waiter.grant.send(()).is_ok() {
+                let mut metrics = metrics.lock().unwrap();
+                metrics.granted += 1;
+                metrics.total_wait += waited;
+                metrics.max_wait = metrics.max_wait.max(waited);
+                crate::metrics::scheduler_wait(waiter.priority, waited);
+                granted = true;
+                break;
+            }
+        }
+        if !granted {
+            bucket.give_back();
+        }
+
+        queue.record_depth(&mut metrics.lock().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::scheduler::{Priority, RateLimit, Scheduler};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_priority_then_fair_share() {
+        let scheduler = Scheduler::new(RateLimit { per_second: 1.0, burst: 1 });
+        scheduler.acquire("warmup", Priority::Normal).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (owner, priority) in [
+            ("PROBE", Priority::Background),
+            ("HAULER-1", Priority::Trade),
+            ("HAULER-1", Priority::Trade),
+            ("HAULER-2", Priority::Trade),
+            ("MINER", Priority::Critical),
+        ] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                scheduler.acquire(owner, priority).await;
+                order.lock().unwrap().push(owner);
+            }));
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["MINER", "HAULER-1", "HAULER-2", "HAULER-1", "PROBE"]);
+
+        let metrics = scheduler.metrics();
+        assert_eq!(metrics.granted, 6);
+        assert_eq!(metrics.queue_depth, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_background_share() {
+        let scheduler = Scheduler::new(RateLimit { per_second: 1.0, burst: 1 });
+        scheduler.acquire("warmup", Priority::Normal).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        let waiters = std::iter::once(("PROBE", Priority::Background))
+            .chain(std::iter::repeat_n(("HAULER", Priority::Trade), 15));
+        for (owner, priority) in waiters {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                scheduler.acquire(owner, priority).await;
+                order.lock().unwrap().push(owner);
+            }));
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // busy as the hauler keeps it, the probe still gets the tenth turn
+        assert_eq!(order.lock().unwrap().iter().position(|owner| *owner == "PROBE"), Some(9));
+    }
+}
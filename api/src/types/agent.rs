@@ -2,11 +2,12 @@ use serde::{Serialize, Deserialize};
 
 pub type AgentSymbol = String;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Agent {
     pub account_id: String,
     pub symbol: String,
     pub headquarters: String,
     pub credits: u64,
+    pub starting_faction: String,
 }
@@ -1,7 +1,7 @@
 use std::error;
 use std::fmt::{Display, Formatter};
 use serde::{Serialize, Deserialize};
-use crate::types::{Agent, FactionSymbol};
+use crate::types::{Agent, Contract, Faction, FactionSymbol, Ship};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -54,9 +54,12 @@ pub struct RegistrationRequest {
     pub faction: FactionSymbol,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegistrationData {
     pub token: String,
     pub agent: Agent,
+    pub contract: Contract,
+    pub faction: Faction,
+    pub ship: Ship,
 }
 
@@ -0,0 +1,275 @@
+//! Checks the types against the models in the vendored OpenAPI spec,
+//! `spec/SpaceTraders.json`. Each object schema gets a sample built from the
+//! spec, which its type has to decode and encode again with the same fields,
+//! decode without any one of its optional properties, and fail to decode
+//! without any one of its required ones. Each enum schema has to match its
+//! enum's variants.
+
+use serde_json::{Map, Value};
+
+use crate::drift::{diff, retyped, DriftKind};
+use crate::types::*;
+
+const SPEC: &str = include_str!("../../spec/SpaceTraders.json");
+
+const TIMESTAMP: &str = "2023-07-01T00:00:00Z";
+const SECTOR: &str = "X1";
+const SYSTEM: &str = "X1-DF55";
+const WAYPOINT: &str = "X1-DF55-20250Z";
+const SHIP: &str = "BEAR-1";
+
+type Retype = fn(&Value) -> serde_json::Result<Value>;
+
+/// The type each object schema decodes as, by its path from `components/schemas`.
+fn model(path: &str) -> Option<Retype> {
+    Some(match path {
+        "Agent" => retyped::<Agent>,
+        "Chart" => retyped::<Chart>,
+        "ConnectedSystem" => retyped::<ConnectedSystem>,
+        "Contract" => retyped::<Contract>,
+        "ContractDeliverGood" => retyped::<ContractDeliverGood>,
+        "ContractPayment" => retyped::<ContractPayment>,
+        "ContractTerms" => retyped::<ContractTerms>,
+        "Cooldown" => retyped::<Cooldown>,
+        "Extraction" => retyped::<Extraction>,
+        "ExtractionYield" => retyped::<ExtractionYield>,
+        "Faction" => retyped::<Faction>,
+        "FactionTrait" => retyped::<FactionTrait>,
+        "JumpGate" => retyped::<JumpGate>,
+        "Market" => retyped::<Market>,
+        "MarketTradeGood" => retyped::<MarketTradeGood>,
+        "MarketTransaction" => retyped::<MarketTransaction>,
+        "Ship" => retyped::<Ship>,
+        "ShipCargo" => retyped::<ShipCargo>,
+        "ShipCargoItem" => retyped::<ShipCargoItem>,
+        "ShipCrew" => retyped::<ShipCrew>,
+        "ShipEngine" => retyped::<ShipEngine>,
+        "ShipFrame" => retyped::<ShipFrame>,
+        "ShipFuel" => retyped::<ShipFuel>,
+        "ShipFuel/consumed" => retyped::<ShipFuelConsumed>,
+        "ShipModule" => retyped::<ShipModule>,
+        "ShipMount" => retyped::<ShipMount>,
+        "ShipNav" => retyped::<ShipNav>,
+        "ShipNavRoute" => retyped::<ShipNavRoute>,
+        "ShipNavRouteWaypoint" => retyped::<ShipNavRouteWaypoint>,
+        "ShipReactor" => retyped::<ShipReactor>,
+        "ShipRegistration" => retyped::<ShipRegistration>,
+        "ShipRequirements" => retyped::<ShipRequirements>,
+        "Shipyard" => retyped::<Shipyard>,
+        "Shipyard/shipTypes[]" => retyped::<ShipTypeReference>,
+        "ShipyardShip" => retyped::<ShipyardShip>,
+        "ShipyardTransaction" => retyped::<ShipTransaction>,
+        "Survey" => retyped::<Survey>,
+        "SurveyDeposit" => retyped::<SurveyDeposit>,
+        "System" => retyped::<System>,
+        "SystemFaction" => retyped::<FactionReference>,
+        "SystemWaypoint" => retyped::<WaypointReference>,
+        "TradeGood" => retyped::<MarketGood>,
+        "Waypoint" => retyped::<Waypoint>,
+        "WaypointFaction" => retyped::<FactionReference>,
+        "WaypointOrbital" => retyped::<Orbital>,
+        "WaypointTrait" => retyped::<WaypointTrait>,
+        _ => return None,
+    })
+}
+
+enum Enumeration {
+    /// A `symbols!` enum: the symbols it has variants for, and whether it has one for a value.
+    Open(Vec<&'static str>, fn(&str) -> bool),
+}
+
+macro_rules! open {
+    ($name:ty) => {
+        Enumeration::Open(<$name>::KNOWN.iter().map(|symbol| symbol.as_str()).collect(), |value| !<$name>::from(value).is_unknown())
+    };
+}
+
+/// The enum each enum schema decodes as, by its path from `components/schemas`.
+fn enumeration(path: &str) -> Option<Enumeration> {
+    Some(match path {
+        "Contract/type" => open!(ContractType),
+        "FactionSymbol" => open!(FactionSymbol),
+        "MarketTradeGood/supply" => open!(SupplyType),
+        "MarketTransaction/type" => open!(TradeType),
+        "ShipCrew/rotation" => open!(ShipCrewRotation),
+        "ShipNavFlightMode" => open!(ShipNavFlightMode),
+        "ShipNavStatus" => open!(ShipNavStatus),
+        "ShipRole" => open!(ShipRole),
+        "ShipType" => open!(ShipType),
+        "Survey/size" => open!(SurveySize),
+        "SystemType" => open!(SystemType),
+        "TradeSymbol" => open!(MarketGoodSymbol),
+        "WaypointTraitSymbol" => open!(WaypointTraitSymbol),
+        "WaypointType" => open!(WaypointType),
+        _ => return None,
+    })
+}
+
+struct Spec {
+    schemas: Map<String, Value>,
+}
+
+impl Spec {
+    fn load() -> Self {
+        let mut spec: Value = serde_json::from_str(SPEC).unwrap();
+        match spec["components"]["schemas"].take() {
+            Value::Object(schemas) => Spec { schemas },
+            _ => panic!("no schemas in the spec"),
+        }
+    }
+
+    /// The schema a `$ref` points to, with its name, or the schema itself.
+    fn resolve<'a>(&'a self, name: &'a str, schema: &'a Value) -> (&'a str, &'a Value) {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => {
+                let name = reference.trim_start_matches("#/components/schemas/");
+                (name, &self.schemas[name])
+            }
+            None => (name, schema),
+        }
+    }
+
+    /// A value of `schema`, a property of the schema called `name`. Strings
+    /// are whatever the property's Rust type parses, e.g. a waypoint symbol.
+    fn sample(&self, name: &str, property: &str, schema: &Value) -> Value {
+        let (name, schema) = self.resolve(name, schema);
+        if let Some(value) = schema.get("enum").and_then(|values| values.get(0)) {
+            return value.clone();
+        }
+
+        match schema["type"].as_str() {
+            Some("object") => {
+                let mut object = Map::new();
+                for (key, property) in properties(schema) {
+                    object.insert(key.clone(), self.sample(name, key, property));
+                }
+                Value::Object(object)
+            }
+            Some("array") => Value::Array(vec![self.sample(name, property, &schema["items"])]),
+            Some("string") if schema["format"] == "date-time" => Value::from(TIMESTAMP),
+            Some("string") => Value::from(string_sample(name, property)),
+            Some("integer") => Value::from(1),
+            // not a whole number, so an integer field in the types fails to decode it
+            Some("number") => Value::from(1.5),
+            Some("boolean") => Value::from(true),
+            other => panic!("{}.{}: no sample for a {:?}", name, property, other),
+        }
+    }
+
+    /// Every object and enum schema, including those nested in properties and
+    /// arrays, with its path, e.g. `ShipFuel/consumed`.
+    fn walk<'a>(&'a self, path: String, schema: &'a Value, objects: &mut Vec<(String, &'a Value)>, enums: &mut Vec<(String, &'a Value)>) {
+        if schema.get("$ref").is_some() {
+            return;
+        }
+        if schema.get("enum").is_some() {
+            enums.push((path, schema));
+            return;
+        }
+
+        match schema["type"].as_str() {
+            Some("object") => {
+                for (key, property) in properties(schema) {
+                    self.walk(format!("{}/{}", path, key), property, objects, enums);
+                }
+                objects.push((path, schema));
+            }
+            Some("array") => self.walk(format!("{}[]", path), &schema["items"], objects, enums),
+            _ => {}
+        }
+    }
+}
+
+fn properties(schema: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    schema["properties"].as_object().into_iter().flatten()
+}
+
+fn required(schema: &Value) -> Vec<&str> {
+    schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).collect()
+}
+
+fn string_sample(name: &str, property: &str) -> &'static str {
+    match (name, property) {
+        (_, "waypointSymbol" | "destinationSymbol" | "headquarters") => WAYPOINT,
+        (_, "systemSymbol") => SYSTEM,
+        (_, "sectorSymbol") => SECTOR,
+        (_, "shipSymbol") => SHIP,
+        ("Waypoint" | "SystemWaypoint" | "ShipNavRouteWaypoint" | "WaypointOrbital" | "Market" | "Shipyard" | "Survey", "symbol") => WAYPOINT,
+        ("System" | "ConnectedSystem", "symbol") => SYSTEM,
+        ("Ship", "symbol") => SHIP,
+        _ => "x",
+    }
+}
+
+/// The ways the type for an object schema disagrees with it.
+fn check_object(spec: &Spec, path: &str, schema: &Value, retype: Retype, problems: &mut Vec<String>) {
+    let name = path.split('/').next().unwrap();
+    let full = spec.sample(name, "", schema);
+    let typed = match retype(&full) {
+        Ok(typed) => typed,
+        Err(e) => {
+            problems.push(format!("{}: does not decode: {}", path, e));
+            Value::Null
+        }
+    };
+    let mut drifts = Vec::new();
+    diff(path, &full, &typed, &mut drifts);
+    for (field, kind) in drifts {
+        problems.push(match kind {
+            DriftKind::Unknown => format!("{}: in the spec but not the type", field),
+            // samples are placeholders like `x`; enum values are `check_enum`'s to check
+            DriftKind::UnknownSymbol => continue,
+            _ => format!("{}: in the type but not the spec", field),
+        });
+    }
+
+    // a property the type doesn't have is already reported above
+    let required = required(schema);
+    for (key, _) in properties(schema).filter(|(key, _)| typed.get(key).is_some()) {
+        let mut sample = full.clone();
+        sample.as_object_mut().unwrap().remove(key);
+        match (required.contains(&key.as_str()), retype(&sample)) {
+            (true, Ok(_)) => problems.push(format!("{}.{}: required in the spec but optional in the type", path, key)),
+            (false, Err(e)) => problems.push(format!("{}.{}: optional in the spec but required in the type: {}", path, key, e)),
+            _ => {}
+        }
+    }
+}
+
+/// The ways the enum for an enum schema disagrees with it.
+fn check_enum(path: &str, schema: &Value, enumeration: Enumeration, problems: &mut Vec<String>) {
+    let values: Vec<&str> = schema["enum"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+    let Enumeration::Open(known, decodes) = enumeration;
+    for value in values.iter().filter(|value| !decodes(value)) {
+        problems.push(format!("{}: no variant for {}", path, value));
+    }
+    for symbol in known.iter().filter(|symbol| !values.contains(symbol)) {
+        problems.push(format!("{}: {} is not in the spec", path, symbol));
+    }
+}
+
+#[test]
+fn test_conformance() {
+    let spec = Spec::load();
+    let mut objects = Vec::new();
+    let mut enums = Vec::new();
+    for (name, schema) in spec.schemas.iter() {
+        spec.walk(name.clone(), schema, &mut objects, &mut enums);
+    }
+
+    let mut problems = Vec::new();
+    for (path, schema) in objects {
+        match model(&path) {
+            Some(retype) => check_object(&spec, &path, schema, retype, &mut problems),
+            None => problems.push(format!("{}: no type to check against", path)),
+        }
+    }
+    for (path, schema) in enums {
+        match enumeration(&path) {
+            Some(enumeration) => check_enum(&path, schema, enumeration, &mut problems),
+            None => problems.push(format!("{}: no enum to check against", path)),
+        }
+    }
+
+    assert!(problems.is_empty(), "types drifted from spec/SpaceTraders.json:\n{}", problems.join("\n"));
+}
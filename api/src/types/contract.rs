@@ -1,13 +1,16 @@
 use serde::{Serialize, Deserialize};
-use crate::types::FactionSymbol;
+use crate::types::{Agent, FactionSymbol, MarketGoodSymbol, ShipCargo, ShipSymbol, WaypointSymbol};
+use crate::types::symbol::symbols;
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "UPPERCASE")]
-pub enum ContractType {
-    Procurement
+symbols! {
+    ContractType {
+        Procurement => "PROCUREMENT",
+        Transport => "TRANSPORT",
+        Shuttle => "SHUTTLE",
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Contract {
     pub id: String,
@@ -15,4 +18,125 @@ pub struct Contract {
 
     #[serde(rename = "type")]
     pub contract_type: ContractType,
+    pub terms: ContractTerms,
+    pub accepted: bool,
+    pub fulfilled: bool,
+    pub expiration: chrono::DateTime<chrono::Utc>,
+    pub deadline_to_accept: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Contract {
+    /// The first delivery that still needs goods.
+    pub fn next_delivery(&self) -> Option<&ContractDeliverGood> {
+        self.terms.deliver.iter().find(|good| good.units_remaining() > 0)
+    }
+
+    pub fn is_delivered(&self) -> bool {
+        self.next_delivery().is_none()
+    }
+
+    pub fn total_payment(&self) -> u64 {
+        self.terms.payment.on_accepted + self.terms.payment.on_fulfilled
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractTerms {
+    pub deadline: chrono::DateTime<chrono::Utc>,
+    pub payment: ContractPayment,
+    #[serde(default)]
+    pub deliver: Vec<ContractDeliverGood>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractPayment {
+    pub on_accepted: u64,
+    pub on_fulfilled: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractDeliverGood {
+    pub trade_symbol: MarketGoodSymbol,
+    pub destination_symbol: WaypointSymbol,
+    pub units_required: u64,
+    pub units_fulfilled: u64,
+}
+
+impl ContractDeliverGood {
+    pub fn units_remaining(&self) -> u64 {
+        self.units_required.saturating_sub(self.units_fulfilled)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliverContractRequest {
+    pub ship_symbol: ShipSymbol,
+    pub trade_symbol: MarketGoodSymbol,
+    pub units: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AcceptContractData {
+    pub agent: Agent,
+    pub contract: Contract,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliverContractData {
+    pub contract: Contract,
+    pub cargo: ShipCargo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FulfillContractData {
+    pub agent: Agent,
+    pub contract: Contract,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NegotiateContractData {
+    pub contract: Contract,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::types::{Contract, MarketGoodSymbol};
+
+    #[test]
+    fn test_deliveries() {
+        let delivery = |good: &str, required: u64, fulfilled: u64| json!({
+            "tradeSymbol": good,
+            "destinationSymbol": "X1-DF55-20250Z",
+            "unitsRequired": required,
+            "unitsFulfilled": fulfilled,
+        });
+        let mut contract: Contract = serde_json::from_value(json!({
+            "id": "contract-1",
+            "factionSymbol": "COSMIC",
+            "type": "PROCUREMENT",
+            "terms": {
+                "deadline": "2023-07-08T00:00:00Z",
+                "payment": { "onAccepted": 1000, "onFulfilled": 4000 },
+                "deliver": [delivery("IRON_ORE", 50, 50), delivery("COPPER_ORE", 30, 10)],
+            },
+            "accepted": true,
+            "fulfilled": false,
+            "expiration": "2023-07-08T00:00:00Z",
+        })).unwrap();
+        assert_eq!(contract.total_payment(), 5000);
+
+        // the iron is all in, so copper is what's still owed
+        let next = contract.next_delivery().unwrap();
+        assert_eq!((&next.trade_symbol, next.units_remaining()), (&MarketGoodSymbol::CopperOre, 20));
+        assert!(!contract.is_delivered());
+
+        contract.terms.deliver[1].units_fulfilled = 30;
+        assert!(contract.next_delivery().is_none());
+        assert!(contract.is_delivered());
+    }
 }
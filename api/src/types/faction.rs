@@ -1,11 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::system_symbol;
+use crate::types::{system_symbol, FactionSymbol};
 
-pub type FactionSymbol = String;
 pub type FactionTraitSymbol = String;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Faction {
     pub symbol: FactionSymbol,
@@ -16,12 +15,12 @@ pub struct Faction {
     pub is_recruiting: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FactionReference {
     pub symbol: FactionSymbol,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FactionTrait {
     pub symbol: String,
@@ -1,33 +1,368 @@
 use serde::{Deserialize, Serialize};
 
-pub type ShipSymbol = String;
+use crate::types::{Agent, FactionSymbol, MarketGoodSymbol, MarketTransaction, ShipSymbol, ShipTransaction, ShipType, SystemSymbol, WaypointSymbol, WaypointType};
+use crate::types::symbol::symbols;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ShipFrame {
+pub struct Ship {
+    pub symbol: ShipSymbol,
+    pub registration: ShipRegistration,
+    pub nav: ShipNav,
+    pub crew: ShipCrew,
+    pub frame: ShipFrame,
+    pub reactor: ShipReactor,
+    pub engine: ShipEngine,
+    pub modules: Vec<ShipModule>,
+    pub mounts: Vec<ShipMount>,
+    pub cargo: ShipCargo,
+    pub fuel: ShipFuel,
+}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipRegistration {
+    pub name: String,
+    pub faction_symbol: FactionSymbol,
+    pub role: ShipRole,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ShipReactor {
+pub struct ShipNav {
+    pub system_symbol: SystemSymbol,
+    pub waypoint_symbol: WaypointSymbol,
+    pub route: ShipNavRoute,
+    pub status: ShipNavStatus,
+    pub flight_mode: ShipNavFlightMode,
+}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipNavRoute {
+    pub destination: ShipNavRouteWaypoint,
+    pub departure: ShipNavRouteWaypoint,
+    pub departure_time: chrono::DateTime<chrono::Utc>,
+    pub arrival: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipNavRouteWaypoint {
+    pub symbol: WaypointSymbol,
+    #[serde(rename = "type")]
+    pub waypoint_type: WaypointType,
+    pub system_symbol: SystemSymbol,
+    pub x: i64,
+    pub y: i64,
+}
+
+symbols! {
+    ShipNavStatus {
+        InTransit => "IN_TRANSIT",
+        InOrbit => "IN_ORBIT",
+        Docked => "DOCKED",
+    }
+}
+
+symbols! {
+    ShipNavFlightMode {
+        Drift => "DRIFT",
+        Stealth => "STEALTH",
+        Cruise => "CRUISE",
+        Burn => "BURN",
+    }
+}
+
+symbols! {
+    ShipRole {
+        Fabricator => "FABRICATOR",
+        Harvester => "HARVESTER",
+        Hauler => "HAULER",
+        Interceptor => "INTERCEPTOR",
+        Excavator => "EXCAVATOR",
+        Transport => "TRANSPORT",
+        Repair => "REPAIR",
+        Surveyor => "SURVEYOR",
+        Command => "COMMAND",
+        Carrier => "CARRIER",
+        Patrol => "PATROL",
+        Satellite => "SATELLITE",
+        Explorer => "EXPLORER",
+        Refinery => "REFINERY",
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipCrew {
+    pub current: u64,
+    pub required: u64,
+    pub capacity: u64,
+    pub rotation: ShipCrewRotation,
+    pub morale: u64,
+    pub wages: u64,
+}
+
+symbols! {
+    ShipCrewRotation {
+        Strict => "STRICT",
+        Relaxed => "RELAXED",
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipFrame {
+    pub symbol: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Option<i64>,
+    pub module_slots: i64,
+    pub mounting_points: i64,
+    pub fuel_capacity: u64,
+    pub requirements: ShipRequirements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipReactor {
+    pub symbol: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Option<i64>,
+    pub power_output: u64,
+    pub requirements: ShipRequirements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShipEngine {
+    pub symbol: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Option<i64>,
+    pub speed: u64,
+    pub requirements: ShipRequirements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipModule {
+    pub symbol: String,
+    pub capacity: Option<u64>,
+    pub range: Option<u64>,
+    pub name: String,
+    pub description: String,
+    pub requirements: ShipRequirements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipMount {
+    pub symbol: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub strength: Option<u64>,
+    pub deposits: Option<Vec<MarketGoodSymbol>>,
+    pub requirements: ShipRequirements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipRequirements {
+    pub power: Option<i64>,
+    pub crew: Option<i64>,
+    pub slots: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipCargo {
+    pub capacity: u64,
+    pub units: u64,
+    pub inventory: Vec<ShipCargoItem>,
+}
+
+impl ShipCargo {
+    pub fn is_full(&self) -> bool {
+        self.units >= self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units == 0
+    }
+
+    pub fn units_of(&self, symbol: &MarketGoodSymbol) -> u64 {
+        self.inventory.iter()
+            .filter(|item| item.symbol == *symbol)
+            .map(|item| item.units)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipCargoItem {
+    pub symbol: MarketGoodSymbol,
+    pub name: String,
+    pub description: String,
+    pub units: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipFuel {
+    pub current: u64,
+    pub capacity: u64,
+    pub consumed: Option<ShipFuelConsumed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShipFuelConsumed {
+    pub amount: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cooldown {
+    pub ship_symbol: ShipSymbol,
+    pub total_seconds: u64,
+    pub remaining_seconds: u64,
+    pub expiration: Option<chrono::DateTime<chrono::Utc>>,
+}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extraction {
+    pub ship_symbol: ShipSymbol,
+    #[serde(rename = "yield")]
+    pub extraction_yield: ExtractionYield,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionYield {
+    pub symbol: MarketGoodSymbol,
+    pub units: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Survey {
+    pub signature: String,
+    pub symbol: WaypointSymbol,
+    pub deposits: Vec<SurveyDeposit>,
+    pub expiration: chrono::DateTime<chrono::Utc>,
+    pub size: SurveySize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurveyDeposit {
+    pub symbol: MarketGoodSymbol,
+}
+
+symbols! {
+    SurveySize {
+        Small => "SMALL",
+        Moderate => "MODERATE",
+        Large => "LARGE",
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ShipModule {
+pub struct NavigateRequest {
+    pub waypoint_symbol: WaypointSymbol,
+}
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellCargoRequest {
+    pub symbol: MarketGoodSymbol,
+    pub units: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ShipMount {
+pub struct PurchaseCargoRequest {
+    pub symbol: MarketGoodSymbol,
+    pub units: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurchaseShipRequest {
+    pub ship_type: ShipType,
+    pub waypoint_symbol: WaypointSymbol,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub survey: Option<Survey>,
+}
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JettisonRequest {
+    pub symbol: MarketGoodSymbol,
+    pub units: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShipNavData {
+    pub nav: ShipNav,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NavigateData {
+    pub fuel: ShipFuel,
+    pub nav: ShipNav,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefuelData {
+    pub agent: Agent,
+    pub fuel: ShipFuel,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractData {
+    pub cooldown: Cooldown,
+    pub extraction: Extraction,
+    pub cargo: ShipCargo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SellCargoData {
+    pub agent: Agent,
+    pub cargo: ShipCargo,
+    pub transaction: MarketTransaction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurchaseCargoData {
+    pub agent: Agent,
+    pub cargo: ShipCargo,
+    pub transaction: MarketTransaction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurchaseShipData {
+    pub agent: Agent,
+    pub ship: Ship,
+    pub transaction: ShipTransaction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSurveyData {
+    pub cooldown: Cooldown,
+    pub surveys: Vec<Survey>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JettisonData {
+    pub cargo: ShipCargo,
 }
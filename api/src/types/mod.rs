@@ -5,6 +5,9 @@ mod contract;
 mod system;
 mod fleet;
 mod system_symbol;
+mod symbol;
+#[cfg(test)]
+mod conformance;
 
 pub use api::*;
 pub use faction::*;
@@ -13,3 +16,4 @@ pub use agent::*;
 pub use contract::*;
 pub use system::*;
 pub use system_symbol::*;
+pub use symbol::*;
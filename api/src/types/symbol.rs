@@ -0,0 +1,488 @@
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// An enum of the symbols the game is known to use, with `Unknown` holding
+/// any it adds later, so that a new good, trait or waypoint type doesn't fail
+/// a whole response. Converting from a string never gives `Unknown` for a known symbol.
+macro_rules! symbols {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $symbol:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, ::serde::Deserialize)]
+        #[serde(from = "String")]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            /// Every symbol there's a variant for.
+            pub const KNOWN: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $symbol,)*
+                    $name::Unknown(symbol) => symbol.as_str(),
+                }
+            }
+
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, $name::Unknown(_))
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($symbol => $name::$variant,)*
+                    _ => $name::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($symbol => $name::$variant,)*
+                    _ => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                match value {
+                    $name::Unknown(symbol) => symbol,
+                    known => known.as_str().to_string(),
+                }
+            }
+        }
+
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                // so that strict mode can tell which values it didn't recognise
+                match self {
+                    $name::Unknown(symbol) => $crate::drift::serialize_unknown(symbol, serializer),
+                    known => serializer.serialize_str(known.as_str()),
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = ::std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok($name::from(s))
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+    };
+}
+
+pub(crate) use symbols;
+
+symbols! {
+    /// A good that can be traded, mined or delivered.
+    MarketGoodSymbol {
+        PreciousStones => "PRECIOUS_STONES",
+        QuartzSand => "QUARTZ_SAND",
+        SiliconCrystals => "SILICON_CRYSTALS",
+        AmmoniaIce => "AMMONIA_ICE",
+        LiquidHydrogen => "LIQUID_HYDROGEN",
+        LiquidNitrogen => "LIQUID_NITROGEN",
+        IceWater => "ICE_WATER",
+        ExoticMatter => "EXOTIC_MATTER",
+        AdvancedCircuitry => "ADVANCED_CIRCUITRY",
+        GravitonEmitters => "GRAVITON_EMITTERS",
+        Iron => "IRON",
+        IronOre => "IRON_ORE",
+        Copper => "COPPER",
+        CopperOre => "COPPER_ORE",
+        Aluminum => "ALUMINUM",
+        AluminumOre => "ALUMINUM_ORE",
+        Silver => "SILVER",
+        SilverOre => "SILVER_ORE",
+        Gold => "GOLD",
+        GoldOre => "GOLD_ORE",
+        Platinum => "PLATINUM",
+        PlatinumOre => "PLATINUM_ORE",
+        Diamonds => "DIAMONDS",
+        Uranite => "URANITE",
+        UraniteOre => "URANITE_ORE",
+        Meritium => "MERITIUM",
+        MeritiumOre => "MERITIUM_ORE",
+        Hydrocarbon => "HYDROCARBON",
+        Antimatter => "ANTIMATTER",
+        Fertilizers => "FERTILIZERS",
+        Fabrics => "FABRICS",
+        Food => "FOOD",
+        Jewelry => "JEWELRY",
+        Machinery => "MACHINERY",
+        Firearms => "FIREARMS",
+        AssaultRifles => "ASSAULT_RIFLES",
+        MilitaryEquipment => "MILITARY_EQUIPMENT",
+        Explosives => "EXPLOSIVES",
+        LabInstruments => "LAB_INSTRUMENTS",
+        Ammunition => "AMMUNITION",
+        Electronics => "ELECTRONICS",
+        ShipPlating => "SHIP_PLATING",
+        Equipment => "EQUIPMENT",
+        Fuel => "FUEL",
+        Medicine => "MEDICINE",
+        Drugs => "DRUGS",
+        Clothing => "CLOTHING",
+        Microprocessors => "MICROPROCESSORS",
+        Plastics => "PLASTICS",
+        Polynucleotides => "POLYNUCLEOTIDES",
+        Biocomposites => "BIOCOMPOSITES",
+        Nanobots => "NANOBOTS",
+        AiMainframes => "AI_MAINFRAMES",
+        QuantumDrives => "QUANTUM_DRIVES",
+        RoboticDrones => "ROBOTIC_DRONES",
+        CyberImplants => "CYBER_IMPLANTS",
+        GeneTherapeutics => "GENE_THERAPEUTICS",
+        NeuralChips => "NEURAL_CHIPS",
+        MoodRegulators => "MOOD_REGULATORS",
+        ViralAgents => "VIRAL_AGENTS",
+        MicroFusionGenerators => "MICRO_FUSION_GENERATORS",
+        Supergrains => "SUPERGRAINS",
+        LaserRifles => "LASER_RIFLES",
+        Holographics => "HOLOGRAPHICS",
+        ShipSalvage => "SHIP_SALVAGE",
+        RelicTech => "RELIC_TECH",
+        NovelLifeforms => "NOVEL_LIFEFORMS",
+        BotanicalSpecimens => "BOTANICAL_SPECIMENS",
+        CulturalArtifacts => "CULTURAL_ARTIFACTS",
+        ReactorSolarI => "REACTOR_SOLAR_I",
+        ReactorFusionI => "REACTOR_FUSION_I",
+        ReactorFissionI => "REACTOR_FISSION_I",
+        ReactorChemicalI => "REACTOR_CHEMICAL_I",
+        ReactorAntimatterI => "REACTOR_ANTIMATTER_I",
+        EngineImpulseDriveI => "ENGINE_IMPULSE_DRIVE_I",
+        EngineIonDriveI => "ENGINE_ION_DRIVE_I",
+        EngineIonDriveIi => "ENGINE_ION_DRIVE_II",
+        EngineHyperDriveI => "ENGINE_HYPER_DRIVE_I",
+        ModuleMineralProcessorI => "MODULE_MINERAL_PROCESSOR_I",
+        ModuleCargoHoldI => "MODULE_CARGO_HOLD_I",
+        ModuleCrewQuartersI => "MODULE_CREW_QUARTERS_I",
+        ModuleEnvoyQuartersI => "MODULE_ENVOY_QUARTERS_I",
+        ModulePassengerCabinI => "MODULE_PASSENGER_CABIN_I",
+        ModuleMicroRefineryI => "MODULE_MICRO_REFINERY_I",
+        ModuleOreRefineryI => "MODULE_ORE_REFINERY_I",
+        ModuleFuelRefineryI => "MODULE_FUEL_REFINERY_I",
+        ModuleScienceLabI => "MODULE_SCIENCE_LAB_I",
+        ModuleJumpDriveI => "MODULE_JUMP_DRIVE_I",
+        ModuleJumpDriveIi => "MODULE_JUMP_DRIVE_II",
+        ModuleJumpDriveIii => "MODULE_JUMP_DRIVE_III",
+        ModuleWarpDriveI => "MODULE_WARP_DRIVE_I",
+        ModuleWarpDriveIi => "MODULE_WARP_DRIVE_II",
+        ModuleWarpDriveIii => "MODULE_WARP_DRIVE_III",
+        ModuleShieldGeneratorI => "MODULE_SHIELD_GENERATOR_I",
+        ModuleShieldGeneratorIi => "MODULE_SHIELD_GENERATOR_II",
+        MountGasSiphonI => "MOUNT_GAS_SIPHON_I",
+        MountGasSiphonIi => "MOUNT_GAS_SIPHON_II",
+        MountGasSiphonIii => "MOUNT_GAS_SIPHON_III",
+        MountSurveyorI => "MOUNT_SURVEYOR_I",
+        MountSurveyorIi => "MOUNT_SURVEYOR_II",
+        MountSurveyorIii => "MOUNT_SURVEYOR_III",
+        MountSensorArrayI => "MOUNT_SENSOR_ARRAY_I",
+        MountSensorArrayIi => "MOUNT_SENSOR_ARRAY_II",
+        MountSensorArrayIii => "MOUNT_SENSOR_ARRAY_III",
+        MountMiningLaserI => "MOUNT_MINING_LASER_I",
+        MountMiningLaserIi => "MOUNT_MINING_LASER_II",
+        MountMiningLaserIii => "MOUNT_MINING_LASER_III",
+        MountLaserCannonI => "MOUNT_LASER_CANNON_I",
+        MountMissileLauncherI => "MOUNT_MISSILE_LAUNCHER_I",
+        MountTurretI => "MOUNT_TURRET_I",
+    }
+}
+
+symbols! {
+    /// Something notable about a waypoint, e.g. that it has a marketplace.
+    WaypointTraitSymbol {
+        Uncharted => "UNCHARTED",
+        Marketplace => "MARKETPLACE",
+        Shipyard => "SHIPYARD",
+        Outpost => "OUTPOST",
+        ScatteredSettlements => "SCATTERED_SETTLEMENTS",
+        SprawlingCities => "SPRAWLING_CITIES",
+        MegaStructures => "MEGA_STRUCTURES",
+        Overcrowded => "OVERCROWDED",
+        HighTech => "HIGH_TECH",
+        Corrupt => "CORRUPT",
+        Bureaucratic => "BUREAUCRATIC",
+        TradingHub => "TRADING_HUB",
+        Industrial => "INDUSTRIAL",
+        BlackMarket => "BLACK_MARKET",
+        ResearchFacility => "RESEARCH_FACILITY",
+        MilitaryBase => "MILITARY_BASE",
+        SurveillanceOutpost => "SURVEILLANCE_OUTPOST",
+        ExplorationOutpost => "EXPLORATION_OUTPOST",
+        MineralDeposits => "MINERAL_DEPOSITS",
+        CommonMetalDeposits => "COMMON_METAL_DEPOSITS",
+        PreciousMetalDeposits => "PRECIOUS_METAL_DEPOSITS",
+        RareMetalDeposits => "RARE_METAL_DEPOSITS",
+        MethanePools => "METHANE_POOLS",
+        IceCrystals => "ICE_CRYSTALS",
+        ExplosiveGases => "EXPLOSIVE_GASES",
+        StrongMagnetosphere => "STRONG_MAGNETOSPHERE",
+        VibrantAuroras => "VIBRANT_AURORAS",
+        SaltFlats => "SALT_FLATS",
+        Canyons => "CANYONS",
+        PerpetualDaylight => "PERPETUAL_DAYLIGHT",
+        PerpetualOvercast => "PERPETUAL_OVERCAST",
+        DrySeabeds => "DRY_SEABEDS",
+        MagmaSeas => "MAGMA_SEAS",
+        Supervolcanoes => "SUPERVOLCANOES",
+        AshClouds => "ASH_CLOUDS",
+        VastRuins => "VAST_RUINS",
+        MutatedFlora => "MUTATED_FLORA",
+        Terraformed => "TERRAFORMED",
+        ExtremeTemperatures => "EXTREME_TEMPERATURES",
+        ExtremePressure => "EXTREME_PRESSURE",
+        DiverseLife => "DIVERSE_LIFE",
+        ScarceLife => "SCARCE_LIFE",
+        Fossils => "FOSSILS",
+        WeakGravity => "WEAK_GRAVITY",
+        StrongGravity => "STRONG_GRAVITY",
+        CrushingGravity => "CRUSHING_GRAVITY",
+        ToxicAtmosphere => "TOXIC_ATMOSPHERE",
+        CorrosiveAtmosphere => "CORROSIVE_ATMOSPHERE",
+        BreathableAtmosphere => "BREATHABLE_ATMOSPHERE",
+        Jovian => "JOVIAN",
+        Rocky => "ROCKY",
+        Volcanic => "VOLCANIC",
+        Frozen => "FROZEN",
+        Swamp => "SWAMP",
+        Barren => "BARREN",
+        Temperate => "TEMPERATE",
+        Jungle => "JUNGLE",
+        Ocean => "OCEAN",
+        Stripped => "STRIPPED",
+    }
+}
+
+symbols! {
+    FactionSymbol {
+        Cosmic => "COSMIC",
+        Void => "VOID",
+        Galactic => "GALACTIC",
+        Quantum => "QUANTUM",
+        Dominion => "DOMINION",
+        Astro => "ASTRO",
+        Corsairs => "CORSAIRS",
+        Obsidian => "OBSIDIAN",
+        Aegis => "AEGIS",
+        United => "UNITED",
+        Solitary => "SOLITARY",
+        Cobalt => "COBALT",
+        Omega => "OMEGA",
+        Echo => "ECHO",
+        Lords => "LORDS",
+        Cult => "CULT",
+        Ancients => "ANCIENTS",
+        Shadow => "SHADOW",
+        Ethereal => "ETHEREAL",
+    }
+}
+
+#[derive(Debug)]
+pub enum SymbolError {
+    InvalidShipSymbol,
+}
+
+impl Display for SymbolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolError::InvalidShipSymbol => f.write_str("ship symbols are an agent symbol and a number, e.g. BEAR-1")
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+/// A ship's symbol: its agent's symbol, a dash, and a hex number, e.g. `BEAR-1A`.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ShipSymbol {
+    _ship: String,
+}
+
+impl TryFrom<String> for ShipSymbol {
+    type Error = SymbolError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ShipSymbol::new(value.as_str())
+    }
+}
+
+impl From<ShipSymbol> for String {
+    fn from(value: ShipSymbol) -> Self {
+        value._ship
+    }
+}
+
+impl FromStr for ShipSymbol {
+    type Err = SymbolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ShipSymbol::new(s)
+    }
+}
+
+impl Display for ShipSymbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.ship())
+    }
+}
+
+impl Debug for ShipSymbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.ship())
+    }
+}
+
+// the symbol hashes and compares as its string does, so maps keyed by it can be looked up by `&str`
+impl Borrow<str> for ShipSymbol {
+    fn borrow(&self) -> &str {
+        self.ship()
+    }
+}
+
+impl AsRef<str> for ShipSymbol {
+    fn as_ref(&self) -> &str {
+        self.ship()
+    }
+}
+
+impl PartialEq<str> for ShipSymbol {
+    fn eq(&self, other: &str) -> bool {
+        self.ship() == other
+    }
+}
+
+impl PartialEq<&str> for ShipSymbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.ship() == *other
+    }
+}
+
+impl ShipSymbol {
+    pub fn new(s: &str) -> Result<Self, SymbolError> {
+        match s.rsplit_once('-') {
+            Some((agent, number)) if !agent.is_empty() && !number.is_empty() && number.chars().all(|c| c.is_ascii_hexdigit()) => Ok(ShipSymbol {
+                _ship: s.to_string(),
+            }),
+            _ => Err(SymbolError::InvalidShipSymbol)
+        }
+    }
+
+    /// The symbol of the agent that owns the ship.
+    pub fn agent(&self) -> &str {
+        self._ship.rsplit_once('-').map(|(agent, _)| agent).unwrap_or_default()
+    }
+
+    pub fn ship(&self) -> &str {
+        self._ship.as_str()
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.ship()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{ContractType, FactionSymbol, MarketGoodSymbol, ShipNavFlightMode, ShipNavStatus, ShipRole, ShipSymbol, ShipType, SupplyType, SurveySize, SystemType, TradeType, WaypointTraitSymbol, WaypointType};
+
+    /// Strings near the known symbols and far from them: empty, mistyped,
+    /// lowercased, extended, and random runs of symbol and non-ASCII characters.
+    fn fuzz_strings(known: &[&str]) -> Vec<String> {
+        const CHARS: [char; 12] = ['A', 'Z', '_', '0', '9', 'x', '-', ' ', '"', '\\', 'é', '🛰'];
+        let mut strings = vec![String::new(), String::from("ARTIFICIAL_GRAVITY_WELL"), String::from("FUEL_STATION"), String::from("HIGH")];
+        for symbol in known {
+            strings.push(symbol.to_lowercase());
+            strings.push(format!("{}_II", symbol));
+            strings.push(format!(" {}", symbol));
+            strings.push(symbol[1..].to_string());
+        }
+
+        // xorshift, so that every run tries the same strings
+        let mut state: u64 = 0x2545F4914F6CDD1D;
+        for _ in 0..500 {
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+            let len = next() % 24;
+            strings.push((0..len).map(|_| CHARS[(next() % CHARS.len() as u64) as usize]).collect());
+        }
+        strings
+    }
+
+    /// Every known symbol decodes to its variant and anything else to
+    /// `Unknown`, and both encode back to the string they came from.
+    macro_rules! fuzz {
+        ($($name:ident),*) => {$(
+            let known: Vec<&str> = $name::KNOWN.iter().map(|symbol| symbol.as_str()).collect();
+            for symbol in $name::KNOWN {
+                let json = serde_json::to_string(symbol).unwrap();
+                assert_eq!(serde_json::from_str::<$name>(&json).unwrap(), *symbol);
+            }
+            for string in fuzz_strings(&known) {
+                let json = serde_json::to_string(&string).unwrap();
+                let decoded: $name = serde_json::from_str(&json)
+                    .unwrap_or_else(|e| panic!("{} failed to decode {}: {}", stringify!($name), json, e));
+                assert_eq!(decoded.is_unknown(), !known.contains(&string.as_str()), "{} decoded {}", stringify!($name), json);
+                assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+            }
+        )*};
+    }
+
+    #[test]
+    fn test_fuzz_unknown_symbols() {
+        fuzz!(MarketGoodSymbol, WaypointTraitSymbol, FactionSymbol, SystemType, WaypointType, SupplyType, ShipType, ContractType, ShipRole, ShipNavFlightMode, ShipNavStatus, TradeType, SurveySize);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let goods: Vec<MarketGoodSymbol> = serde_json::from_str(r#"["IRON_ORE", "UNOBTANIUM"]"#).unwrap();
+        assert_eq!(goods, vec![MarketGoodSymbol::IronOre, MarketGoodSymbol::Unknown(String::from("UNOBTANIUM"))]);
+        assert_eq!(serde_json::to_string(&goods).unwrap(), r#"["IRON_ORE","UNOBTANIUM"]"#);
+        assert_eq!("MARKETPLACE".parse::<WaypointTraitSymbol>().unwrap(), WaypointTraitSymbol::Marketplace);
+        assert_eq!(FactionSymbol::Cosmic, "COSMIC");
+
+        let ship_symbol = ShipSymbol::new("BEAR-CUB-1A").unwrap();
+        assert_eq!(ship_symbol.agent(), "BEAR-CUB");
+        assert!(ShipSymbol::new("BEAR").is_err());
+        assert!(ShipSymbol::new("BEAR-").is_err());
+        assert!(serde_json::from_str::<ShipSymbol>(r#""-1""#).is_err());
+    }
+}
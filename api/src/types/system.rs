@@ -3,8 +3,9 @@ use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
 use crate::types::{AgentSymbol, FactionReference, FactionSymbol, MarketGoodSymbol, SectorSymbol, ShipEngine, ShipFrame, ShipModule, ShipMount, ShipReactor, ShipSymbol, SystemSymbol, WaypointSymbol, WaypointTraitSymbol};
+use crate::types::symbol::symbols;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct System {
     pub symbol: SystemSymbol,
@@ -17,7 +18,7 @@ pub struct System {
     pub factions: Vec<FactionReference>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WaypointReference {
     pub symbol: WaypointSymbol,
     #[serde(rename = "type")]
@@ -26,7 +27,13 @@ pub struct WaypointReference {
     pub y: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl WaypointReference {
+    pub fn distance_to(&self, x: i64, y: i64) -> f64 {
+        (((self.x - x).pow(2) + (self.y - y).pow(2)) as f64).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Waypoint {
     #[serde(flatten)]
@@ -40,24 +47,76 @@ pub struct Waypoint {
 
 impl Waypoint {
     pub fn is_market(&self) -> bool {
-        self.traits.iter().find(|t| t.symbol == "MARKETPLACE".to_string()).is_some()
+        self.has_trait(&WaypointTraitSymbol::Marketplace)
     }
 
     pub fn is_shipyard(&self) -> bool {
-        self.traits.iter().find(|t| t.symbol == "SHIPYARD".to_string()).is_some()
+        self.has_trait(&WaypointTraitSymbol::Shipyard)
     }
 
     pub fn is_jump_gate(&self) -> bool {
         self.reference.waypoint_type == WaypointType::JumpGate
     }
+
+    pub fn has_trait(&self, symbol: &WaypointTraitSymbol) -> bool {
+        self.traits.iter().any(|t| t.symbol == *symbol)
+    }
+}
+
+/// Which of a system's waypoints to list. The server filters by trait and
+/// type; `filter` is checked on what it returns, for anything else.
+#[derive(Debug, Clone, Default)]
+pub struct WaypointQuery {
+    pub traits: Vec<WaypointTraitSymbol>,
+    pub waypoint_type: Option<WaypointType>,
+    pub filter: Option<fn(&Waypoint) -> bool>,
+}
+
+impl WaypointQuery {
+    pub fn new() -> Self {
+        WaypointQuery::default()
+    }
+
+    /// Only waypoints with the trait, as well as any others asked for.
+    pub fn with_trait(mut self, symbol: WaypointTraitSymbol) -> Self {
+        self.traits.push(symbol);
+        self
+    }
+
+    pub fn of_type(mut self, waypoint_type: WaypointType) -> Self {
+        self.waypoint_type = Some(waypoint_type);
+        self
+    }
+
+    /// Only waypoints `p` holds for, which has to be checked client-side.
+    pub fn filter(mut self, p: fn(&Waypoint) -> bool) -> Self {
+        self.filter = Some(p);
+        self
+    }
+
+    /// The server's filters as a query string, e.g. `traits=MARKETPLACE&type=PLANET`.
+    pub fn query_string(&self) -> String {
+        let mut params: Vec<String> = self.traits.iter().map(|t| format!("traits={}", t)).collect();
+        if let Some(waypoint_type) = &self.waypoint_type {
+            params.push(format!("type={}", waypoint_type));
+        }
+        params.join("&")
+    }
+
+    /// Whether the waypoint passes every filter, the server's included.
+    pub fn matches(&self, waypoint: &Waypoint) -> bool {
+        self.traits.iter().all(|t| waypoint.has_trait(t))
+            && self.waypoint_type.as_ref().is_none_or(|waypoint_type| waypoint.reference.waypoint_type == *waypoint_type)
+            && self.filter.is_none_or(|p| p(waypoint))
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Orbital {
     pub symbol: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WaypointTrait {
     pub symbol: WaypointTraitSymbol,
@@ -65,34 +124,54 @@ pub struct WaypointTrait {
     pub description: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Chart {
     pub waypoint_symbol: Option<WaypointSymbol>,
-    pub submitted_by: String,
-    pub submitted_on: chrono::DateTime<chrono::Utc>,
+    pub submitted_by: Option<AgentSymbol>,
+    pub submitted_on: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Market {
     pub symbol: WaypointSymbol,
     pub imports: Vec<MarketGood>,
     pub exports: Vec<MarketGood>,
     pub exchange: Vec<MarketGood>,
-    pub transactions: Vec<MarketTransaction>,
-    pub trade_goods: Vec<MarketTradeGood>,
+    /// Only listed with a ship present.
+    pub transactions: Option<Vec<MarketTransaction>>,
+    /// Only listed with a ship present.
+    pub trade_goods: Option<Vec<MarketTradeGood>>,
+}
+
+impl Market {
+    /// Whether one of our ships was at the market, so prices and recent
+    /// transactions are listed, rather than only which goods it trades.
+    pub fn is_live(&self) -> bool {
+        self.trade_goods.is_some()
+    }
+
+    /// The prices of each good, or none without a ship present.
+    pub fn trade_goods(&self) -> &[MarketTradeGood] {
+        self.trade_goods.as_deref().unwrap_or_default()
+    }
+
+    /// The recent transactions, or none without a ship present.
+    pub fn transactions(&self) -> &[MarketTransaction] {
+        self.transactions.as_deref().unwrap_or_default()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketGood {
     pub symbol: MarketGoodSymbol,
     pub name: String,
     pub description: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketTransaction {
     pub waypoint_symbol: WaypointSymbol,
@@ -106,7 +185,7 @@ pub struct MarketTransaction {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketTradeGood {
     pub symbol: MarketGoodSymbol,
@@ -116,34 +195,56 @@ pub struct MarketTradeGood {
     pub sell_price: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Shipyard {
     pub symbol: WaypointSymbol,
     pub ship_types: Vec<ShipTypeReference>,
-    pub transactions: Vec<ShipTransaction>,
-    pub ships: Vec<Ship>,
+    /// Only listed with a ship present.
+    pub transactions: Option<Vec<ShipTransaction>>,
+    /// Only listed with a ship present.
+    pub ships: Option<Vec<ShipyardShip>>,
+    pub modifications_fee: u64,
+}
+
+impl Shipyard {
+    /// Whether one of our ships was at the shipyard, so the ships for sale
+    /// and recent sales are listed, rather than only which types it sells.
+    pub fn is_live(&self) -> bool {
+        self.ships.is_some()
+    }
+
+    /// The ships for sale with their prices, or none without a ship present.
+    pub fn ships(&self) -> &[ShipyardShip] {
+        self.ships.as_deref().unwrap_or_default()
+    }
+
+    /// The recent sales, or none without a ship present.
+    pub fn transactions(&self) -> &[ShipTransaction] {
+        self.transactions.as_deref().unwrap_or_default()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShipTypeReference {
     #[serde(rename = "type")]
     pub ship_type: ShipType,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShipTransaction {
     pub waypoint_symbol: WaypointSymbol,
-    pub ship_symbol: ShipSymbol,
+    /// The type of ship sold, e.g. `SHIP_MINING_DRONE`, despite the name.
+    pub ship_symbol: String,
     pub price: u64,
     pub agent_symbol: AgentSymbol,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Ship {
+pub struct ShipyardShip {
     #[serde(rename = "type")]
     pub ship_type: ShipType,
     pub name: String,
@@ -156,83 +257,83 @@ pub struct Ship {
     pub mounts: Vec<ShipMount>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JumpGate {
-    pub jump_range: i64,
-    pub faction_symbol: FactionSymbol,
+    pub jump_range: f64,
+    pub faction_symbol: Option<FactionSymbol>,
     pub connected_systems: Vec<ConnectedSystem>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectedSystem {
     pub symbol: SystemSymbol,
     pub sector_symbol: SectorSymbol,
     #[serde(rename = "type")]
     pub system_type: SystemType,
-    pub faction_symbol: FactionSymbol,
+    pub faction_symbol: Option<FactionSymbol>,
     pub x: i64,
     pub y: i64,
     pub distance: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum SystemType {
-    NeutronStar,
-    RedStar,
-    OrangeStar,
-    BlueStar,
-    YoungStar,
-    WhiteDwarf,
-    BlackHole,
-    Hypergiant,
-    Nebula,
-    Unstable,
-}
-
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum WaypointType {
-    Planet,
-    GasGiant,
-    Moon,
-    OrbitalStation,
-    JumpGate,
-    AsteroidField,
-    Nebula,
-    DebrisField,
-    GravityWell,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum TradeType {
-    Purchase,
-    Sell,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum SupplyType {
-    Scarce,
-    Limited,
-    Moderate,
-    Abundant,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ShipType {
-    ShipProbe,
-    ShipMiningDrone,
-    ShipInterceptor,
-    ShipLightHauler,
-    ShipCommandFrigate,
-    ShipExplorer,
-    ShipHeavyFreighter,
-    ShipLightShuttle,
-    ShipOreHound,
-    ShipRefiningFreighter,
+symbols! {
+    SystemType {
+        NeutronStar => "NEUTRON_STAR",
+        RedStar => "RED_STAR",
+        OrangeStar => "ORANGE_STAR",
+        BlueStar => "BLUE_STAR",
+        YoungStar => "YOUNG_STAR",
+        WhiteDwarf => "WHITE_DWARF",
+        BlackHole => "BLACK_HOLE",
+        Hypergiant => "HYPERGIANT",
+        Nebula => "NEBULA",
+        Unstable => "UNSTABLE",
+    }
+}
+
+symbols! {
+    WaypointType {
+        Planet => "PLANET",
+        GasGiant => "GAS_GIANT",
+        Moon => "MOON",
+        OrbitalStation => "ORBITAL_STATION",
+        JumpGate => "JUMP_GATE",
+        AsteroidField => "ASTEROID_FIELD",
+        Nebula => "NEBULA",
+        DebrisField => "DEBRIS_FIELD",
+        GravityWell => "GRAVITY_WELL",
+    }
+}
+
+symbols! {
+    TradeType {
+        Purchase => "PURCHASE",
+        Sell => "SELL",
+    }
+}
+
+symbols! {
+    SupplyType {
+        Scarce => "SCARCE",
+        Limited => "LIMITED",
+        Moderate => "MODERATE",
+        Abundant => "ABUNDANT",
+    }
+}
+
+symbols! {
+    ShipType {
+        ShipProbe => "SHIP_PROBE",
+        ShipMiningDrone => "SHIP_MINING_DRONE",
+        ShipInterceptor => "SHIP_INTERCEPTOR",
+        ShipLightHauler => "SHIP_LIGHT_HAULER",
+        ShipCommandFrigate => "SHIP_COMMAND_FRIGATE",
+        ShipExplorer => "SHIP_EXPLORER",
+        ShipHeavyFreighter => "SHIP_HEAVY_FREIGHTER",
+        ShipLightShuttle => "SHIP_LIGHT_SHUTTLE",
+        ShipOreHound => "SHIP_ORE_HOUND",
+        ShipRefiningFreighter => "SHIP_REFINING_FREIGHTER",
+    }
 }
@@ -1,10 +1,12 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-pub type MarketGoodSymbol = String;
-pub type WaypointTraitSymbol = String;
-
 #[derive(Debug)]
 pub enum SystemError {
     InvalidCoordinates
@@ -13,191 +15,232 @@ pub enum SystemError {
 impl Display for SystemError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            SystemError::InvalidCoordinates => write!(f, "{}", "coordinates are improperly formatted")
+            SystemError::InvalidCoordinates => f.write_str("coordinates are improperly formatted")
         }
     }
 }
 
-#[derive(PartialEq, Clone, Serialize, Deserialize)]
-#[serde(try_from = "String", into = "String")]
-pub struct SectorSymbol {
-    _sector: String,
-}
+impl std::error::Error for SystemError {}
 
-impl TryFrom<String> for SectorSymbol {
-    type Error = SystemError;
+/// Conversions and comparisons for a coordinate symbol, all by the string
+/// `$part` returns. Hashing and ordering as that string does is what lets
+/// maps keyed by the symbol be looked up by `&str`.
+macro_rules! coordinates {
+    ($name:ident, $part:ident) => {
+        impl TryFrom<String> for $name {
+            type Error = SystemError;
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        SectorSymbol::new(value.as_str())
-    }
-}
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                $name::new(value.as_str())
+            }
+        }
 
-impl Into<String> for SectorSymbol {
-    fn into(self) -> String {
-        self._sector
-    }
-}
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.$part().to_string()
+            }
+        }
 
-impl Display for SectorSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.sector())
-    }
+        impl FromStr for $name {
+            type Err = SystemError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::new(s)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.$part())
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.$part())
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.$part() == other.$part()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.$part() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.$part() == *other
+            }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.$part().hash(state)
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.$part().cmp(other.$part())
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                self.$part()
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.$part()
+            }
+        }
+    };
 }
 
-impl Debug for SectorSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.sector())
-    }
+// Each symbol shares the string it was parsed from, so cloning one or taking
+// its system or sector doesn't allocate. `_sector` and `_system` are where
+// those parts of `_symbol` end.
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SectorSymbol {
+    _symbol: Arc<str>,
+    _sector: usize,
 }
 
+coordinates!(SectorSymbol, sector);
+
 impl SectorSymbol {
     pub fn new(s: &str) -> Result<Self, SystemError> {
         let parts: Vec<&str> = s.split("-").collect();
         match parts[..] {
             [sector] => Ok(SectorSymbol {
-                _sector: sector.to_string(),
+                _symbol: Arc::from(s),
+                _sector: sector.len(),
             }),
             _ => Err(SystemError::InvalidCoordinates)
         }
     }
 
     pub fn sector(&self) -> &str {
-        self._sector.as_str()
+        &self._symbol[..self._sector]
     }
 }
 
 
-#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "String")]
 pub struct SystemSymbol {
-    _sector: String,
-    _system: String,
-}
-
-impl TryFrom<String> for SystemSymbol {
-    type Error = SystemError;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        SystemSymbol::new(value.as_str())
-    }
-}
-
-impl Into<String> for SystemSymbol {
-    fn into(self) -> String {
-        self._system
-    }
+    _symbol: Arc<str>,
+    _sector: usize,
+    _system: usize,
 }
 
-impl Display for SystemSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.system())
-    }
-}
-
-impl Debug for SystemSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.system())
-    }
-}
+coordinates!(SystemSymbol, system);
 
 impl SystemSymbol {
     pub fn new(s: &str) -> Result<Self, SystemError> {
         let parts: Vec<&str> = s.split("-").collect();
         match parts[..] {
-            [sector, system] => Ok(SystemSymbol {
-                _sector: sector.to_string(),
-                _system: format!("{}-{}", sector, system),
+            [sector, _] => Ok(SystemSymbol {
+                _symbol: Arc::from(s),
+                _sector: sector.len(),
+                _system: s.len(),
             }),
             _ => Err(SystemError::InvalidCoordinates)
         }
     }
 
     pub fn sector(&self) -> &str {
-        self._sector.as_str()
+        &self._symbol[..self._sector]
     }
 
     pub fn sector_symbol(&self) -> SectorSymbol {
-        SectorSymbol::new(self.sector()).unwrap()
+        SectorSymbol {
+            _symbol: self._symbol.clone(),
+            _sector: self._sector,
+        }
     }
 
     pub fn system(&self) -> &str {
-        self._system.as_str()
+        &self._symbol[..self._system]
     }
 }
 
-#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "String")]
 pub struct WaypointSymbol {
-    _sector: String,
-    _system: String,
-    _waypoint: String,
-}
-
-impl TryFrom<String> for WaypointSymbol {
-    type Error = SystemError;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        WaypointSymbol::new(value.as_str())
-    }
-}
-
-impl Into<String> for WaypointSymbol {
-    fn into(self) -> String {
-        self._waypoint
-    }
-}
-
-impl Display for WaypointSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.waypoint())
-    }
+    _symbol: Arc<str>,
+    _sector: usize,
+    _system: usize,
 }
 
-impl Debug for WaypointSymbol {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.waypoint())
-    }
-}
+coordinates!(WaypointSymbol, waypoint);
 
 impl WaypointSymbol {
     pub fn new(s: &str) -> Result<Self, SystemError> {
         let parts: Vec<&str> = s.split("-").collect();
         match parts[..] {
-            [sector, system, waypoint] => Ok(WaypointSymbol {
-                _sector: sector.to_string(),
-                _system: format!("{}-{}", sector, system),
-                _waypoint: format!("{}-{}-{}", sector, system, waypoint),
+            [sector, system, _] => Ok(WaypointSymbol {
+                _symbol: Arc::from(s),
+                _sector: sector.len(),
+                _system: sector.len() + 1 + system.len(),
             }),
             _ => Err(SystemError::InvalidCoordinates)
         }
     }
 
     pub fn sector(&self) -> &str {
-        self._sector.as_str()
+        &self._symbol[..self._sector]
     }
 
     pub fn sector_symbol(&self) -> SectorSymbol {
-        SectorSymbol::new(self.sector()).unwrap()
+        SectorSymbol {
+            _symbol: self._symbol.clone(),
+            _sector: self._sector,
+        }
     }
 
     pub fn system(&self) -> &str {
-        self._system.as_str()
+        &self._symbol[..self._system]
     }
 
     pub fn system_symbol(&self) -> SystemSymbol {
-        SystemSymbol::new(self._system.as_str()).unwrap()
+        SystemSymbol {
+            _symbol: self._symbol.clone(),
+            _sector: self._sector,
+            _system: self._system,
+        }
     }
 
     pub fn waypoint(&self) -> &str {
-        self._waypoint.as_str()
+        &self._symbol
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
     use serde::Deserialize;
     use serde_json::json;
-    use crate::types::WaypointSymbol;
+    use crate::types::{SystemSymbol, WaypointSymbol};
 
     #[derive(Deserialize)]
     struct Object {
@@ -212,7 +255,7 @@ mod tests {
             Ok(s) => {
                 assert_eq!(format!("\"{}\"", expected), s);
             }
-            Err(e) => assert!(false, "could not serialize: {:?}", e)
+            Err(e) => panic!("could not serialize: {:?}", e)
         }
     }
 
@@ -235,7 +278,21 @@ mod tests {
                 assert_eq!(sector_symbol.sector(), "X1");
                 assert_eq!(sector_symbol, system_symbol.sector_symbol());
             }
-            Err(e) => assert!(false, "could not deserialize: {:?}", e)
+            Err(e) => panic!("could not deserialize: {:?}", e)
         }
     }
+
+    #[test]
+    fn test_keys() {
+        let a = WaypointSymbol::new("X1-DF55-20250Z").unwrap();
+        let b = WaypointSymbol::new("X1-DF55-17335A").unwrap();
+        let markets = HashMap::from([(a.clone(), 1), (b.clone(), 2)]);
+        assert_eq!(markets.get("X1-DF55-20250Z"), Some(&1));
+        assert_eq!(markets.get(&b), Some(&2));
+
+        let systems: BTreeSet<SystemSymbol> = [a.system_symbol(), b.system_symbol(), "X1-AB12".parse().unwrap()].into_iter().collect();
+        assert_eq!(systems.iter().map(|system| system.system()).collect::<Vec<_>>(), vec!["X1-AB12", "X1-DF55"]);
+        assert_eq!(String::from(a.system_symbol()), "X1-DF55");
+        assert!("X1-DF55-20250Z-1".parse::<WaypointSymbol>().is_err());
+    }
 }
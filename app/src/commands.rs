@@ -0,0 +1,281 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use space_traders_api::error::UnsavedRegistration;
+use space_traders_api::{credentials_path, find_credentials, find_saved_agents, save_credentials, ApiManager, SpaceTradersApi, types};
+use space_traders_api::drift::{DriftReport, SchemaDrift, SessionRecorder};
+
+use crate::{dashboard, fleet};
+use crate::table::{name, Table};
+use crate::{Cli, Command, ContractsCommand, SystemsCommand};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let json = cli.json;
+    if let Command::Register { call_sign, faction } = cli.command {
+        let (registration_data, path) = match SpaceTradersApi::register_and_save(&call_sign, faction).await {
+            Ok(saved) => saved,
+            Err(space_traders_api::error::Error::UnsavedRegistration(unsaved)) => return Err(rescue(unsaved)),
+            Err(e) => return Err(e.into()),
+        };
+        eprintln!("token saved to {}", path.display());
+        return show(json, &registration_data.agent, agent_table);
+    }
+
+    if let Command::Drift { session } = cli.command {
+        let drift = SchemaDrift::new();
+        for response in SessionRecorder::read(session)? {
+            drift.check_recorded(&response);
+        }
+        return show(json, &drift.report(), drift_table);
+    }
+
+    let mut api = api(cli.agent.as_deref())?;
+    if let Some(path) = cli.record {
+        api = api.recording(Arc::new(SessionRecorder::open(path)?));
+    }
+    let drift = Arc::new(SchemaDrift::new());
+    if cli.strict {
+        api = api.strict(drift.clone());
+    }
+    let result = play(json, api, cli.command).await;
+    if cli.strict {
+        let report = drift.report();
+        if report.is_empty() {
+            eprintln!("no schema drift in {} responses", report.checked.values().sum::<u64>());
+        } else {
+            eprintln!();
+            drift_table(&report).eprint();
+        }
+    }
+    result
+}
+
+async fn play(json: bool, api: SpaceTradersApi, command: Command) -> Result<()> {
+    match command {
+        Command::Register { .. } | Command::Drift { .. } => unreachable!(),
+        Command::Agent => show(json, &api.get_agent().await?, agent_table),
+        Command::Systems(SystemsCommand::List { page, limit }) => {
+            show(json, &api.list_systems(Some(page), Some(limit)).await?, |systems| systems_table(systems))
+        }
+        Command::Systems(SystemsCommand::Get { system }) => {
+            show(json, &api.get_system(system).await?, |system| waypoint_references_table(&system.waypoints))
+        }
+        Command::Waypoints { system, traits } => {
+            let query = types::WaypointQuery { traits, ..Default::default() };
+            let waypoints = api.query_system_waypoints(system, &query).await?;
+            show(json, &waypoints, |waypoints| waypoints_table(waypoints))
+        }
+        Command::Market { waypoint } => show(json, &api.get_market(waypoint).await?, market_table),
+        Command::Shipyard { waypoint } => show(json, &api.get_shipyard(waypoint).await?, shipyard_table),
+        Command::Ships => show(json, &api.list_ships().await?, |ships| ships_table(ships)),
+        Command::Navigate { ship, waypoint } => {
+            let data = api.navigate_ship(ship, waypoint).await?;
+            show(json, &data, |data| nav_table(&data.nav))
+        }
+        Command::Dock { ship } => show(json, &api.dock_ship(ship).await?, nav_table),
+        Command::Orbit { ship } => show(json, &api.orbit_ship(ship).await?, nav_table),
+        Command::Refuel { ship } => {
+            let data = api.refuel_ship(ship).await?;
+            show(json, &data, |data| {
+                let mut table = Table::new(&["FUEL", "CAPACITY", "CREDITS"]);
+                table.row(vec![data.fuel.current.to_string(), data.fuel.capacity.to_string(), data.agent.credits.to_string()]);
+                table
+            })
+        }
+        Command::Dashboard { interval, state } => {
+            let manager = ApiManager::new(Arc::new(api)).await?;
+            Ok(dashboard::run(manager, Duration::from_secs(interval), state).await?)
+        }
+        Command::Run { state, port } => Ok(fleet::run(api, state, port).await?),
+        Command::Contracts { command: None | Some(ContractsCommand::List) } => {
+            show(json, &api.list_contracts().await?, |contracts| contracts_table(contracts))
+        }
+        Command::Contracts { command: Some(ContractsCommand::Accept { contract_id }) } => {
+            let data = api.accept_contract(&contract_id).await?;
+            show(json, &data, |data| contracts_table(std::slice::from_ref(&data.contract)))
+        }
+    }
+}
+
+/// Plays with `AUTH_TOKEN` if it's set, otherwise with the saved registration
+/// for `agent`, which can be left out if only one agent has been registered.
+fn api(agent: Option<&str>) -> Result<SpaceTradersApi> {
+    if let Ok(auth_token) = std::env::var("AUTH_TOKEN") {
+        return Ok(SpaceTradersApi::new(&auth_token));
+    }
+    let call_sign = match (agent, find_saved_agents()?.as_slice()) {
+        (Some(agent), _) => agent.to_string(),
+        (None, [only]) => only.clone(),
+        (None, []) => return Err(format!("no token: set AUTH_TOKEN, or run `register` to save one to {}", credentials_path().display()).into()),
+        (None, agents) => return Err(format!("pick an agent with --agent: {}", agents.join(", ")).into()),
+    };
+    match find_credentials(&call_sign)? {
+        Some((registration_data, _)) => Ok(SpaceTradersApi::new(&registration_data.token)),
+        None => Err(format!("no token for {call_sign}: run `register {call_sign}` to save one to {}", credentials_path().display()).into()),
+    }
+}
+
+/// Makes a last try at keeping a registration that neither credentials file
+/// could take, since the agent can't be registered again. The token itself
+/// is never shown.
+fn rescue(unsaved: UnsavedRegistration) -> Box<dyn Error> {
+    let path = std::env::temp_dir().join(format!("space-traders-{}.json", unsaved.registration_data.agent.symbol));
+    match save_credentials(&path, &unsaved.registration_data) {
+        Ok(()) => format!("{}; saved it to {} instead, set SPACE_TRADERS_CREDENTIALS to that path to play", unsaved, path.display()).into(),
+        Err(e) => format!("{}, and not to {} either: {}", unsaved, path.display(), e).into(),
+    }
+}
+
+fn show<T: Serialize>(json: bool, value: &T, table: fn(&T) -> Table) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        table(value).print();
+    }
+    Ok(())
+}
+
+fn agent_table(agent: &types::Agent) -> Table {
+    let mut table = Table::new(&["SYMBOL", "HEADQUARTERS", "CREDITS"]);
+    table.row(vec![agent.symbol.clone(), agent.headquarters.clone(), agent.credits.to_string()]);
+    table
+}
+
+fn systems_table(systems: &[types::System]) -> Table {
+    let mut table = Table::new(&["SYMBOL", "TYPE", "X", "Y", "WAYPOINTS", "FACTIONS"]);
+    for system in systems {
+        let factions: Vec<&str> = system.factions.iter().map(|faction| faction.symbol.as_str()).collect();
+        table.row(vec![
+            system.symbol.to_string(),
+            name(&system.system_type),
+            system.x.to_string(),
+            system.y.to_string(),
+            system.waypoints.len().to_string(),
+            factions.join(","),
+        ]);
+    }
+    table
+}
+
+fn waypoint_references_table(waypoints: &[types::WaypointReference]) -> Table {
+    let mut table = Table::new(&["SYMBOL", "TYPE", "X", "Y"]);
+    for waypoint in waypoints {
+        table.row(vec![waypoint.symbol.to_string(), name(&waypoint.waypoint_type), waypoint.x.to_string(), waypoint.y.to_string()]);
+    }
+    table
+}
+
+fn waypoints_table(waypoints: &[types::Waypoint]) -> Table {
+    let mut table = Table::new(&["SYMBOL", "TYPE", "X", "Y", "TRAITS"]);
+    for waypoint in waypoints {
+        let traits: Vec<&str> = waypoint.traits.iter().map(|t| t.symbol.as_str()).collect();
+        table.row(vec![
+            waypoint.reference.symbol.to_string(),
+            name(&waypoint.reference.waypoint_type),
+            waypoint.reference.x.to_string(),
+            waypoint.reference.y.to_string(),
+            traits.join(","),
+        ]);
+    }
+    table
+}
+
+fn market_table(market: &types::Market) -> Table {
+    // prices are only shown while one of our ships is at the market
+    if let Some(trade_goods) = &market.trade_goods {
+        let mut table = Table::new(&["GOOD", "SUPPLY", "BUY", "SELL", "VOLUME"]);
+        for good in trade_goods.iter() {
+            table.row(vec![
+                good.symbol.to_string(),
+                name(&good.supply),
+                good.purchase_price.to_string(),
+                good.sell_price.to_string(),
+                good.trade_volume.to_string(),
+            ]);
+        }
+        return table;
+    }
+
+    let mut table = Table::new(&["GOOD", "TRADE"]);
+    for (trade, goods) in [("IMPORT", &market.imports), ("EXPORT", &market.exports), ("EXCHANGE", &market.exchange)] {
+        for good in goods.iter() {
+            table.row(vec![good.symbol.to_string(), String::from(trade)]);
+        }
+    }
+    table
+}
+
+fn shipyard_table(shipyard: &types::Shipyard) -> Table {
+    if let Some(ships) = &shipyard.ships {
+        let mut table = Table::new(&["TYPE", "NAME", "PRICE"]);
+        for ship in ships.iter() {
+            table.row(vec![name(&ship.ship_type), ship.name.clone(), ship.purchase_price.to_string()]);
+        }
+        return table;
+    }
+
+    let mut table = Table::new(&["TYPE"]);
+    for ship_type in shipyard.ship_types.iter() {
+        table.row(vec![name(&ship_type.ship_type)]);
+    }
+    table
+}
+
+fn ships_table(ships: &[types::Ship]) -> Table {
+    let mut table = Table::new(&["SYMBOL", "ROLE", "STATUS", "WAYPOINT", "FUEL", "CARGO"]);
+    for ship in ships {
+        table.row(vec![
+            ship.symbol.to_string(),
+            name(&ship.registration.role),
+            name(&ship.nav.status),
+            ship.nav.waypoint_symbol.to_string(),
+            format!("{}/{}", ship.fuel.current, ship.fuel.capacity),
+            format!("{}/{}", ship.cargo.units, ship.cargo.capacity),
+        ]);
+    }
+    table
+}
+
+fn nav_table(nav: &types::ShipNav) -> Table {
+    let mut table = Table::new(&["STATUS", "WAYPOINT", "MODE", "ARRIVAL"]);
+    table.row(vec![
+        name(&nav.status),
+        nav.waypoint_symbol.to_string(),
+        name(&nav.flight_mode),
+        nav.route.arrival.to_rfc3339(),
+    ]);
+    table
+}
+
+fn contracts_table(contracts: &[types::Contract]) -> Table {
+    let mut table = Table::new(&["ID", "FACTION", "ACCEPTED", "FULFILLED", "DELIVER", "PAYMENT", "DEADLINE"]);
+    for contract in contracts {
+        let deliver: Vec<String> = contract.terms.deliver.iter()
+            .map(|good| format!("{}/{} {} to {}", good.units_fulfilled, good.units_required, good.trade_symbol, good.destination_symbol))
+            .collect();
+        table.row(vec![
+            contract.id.clone(),
+            contract.faction_symbol.to_string(),
+            contract.accepted.to_string(),
+            contract.fulfilled.to_string(),
+            deliver.join(", "),
+            contract.total_payment().to_string(),
+            contract.terms.deadline.to_rfc3339(),
+        ]);
+    }
+    table
+}
+
+fn drift_table(report: &DriftReport) -> Table {
+    let mut table = Table::new(&["ENDPOINT", "FIELD", "DRIFT", "SEEN"]);
+    for field in report.fields.iter() {
+        let checked = report.checked.get(&field.endpoint).copied().unwrap_or_default();
+        table.row(vec![field.endpoint.clone(), field.field.clone(), name(&field.kind), format!("{}/{}", field.seen, checked)]);
+    }
+    table
+}
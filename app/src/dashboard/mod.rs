@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+
+use space_traders_api::behaviors::StateStore;
+use space_traders_api::{error, ApiManager, SpaceTradersClient};
+
+use model::{Dashboard, Snapshot};
+
+mod model;
+mod ui;
+
+/// Polls the server every `interval` and shows the fleet until `q` is
+/// pressed. With `state`, the behavior states a runner saves there are shown
+/// too.
+pub async fn run(manager: ApiManager, interval: Duration, state: Option<PathBuf>) -> std::io::Result<()> {
+    let dashboard = Arc::new(Mutex::new(Dashboard::default()));
+    let poller = tokio::spawn(poll(manager, interval, state, dashboard.clone()));
+
+    let result = tokio::task::spawn_blocking(move || ui::run(dashboard)).await?;
+    poller.abort();
+    result
+}
+
+async fn poll(manager: ApiManager, interval: Duration, state: Option<PathBuf>, dashboard: Arc<Mutex<Dashboard>>) {
+    loop {
+        match snapshot(&manager, state.as_deref()).await {
+            Ok(snapshot) => dashboard.lock().unwrap().update(snapshot),
+            Err(e) => dashboard.lock().unwrap().log(Utc::now(), format!("poll failed: {}", e)),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn snapshot(manager: &ApiManager, state: Option<&Path>) -> error::Result<Snapshot> {
+    // through the manager's client, so that the polls keep the metrics current
+    let api = manager.client();
+    let ships = api.list_ships().await?;
+    let mut cooldowns = HashMap::new();
+    for ship in ships.iter() {
+        if let Some(cooldown) = api.get_ship_cooldown(ship.symbol.clone()).await? {
+            cooldowns.insert(ship.symbol.clone(), cooldown);
+        }
+    }
+
+    Ok(Snapshot {
+        taken_at: Utc::now(),
+        agent: api.get_agent().await?,
+        contracts: api.list_contracts().await?,
+        records: match state {
+            Some(path) => StateStore::open(path)?.all(),
+            None => HashMap::new(),
+        },
+        ships,
+        cooldowns,
+    })
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+
+use space_traders_api::behaviors::ShipRecord;
+use space_traders_api::types;
+
+// how much history to keep: a day of credits at the default interval, and a screenful or two of events
+const MAX_CREDITS: usize = 5760;
+const MAX_EVENTS: usize = 200;
+
+/// Everything one poll of the server found.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub taken_at: DateTime<Utc>,
+    pub agent: types::Agent,
+    pub ships: Vec<types::Ship>,
+    pub contracts: Vec<types::Contract>,
+    pub cooldowns: HashMap<types::ShipSymbol, types::Cooldown>,
+    /// What each ship's behavior is doing, if a runner is saving its state.
+    pub records: HashMap<types::ShipSymbol, ShipRecord>,
+}
+
+/// What the dashboard shows: the latest snapshot, and what changed between
+/// the ones before it.
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    pub latest: Option<Snapshot>,
+    pub credits: VecDeque<(DateTime<Utc>, u64)>,
+    pub events: VecDeque<(DateTime<Utc>, String)>,
+}
+
+impl Dashboard {
+    pub fn update(&mut self, snapshot: Snapshot) {
+        if let Some(previous) = self.latest.take() {
+            for event in changes(&previous, &snapshot) {
+                self.log(snapshot.taken_at, event);
+            }
+        }
+
+        self.credits.push_back((snapshot.taken_at, snapshot.agent.credits));
+        if self.credits.len() > MAX_CREDITS {
+            self.credits.pop_front();
+        }
+        self.latest = Some(snapshot);
+    }
+
+    pub fn log(&mut self, at: DateTime<Utc>, event: String) {
+        self.events.push_back((at, event));
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Ships arriving and leaving, credits changing hands and contracts moving along.
+fn changes(previous: &Snapshot, next: &Snapshot) -> Vec<String> {
+    let mut events = Vec::new();
+
+    for ship in next.ships.iter() {
+        let Some(before) = previous.ships.iter().find(|s| s.symbol == ship.symbol) else {
+            events.push(format!("{} joined the fleet at {}", ship.symbol, ship.nav.waypoint_symbol));
+            continue;
+        };
+        let was_moving = before.nav.status == types::ShipNavStatus::InTransit;
+        let is_moving = ship.nav.status == types::ShipNavStatus::InTransit;
+        if is_moving && (!was_moving || before.nav.route.destination.symbol != ship.nav.route.destination.symbol) {
+            events.push(format!("{} left for {}", ship.symbol, ship.nav.route.destination.symbol));
+        } else if was_moving && !is_moving {
+            events.push(format!("{} arrived at {}", ship.symbol, ship.nav.waypoint_symbol));
+        }
+    }
+
+    let credits = next.agent.credits as i64 - previous.agent.credits as i64;
+    if credits != 0 {
+        events.push(format!("credits {:+} to {}", credits, next.agent.credits));
+    }
+
+    for contract in next.contracts.iter() {
+        let Some(before) = previous.contracts.iter().find(|c| c.id == contract.id) else {
+            events.push(format!("new contract {}", contract.id));
+            continue;
+        };
+        if contract.fulfilled && !before.fulfilled {
+            events.push(format!("contract {} fulfilled", contract.id));
+        } else if contract.accepted && !before.accepted {
+            events.push(format!("contract {} accepted", contract.id));
+        }
+        for (good, was) in contract.terms.deliver.iter().zip(before.terms.deliver.iter()) {
+            if good.units_fulfilled > was.units_fulfilled {
+                events.push(format!("delivered {} {} for {}, {}/{}", good.units_fulfilled - was.units_fulfilled, good.trade_symbol, contract.id, good.units_fulfilled, good.units_required));
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use serde_json::json;
+    use super::{Dashboard, Snapshot};
+
+    fn snapshot(at: &str, credits: u64, status: &str, delivered: u64) -> Snapshot {
+        let waypoint = |symbol: &str| json!({
+            "symbol": symbol,
+            "type": "PLANET",
+            "systemSymbol": "X1-DF55",
+            "x": 0,
+            "y": 0,
+        });
+        let ship = json!({
+            "symbol": "BEAR-1",
+            "registration": { "name": "BEAR-1", "factionSymbol": "COSMIC", "role": "COMMAND" },
+            "nav": {
+                "systemSymbol": "X1-DF55",
+                "waypointSymbol": "X1-DF55-20250Z",
+                "route": {
+                    "destination": waypoint("X1-DF55-20250Z"),
+                    "departure": waypoint("X1-DF55-69207D"),
+                    "departureTime": "2023-07-01T00:00:00Z",
+                    "arrival": "2023-07-01T00:01:30Z",
+                },
+                "status": status,
+                "flightMode": "CRUISE",
+            },
+            "crew": { "current": 59, "required": 59, "capacity": 80, "rotation": "STRICT", "morale": 100, "wages": 0 },
+            "frame": {
+                "symbol": "FRAME_FRIGATE", "name": "Frigate", "description": "", "moduleSlots": 8,
+                "mountingPoints": 5, "fuelCapacity": 1200, "requirements": {},
+            },
+            "reactor": {
+                "symbol": "REACTOR_FISSION_I", "name": "Fission Reactor I", "description": "", "powerOutput": 31,
+                "requirements": {},
+            },
+            "engine": { "symbol": "ENGINE_ION_DRIVE_II", "name": "Ion Drive II", "description": "", "speed": 30, "requirements": {} },
+            "modules": [],
+            "mounts": [],
+            "cargo": { "capacity": 60, "units": 0, "inventory": [] },
+            "fuel": { "current": 1200, "capacity": 1200 },
+        });
+        let contract = json!({
+            "id": "contract-1",
+            "factionSymbol": "COSMIC",
+            "type": "PROCUREMENT",
+            "terms": {
+                "deadline": "2023-07-08T00:00:00Z",
+                "payment": { "onAccepted": 1000, "onFulfilled": 4000 },
+                "deliver": [{
+                    "tradeSymbol": "IRON_ORE",
+                    "destinationSymbol": "X1-DF55-20250Z",
+                    "unitsRequired": 100,
+                    "unitsFulfilled": delivered,
+                }],
+            },
+            "accepted": true,
+            "fulfilled": false,
+            "expiration": "2023-07-08T00:00:00Z",
+        });
+
+        Snapshot {
+            taken_at: at.parse().unwrap(),
+            agent: serde_json::from_value(json!({
+                "accountId": "account",
+                "symbol": "BEAR",
+                "headquarters": "X1-DF55-20250Z",
+                "credits": credits,
+                "startingFaction": "COSMIC",
+            })).unwrap(),
+            ships: vec![serde_json::from_value(ship).unwrap()],
+            contracts: vec![serde_json::from_value(contract).unwrap()],
+            cooldowns: HashMap::new(),
+            records: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_update() {
+        let mut dashboard = Dashboard::default();
+        dashboard.update(snapshot("2023-07-01T00:00:00Z", 1000, "IN_TRANSIT", 0));
+        dashboard.update(snapshot("2023-07-01T00:02:00Z", 1000, "IN_TRANSIT", 0));
+        assert!(dashboard.events.is_empty());
+
+        dashboard.update(snapshot("2023-07-01T00:04:00Z", 1500, "DOCKED", 20));
+        let events: Vec<&str> = dashboard.events.iter().map(|(_, event)| event.as_str()).collect();
+        assert_eq!(events, vec![
+            "BEAR-1 arrived at X1-DF55-20250Z",
+            "credits +500 to 1500",
+            "delivered 20 IRON_ORE for contract-1, 20/100",
+        ]);
+        assert_eq!(dashboard.credits.len(), 3);
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Sparkline, Table};
+use ratatui::Frame;
+
+use space_traders_api::types;
+
+use crate::dashboard::Dashboard;
+use crate::table::name;
+
+// redraw often enough for the countdowns to tick over every second
+const FRAME: Duration = Duration::from_millis(250);
+
+/// Draws the dashboard until `q` or Esc is pressed. Up and down scroll the
+/// event log back and forth.
+pub fn run(dashboard: Arc<Mutex<Dashboard>>) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut scroll = 0;
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| draw(frame, &dashboard.lock().unwrap(), scroll)) {
+            break Err(e);
+        }
+
+        match event::poll(FRAME) {
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => break Err(e),
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+                KeyCode::Up => scroll += 1,
+                KeyCode::Down => scroll = usize::saturating_sub(scroll, 1),
+                KeyCode::End => scroll = 0,
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard, scroll: usize) {
+    let now = Utc::now();
+    let [header, ships, middle, events] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(6),
+        Constraint::Length(8),
+        Constraint::Length(10),
+    ]).areas(frame.area());
+    let [credits, contracts] = Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(middle);
+
+    let Some(snapshot) = &dashboard.latest else {
+        frame.render_widget(Paragraph::new("waiting for the first poll..."), header);
+        draw_events(frame, events, dashboard, scroll);
+        return;
+    };
+
+    let title = format!(
+        "{}  {} credits  updated {} ago  (q to quit)",
+        snapshot.agent.symbol,
+        snapshot.agent.credits,
+        countdown(now, snapshot.taken_at),
+    );
+    frame.render_widget(Paragraph::new(title).style(Style::default().add_modifier(Modifier::BOLD)), header);
+
+    let rows = snapshot.ships.iter().map(|ship| {
+        let nav = &ship.nav;
+        let status = match nav.status {
+            types::ShipNavStatus::InTransit => format!("-> {} in {}", nav.route.destination.symbol, countdown(nav.route.arrival, now)),
+            _ => name(&nav.status),
+        };
+        let behavior = snapshot.records.get(&ship.symbol)
+            .map(|record| format!("{} {}", record.behavior, state_name(&record.state)))
+            .unwrap_or_default();
+        let cooldown = snapshot.cooldowns.get(&ship.symbol)
+            .and_then(|cooldown| cooldown.expiration)
+            .filter(|expiration| *expiration > now)
+            .map(|expiration| countdown(expiration, now))
+            .unwrap_or_default();
+
+        Row::new(vec![
+            Cell::from(ship.symbol.to_string()),
+            Cell::from(nav.waypoint_symbol.to_string()),
+            Cell::from(status),
+            Cell::from(format!("{}/{}", ship.fuel.current, ship.fuel.capacity)),
+            Cell::from(fill(ship.cargo.units, ship.cargo.capacity)),
+            Cell::from(behavior),
+            Cell::from(cooldown),
+        ])
+    });
+    let table = Table::new(rows, [
+        Constraint::Length(14),
+        Constraint::Length(16),
+        Constraint::Min(24),
+        Constraint::Length(10),
+        Constraint::Length(18),
+        Constraint::Min(20),
+        Constraint::Length(9),
+    ])
+        .header(Row::new(vec!["SHIP", "WAYPOINT", "STATUS", "FUEL", "CARGO", "BEHAVIOR", "COOLDOWN"])
+            .style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title("Fleet"));
+    frame.render_widget(table, ships);
+
+    let history: Vec<u64> = dashboard.credits.iter().map(|(_, credits)| *credits).collect();
+    let low = history.iter().min().copied().unwrap_or(0);
+    // plot from the lowest point, so that small changes on a large balance still show
+    let history: Vec<u64> = history.iter().map(|credits| credits - low + 1).collect();
+    let shown = &history[history.len().saturating_sub(credits.width.saturating_sub(2) as usize)..];
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!("Credits (from {})", low)))
+            .data(shown)
+            .style(Style::default().fg(Color::Yellow)),
+        credits,
+    );
+
+    let items: Vec<ListItem> = snapshot.contracts.iter()
+        .filter(|contract| !contract.fulfilled && contract.expiration > now)
+        .flat_map(|contract| {
+            let status = if contract.accepted { "accepted" } else { "offered" };
+            let mut lines = vec![ListItem::new(format!("{} {}, due in {}", contract.id, status, countdown(contract.terms.deadline, now)))];
+            for good in contract.terms.deliver.iter() {
+                lines.push(ListItem::new(format!(
+                    "  {} to {}: {}",
+                    good.trade_symbol,
+                    good.destination_symbol,
+                    fill(good.units_fulfilled, good.units_required),
+                )));
+            }
+            lines
+        })
+        .collect();
+    frame.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title("Contracts")), contracts);
+
+    draw_events(frame, events, dashboard, scroll);
+}
+
+fn draw_events(frame: &mut Frame, area: Rect, dashboard: &Dashboard, scroll: usize) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = dashboard.events.len().saturating_sub(scroll);
+    let lines: Vec<Line> = dashboard.events.iter()
+        .take(end)
+        .skip(end.saturating_sub(height))
+        .map(|(at, event)| Line::from(format!("{} {}", at.format("%H:%M:%S"), event)))
+        .collect();
+    let title = match scroll {
+        0 => String::from("Events"),
+        _ => format!("Events ({} newer, End to follow)", scroll),
+    };
+    frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+/// How long from `from` until `to`, e.g. `1m05s`.
+fn countdown(to: DateTime<Utc>, from: DateTime<Utc>) -> String {
+    let seconds = (to - from).num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// e.g. `[#####     ] 30/60`.
+fn fill(units: u64, capacity: u64) -> String {
+    let filled = match capacity {
+        0 => 0,
+        _ => (units * 10 / capacity).min(10) as usize,
+    };
+    format!("[{}{}] {}/{}", "#".repeat(filled), " ".repeat(10 - filled), units, capacity)
+}
+
+fn state_name(state: &space_traders_api::behaviors::ShipState) -> String {
+    serde_json::to_value(state).ok()
+        .and_then(|value| value.get("state").and_then(|name| name.as_str()).map(str::to_string))
+        .unwrap_or_default()
+}
@@ -0,0 +1,65 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use space_traders_api::behaviors::{BehaviorRuntime, ContractBehavior, MarketCache, MiningBehavior, MiningConfig, ShipBehavior, StateStore, SurveyStore};
+use space_traders_api::control::{self, BehaviorFactory, ControlState};
+use space_traders_api::scheduler::{RateLimit, Scheduler};
+use space_traders_api::{error, ApiManager, SpaceTradersApi, types};
+
+/// Runs a behavior on every ship that has one, until they've all finished,
+/// with the control API served on `port` of localhost. Ships pick up the
+/// behavior they were last running in `state`, and otherwise mine if they're
+/// excavators or work contracts if they're the command ship.
+pub async fn run(api: SpaceTradersApi, state: PathBuf, port: u16) -> error::Result<()> {
+    let manager = Arc::new(ApiManager::new(Arc::new(api)).await?);
+    let markets = Arc::new(MarketCache::default());
+    let surveys = Arc::new(SurveyStore::default());
+    let behaviors: BehaviorFactory = {
+        let markets = markets.clone();
+        Arc::new(move |name: &str, _: &types::ShipSymbol| match name {
+            "mining" => Some(Box::new(MiningBehavior::new(MiningConfig::default(), markets.clone(), surveys.clone())) as Box<dyn ShipBehavior>),
+            "contract" => Some(Box::new(ContractBehavior::new(markets.clone(), surveys.clone())) as Box<dyn ShipBehavior>),
+            _ => None,
+        })
+    };
+
+    // built on the manager's client, so the control API's event stream sees the game's events
+    let mut runtime = BehaviorRuntime::new(manager.client(), Scheduler::new(RateLimit::default()), StateStore::open(state)?);
+    let spawned = runtime.spawn_fleet(|ship, stored| {
+        let default = match ship.registration.role {
+            types::ShipRole::Excavator => Some("mining"),
+            types::ShipRole::Command => Some("contract"),
+            _ => None,
+        };
+        stored.and_then(|stored| behaviors(stored, &ship.symbol))
+            .or_else(|| default.and_then(|name| behaviors(name, &ship.symbol)))
+    }).await?;
+    eprintln!("started {} ships", spawned.len());
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let server = control::serve(addr, ControlState {
+        fleet: runtime.control(),
+        manager,
+        markets,
+        behaviors,
+    });
+    eprintln!("control API on http://{}", addr);
+
+    let fleet = async move {
+        loop {
+            match runtime.join_next().await {
+                Some((ship_symbol, Ok(()))) => eprintln!("{}: done", ship_symbol),
+                Some((ship_symbol, Err(e))) => eprintln!("{}: stopped: {}", ship_symbol, e),
+                // with every ship finished, the control API can still put them back to work
+                None => runtime.next_reassignment().await,
+            }
+        }
+    };
+
+    tokio::select! {
+        // the server only returns if it couldn't start
+        result = server => result,
+        never = fleet => never,
+    }
+}
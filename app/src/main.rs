@@ -1,4 +1,151 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use space_traders_api::types::{FactionSymbol, ShipSymbol, SystemSymbol, WaypointSymbol, WaypointTraitSymbol};
+
+mod commands;
+mod dashboard;
+mod fleet;
+mod table;
+mod telemetry;
+
+/// Plays SpaceTraders from the command line. Uses `AUTH_TOKEN` if it's set,
+/// and otherwise the token saved by `register`.
+#[derive(Debug, Parser)]
+pub struct Cli {
+    /// Print what the server returned as JSON instead of a table.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Serve Prometheus metrics at `http://ADDR/metrics` while running, e.g. `127.0.0.1:9091`.
+    #[arg(long, global = true, value_name = "ADDR")]
+    pub metrics: Option<SocketAddr>,
+
+    /// Append every response from the server to FILE, to check for drift later.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Report fields the server sent that the types don't model, or that it left out.
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Play as this saved agent; needed once more than one has been registered.
+    #[arg(long, global = true, value_name = "CALL_SIGN")]
+    pub agent: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Register a new agent and save its token.
+    Register {
+        call_sign: String,
+        #[arg(long, default_value = "COSMIC")]
+        faction: FactionSymbol,
+    },
+    /// Show the agent's credits and headquarters.
+    Agent,
+    /// List systems, or show one.
+    #[command(subcommand)]
+    Systems(SystemsCommand),
+    /// List the waypoints of a system.
+    Waypoints {
+        #[arg(value_parser = SystemSymbol::new)]
+        system: SystemSymbol,
+        /// Only waypoints with this trait, e.g. MARKETPLACE. May be repeated.
+        #[arg(long = "trait")]
+        traits: Vec<WaypointTraitSymbol>,
+    },
+    /// Show the goods a market trades, with prices if a ship is there.
+    Market {
+        #[arg(value_parser = WaypointSymbol::new)]
+        waypoint: WaypointSymbol,
+    },
+    /// Show the ships a shipyard sells, with prices if a ship is there.
+    Shipyard {
+        #[arg(value_parser = WaypointSymbol::new)]
+        waypoint: WaypointSymbol,
+    },
+    /// List the fleet.
+    Ships,
+    /// Fly a ship to a waypoint in its system.
+    Navigate {
+        ship: ShipSymbol,
+        #[arg(value_parser = WaypointSymbol::new)]
+        waypoint: WaypointSymbol,
+    },
+    /// Dock a ship at its waypoint.
+    Dock { ship: ShipSymbol },
+    /// Move a ship into orbit of its waypoint.
+    Orbit { ship: ShipSymbol },
+    /// Fill a docked ship's tanks.
+    Refuel { ship: ShipSymbol },
+    /// Watch the fleet live, until q is pressed.
+    Dashboard {
+        /// Seconds between polls of the server.
+        #[arg(long, default_value_t = 15)]
+        interval: u64,
+        /// The state file of a running fleet, to show what each ship's behavior is doing.
+        #[arg(long)]
+        state: Option<PathBuf>,
+    },
+    /// Run the fleet's behaviors, steered through a control API on localhost.
+    Run {
+        /// The file ship states are kept in, so a restart carries on where the fleet left off.
+        #[arg(long, default_value = "ship-states.json")]
+        state: PathBuf,
+        /// The port the control API listens on.
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+    },
+    /// List contracts, or accept one.
+    Contracts {
+        #[command(subcommand)]
+        command: Option<ContractsCommand>,
+    },
+    /// Report where the responses in a file written by `--record` have drifted from the types.
+    Drift { session: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SystemsCommand {
+    /// List systems, a page of 20 at a time.
+    List {
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show a system and its waypoints.
+    Get {
+        #[arg(value_parser = SystemSymbol::new)]
+        system: SystemSymbol,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ContractsCommand {
+    List,
+    Accept { contract_id: String },
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    Ok(())
-}
\ No newline at end of file
+async fn main() {
+    let telemetry = telemetry::init();
+    let cli = Cli::parse();
+    if let Some(addr) = cli.metrics {
+        if let Err(e) = telemetry::serve_metrics(addr) {
+            eprintln!("error: could not serve metrics on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = commands::run(cli).await {
+        eprintln!("error: {}", e);
+        drop(telemetry);
+        std::process::exit(1);
+    }
+}
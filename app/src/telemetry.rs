@@ -0,0 +1,70 @@
+//! Where the client's spans and logs go. They're written to stderr as
+//! filtered by `RUST_LOG`, e.g. `RUST_LOG=space_traders_api=debug`, and
+//! nothing is written without it, so tables and the dashboard stay clean.
+//! Built with the `otel` feature, spans are also exported over OTLP to the
+//! collector at `OTEL_EXPORTER_OTLP_ENDPOINT` when it's set. Metrics are
+//! served for Prometheus to scrape on request.
+
+use std::net::SocketAddr;
+
+use metrics_exporter_prometheus::{BuildError, PrometheusBuilder};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Flushes exported spans when dropped, so keep it until the program exits.
+pub struct Telemetry(());
+
+pub fn init() -> Telemetry {
+    let logs = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off")));
+
+    let registry = tracing_subscriber::registry().with(logs);
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel::layer().map(|layer| layer.with_filter(EnvFilter::new("space_traders_api=info"))));
+    registry.init();
+
+    Telemetry(())
+}
+
+/// Serves the API client's metrics on `addr` in the background. Prometheus
+/// can scrape any path, but `/metrics` is the one to configure.
+pub fn serve_metrics(addr: SocketAddr) -> Result<(), BuildError> {
+    PrometheusBuilder::new().with_http_listener(addr).install()?;
+    space_traders_api::metrics::describe();
+    Ok(())
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    pub fn layer<S: Subscriber + for<'a> LookupSpan<'a>>() -> Option<impl Layer<S>> {
+        std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+            .with_trace_config(trace::config().with_resource(Resource::new([KeyValue::new("service.name", "space-traders")])))
+            .install_batch(runtime::Tokio);
+
+        match tracer {
+            Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+            Err(e) => {
+                eprintln!("not exporting spans: {}", e);
+                None
+            }
+        }
+    }
+}
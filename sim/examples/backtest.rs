@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use space_traders_api::behaviors::{BehaviorRuntime, MarketCache, MiningBehavior, MiningConfig, StateStore, SurveyStore};
+use space_traders_api::scheduler::{RateLimit, Scheduler};
+use space_traders_api::types::{ShipType, WaypointSymbol};
+use space_traders_sim::{run_for, SimClient, World};
+
+const WEEK: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Mines for a simulated week with `drones` mining drones, returning the credits gained.
+async fn backtest(drones: usize, config: MiningConfig) -> i64 {
+    let epoch = "2023-07-01T00:00:00Z".parse().unwrap();
+    let mut world = World::starter("BACKTEST", epoch, 42);
+    let headquarters = WaypointSymbol::new("X1-SIM1-A1").unwrap();
+    let ships: Vec<_> = (0..drones).map(|_| world.add_ship(ShipType::ShipMiningDrone, &headquarters)).collect();
+    let starting_credits = world.credits() as i64;
+
+    let api = Arc::new(SimClient::new(world));
+    let markets = Arc::new(MarketCache::default());
+    let surveys = Arc::new(SurveyStore::default());
+    let mut runtime = BehaviorRuntime::new(api.clone(), Scheduler::new(RateLimit::default()), StateStore::in_memory());
+    for ship in ships {
+        runtime.spawn(ship, Box::new(MiningBehavior::new(config.clone(), markets.clone(), surveys.clone())));
+    }
+
+    for (ship_symbol, result) in run_for(runtime, WEEK).await {
+        println!("{} stopped early: {:?}", ship_symbol, result);
+    }
+    let credits = api.world().credits() as i64;
+    credits - starting_credits
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let strategies = [
+        ("1 drone, keep everything", 1, MiningConfig { min_unit_value: 0, ..MiningConfig::default() }),
+        ("1 drone, jettison anything under 30", 1, MiningConfig { min_unit_value: 30, ..MiningConfig::default() }),
+        ("4 drones, keep everything", 4, MiningConfig { min_unit_value: 0, ..MiningConfig::default() }),
+    ];
+
+    for (name, drones, config) in strategies {
+        // each strategy gets its own game, and its own clock
+        let started = std::time::Instant::now();
+        let profit = space_traders_sim::runtime()?.block_on(backtest(drones, config));
+        println!("{}: {} credits in a week, simulated in {:.1?}", name, profit, started.elapsed());
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use space_traders_api::error::Result;
+use space_traders_api::{SpaceTradersClient, types};
+
+use crate::clock::VirtualClock;
+use crate::rules::{error, NOT_FOUND};
+use crate::world::World;
+
+// the real client's default, and the most it fetches per page
+const PAGE_LIMIT: usize = 20;
+
+/// Plays the game against a `World` instead of the server, on the clock of
+/// the runtime it was created in.
+#[derive(Debug)]
+pub struct SimClient {
+    clock: VirtualClock,
+    world: Mutex<World>,
+}
+
+impl SimClient {
+    /// Starts the game at the world's epoch. Create it inside the runtime the game will run on.
+    pub fn new(world: World) -> Self {
+        SimClient {
+            clock: VirtualClock::new(world.epoch()),
+            world: Mutex::new(world),
+        }
+    }
+
+    /// The world as of now, for looking at how a run is going.
+    pub fn world(&self) -> MutexGuard<'_, World> {
+        let mut world = self.world.lock().unwrap();
+        world.settle(self.clock.now());
+        world
+    }
+
+    fn play<T, F: FnOnce(&mut World, DateTime<Utc>) -> Result<T>>(&self, f: F) -> Result<T> {
+        let now = self.clock.now();
+        f(&mut self.world(), now)
+    }
+}
+
+#[async_trait]
+impl SpaceTradersClient for SimClient {
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    async fn get_agent(&self) -> Result<types::Agent> {
+        self.play(|world, _| Ok(world.agent.clone()))
+    }
+
+    async fn list_factions(&self) -> Result<Vec<types::Faction>> {
+        self.play(|world, _| Ok(vec![world.faction()]))
+    }
+
+    async fn list_contracts(&self) -> Result<Vec<types::Contract>> {
+        self.play(|world, _| Ok(world.contracts.clone()))
+    }
+
+    async fn get_contract(&self, contract_id: &str) -> Result<types::Contract> {
+        self.play(|world, _| world.contract(contract_id))
+    }
+
+    async fn accept_contract(&self, contract_id: &str) -> Result<types::AcceptContractData> {
+        self.play(|world, now| world.accept_contract(contract_id, now))
+    }
+
+    async fn deliver_contract(&self, contract_id: &str, ship_symbol: types::ShipSymbol, trade_symbol: types::MarketGoodSymbol, units: u64) -> Result<types::DeliverContractData> {
+        self.play(|world, now| world.deliver_contract(contract_id, &ship_symbol, &trade_symbol, units, now))
+    }
+
+    async fn fulfill_contract(&self, contract_id: &str) -> Result<types::FulfillContractData> {
+        self.play(|world, _| world.fulfill_contract(contract_id))
+    }
+
+    async fn negotiate_contract(&self, ship_symbol: types::ShipSymbol) -> Result<types::Contract> {
+        self.play(|world, now| world.negotiate_contract(&ship_symbol, now))
+    }
+
+    async fn list_systems(&self, page: Option<u32>, limit: Option<usize>) -> Result<Vec<types::System>> {
+        self.play(|world, _| Ok(paginate(world.list_systems(), page, limit)))
+    }
+
+    async fn get_system(&self, system_symbol: types::SystemSymbol) -> Result<types::System> {
+        self.play(|world, _| {
+            world.list_systems().into_iter()
+                .find(|system| system.symbol == system_symbol)
+                .ok_or_else(|| error(NOT_FOUND, format!("system {} not found", system_symbol)))
+        })
+    }
+
+    async fn list_system_waypoints(&self, system_symbol: types::SystemSymbol, page: Option<u32>, limit: Option<usize>) -> Result<Vec<types::Waypoint>> {
+        self.play(|world, _| {
+            let waypoints = world.list_waypoints(&system_symbol);
+            if waypoints.is_empty() {
+                return Err(error(NOT_FOUND, format!("system {} not found", system_symbol)));
+            }
+            Ok(paginate(waypoints, page, limit))
+        })
+    }
+
+    async fn get_waypoint(&self, waypoint_symbol: types::WaypointSymbol) -> Result<types::Waypoint> {
+        self.play(|world, _| world.waypoint(&waypoint_symbol).cloned())
+    }
+
+    async fn get_market(&self, waypoint_symbol: types::WaypointSymbol) -> Result<types::Market> {
+        self.play(|world, _| world.get_market(&waypoint_symbol))
+    }
+
+    async fn get_shipyard(&self, waypoint_symbol: types::WaypointSymbol) -> Result<types::Shipyard> {
+        self.play(|world, _| world.get_shipyard(&waypoint_symbol))
+    }
+
+    async fn get_jump_gate(&self, waypoint_symbol: types::WaypointSymbol) -> Result<types::JumpGate> {
+        self.play(|world, _| world.jump_gate(&waypoint_symbol))
+    }
+
+    async fn list_ships(&self) -> Result<Vec<types::Ship>> {
+        self.play(|world, _| Ok(world.ships.values().map(|sim| sim.ship.clone()).collect()))
+    }
+
+    async fn get_ship(&self, ship_symbol: types::ShipSymbol) -> Result<types::Ship> {
+        self.play(|world, _| Ok(world.ship(&ship_symbol)?.ship.clone()))
+    }
+
+    async fn purchase_ship(&self, ship_type: types::ShipType, waypoint_symbol: types::WaypointSymbol) -> Result<types::PurchaseShipData> {
+        self.play(|world, now| world.purchase_ship(ship_type, &waypoint_symbol, now))
+    }
+
+    async fn orbit_ship(&self, ship_symbol: types::ShipSymbol) -> Result<types::ShipNav> {
+        self.play(|world, _| world.orbit(&ship_symbol))
+    }
+
+    async fn dock_ship(&self, ship_symbol: types::ShipSymbol) -> Result<types::ShipNav> {
+        self.play(|world, _| world.dock(&ship_symbol))
+    }
+
+    async fn navigate_ship(&self, ship_symbol: types::ShipSymbol, waypoint_symbol: types::WaypointSymbol) -> Result<types::NavigateData> {
+        self.play(|world, now| world.navigate(&ship_symbol, &waypoint_symbol, now))
+    }
+
+    async fn refuel_ship(&self, ship_symbol: types::ShipSymbol) -> Result<types::RefuelData> {
+        self.play(|world, now| world.refuel(&ship_symbol, now))
+    }
+
+    async fn get_ship_cooldown(&self, ship_symbol: types::ShipSymbol) -> Result<Option<types::Cooldown>> {
+        self.play(|world, now| world.cooldown(&ship_symbol, now))
+    }
+
+    async fn create_survey(&self, ship_symbol: types::ShipSymbol) -> Result<types::CreateSurveyData> {
+        self.play(|world, now| world.survey(&ship_symbol, now))
+    }
+
+    async fn extract_resources(&self, ship_symbol: types::ShipSymbol, survey: Option<types::Survey>) -> Result<types::ExtractData> {
+        self.play(|world, now| world.extract(&ship_symbol, survey, now))
+    }
+
+    async fn jettison_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> Result<types::ShipCargo> {
+        self.play(|world, _| world.jettison(&ship_symbol, &symbol, units))
+    }
+
+    async fn sell_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> Result<types::SellCargoData> {
+        self.play(|world, now| world.sell(&ship_symbol, &symbol, units, now))
+    }
+
+    async fn purchase_cargo(&self, ship_symbol: types::ShipSymbol, symbol: types::MarketGoodSymbol, units: u64) -> Result<types::PurchaseCargoData> {
+        self.play(|world, now| world.purchase(&ship_symbol, &symbol, units, now))
+    }
+}
+
+/// Pages like the real client: from `page` on, as many items as `limit` asks for.
+fn paginate<T>(items: Vec<T>, page: Option<u32>, limit: Option<usize>) -> Vec<T> {
+    let skip = (page.unwrap_or(1).max(1) as usize - 1) * PAGE_LIMIT;
+    items.into_iter().skip(skip).take(limit.unwrap_or(usize::MAX)).collect()
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use tokio::time::Instant;
+
+/// Game time that follows tokio's clock. On a runtime started paused, tokio
+/// jumps straight to the next timer whenever every task is waiting, so a
+/// week of game time passes as fast as the behaviors can step.
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    epoch: DateTime<Utc>,
+    started: Instant,
+}
+
+impl VirtualClock {
+    /// Starts at `epoch`. Create it inside the runtime the game will run on.
+    pub fn new(epoch: DateTime<Utc>) -> Self {
+        VirtualClock {
+            epoch,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.epoch + chrono::Duration::from_std(self.started.elapsed()).unwrap()
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+
+use space_traders_api::error::Result;
+use space_traders_api::types;
+
+use crate::fleet::{docked, remove_cargo};
+use crate::rules::*;
+use crate::world::World;
+
+impl World {
+    /// Draws up a procurement contract from one of the offers, if there are any.
+    pub(crate) fn offer_contract(&mut self, now: DateTime<Utc>) -> Option<types::Contract> {
+        if self.contract_offers.is_empty() {
+            return None;
+        }
+        let index = self.rng.range(0, self.contract_offers.len() as u64 - 1) as usize;
+        let offer = self.contract_offers[index].clone();
+        let units = self.rng.range(3, 9) * 10;
+        let payment = offer.unit_payment * units;
+
+        let contract = types::Contract {
+            id: format!("sim-contract-{}", self.next_id()),
+            faction_symbol: self.faction_symbol.clone(),
+            contract_type: types::ContractType::Procurement,
+            terms: types::ContractTerms {
+                deadline: now + Duration::days(7),
+                payment: types::ContractPayment {
+                    on_accepted: payment / 4,
+                    on_fulfilled: payment - payment / 4,
+                },
+                deliver: vec![types::ContractDeliverGood {
+                    trade_symbol: offer.trade_symbol,
+                    destination_symbol: offer.destination_symbol,
+                    units_required: units,
+                    units_fulfilled: 0,
+                }],
+            },
+            accepted: false,
+            fulfilled: false,
+            expiration: now + Duration::days(7),
+            deadline_to_accept: Some(now + Duration::days(1)),
+        };
+        self.contracts.push(contract.clone());
+        Some(contract)
+    }
+
+    pub(crate) fn contract(&self, contract_id: &str) -> Result<types::Contract> {
+        self.contracts.iter()
+            .find(|contract| contract.id == contract_id)
+            .cloned()
+            .ok_or_else(|| error(NOT_FOUND, format!("contract {} not found", contract_id)))
+    }
+
+    fn contract_mut(&mut self, contract_id: &str) -> Result<&mut types::Contract> {
+        self.contracts.iter_mut()
+            .find(|contract| contract.id == contract_id)
+            .ok_or_else(|| error(NOT_FOUND, format!("contract {} not found", contract_id)))
+    }
+
+    pub(crate) fn accept_contract(&mut self, contract_id: &str, now: DateTime<Utc>) -> Result<types::AcceptContractData> {
+        let contract = self.contract_mut(contract_id)?;
+        if contract.accepted {
+            return Err(error(CONTRACT_ALREADY_ACCEPTED, format!("contract {} has already been accepted", contract_id)));
+        }
+        if contract.deadline_to_accept.map(|deadline| deadline <= now).unwrap_or(false) {
+            return Err(error(CONTRACT_DEADLINE, format!("contract {} can no longer be accepted", contract_id)));
+        }
+
+        contract.accepted = true;
+        let contract = contract.clone();
+        self.agent.credits += contract.terms.payment.on_accepted;
+        Ok(types::AcceptContractData {
+            agent: self.agent.clone(),
+            contract,
+        })
+    }
+
+    pub(crate) fn deliver_contract(&mut self, contract_id: &str, ship_symbol: &types::ShipSymbol, trade_symbol: &types::MarketGoodSymbol, units: u64, now: DateTime<Utc>) -> Result<types::DeliverContractData> {
+        let sim = self.ship(ship_symbol)?;
+        docked(sim)?;
+        let waypoint_symbol = sim.ship.nav.waypoint_symbol.clone();
+
+        let contract = self.contract_mut(contract_id)?;
+        if !contract.accepted {
+            return Err(error(CONTRACT_NOT_ACCEPTED, format!("contract {} has not been accepted", contract_id)));
+        }
+        if contract.fulfilled {
+            return Err(error(CONTRACT_ALREADY_FULFILLED, format!("contract {} has already been fulfilled", contract_id)));
+        }
+        if contract.terms.deadline <= now {
+            return Err(error(CONTRACT_DEADLINE, format!("contract {} is past its deadline", contract_id)));
+        }
+        let Some(good) = contract.terms.deliver.iter_mut()
+            .find(|good| good.trade_symbol == *trade_symbol && good.destination_symbol == waypoint_symbol) else {
+            return Err(error(CONTRACT_INVALID_DELIVERY, format!("contract {} doesn't take {} at {}", contract_id, trade_symbol, waypoint_symbol)));
+        };
+        if units > good.units_remaining() {
+            return Err(error(CONTRACT_INVALID_DELIVERY, format!("contract {} only needs {} more {}", contract_id, good.units_remaining(), trade_symbol)));
+        }
+        good.units_fulfilled += units;
+        let contract = contract.clone();
+
+        let cargo = &mut self.ship_mut(ship_symbol)?.ship.cargo;
+        remove_cargo(cargo, ship_symbol, trade_symbol, units)?;
+        Ok(types::DeliverContractData {
+            contract,
+            cargo: cargo.clone(),
+        })
+    }
+
+    pub(crate) fn fulfill_contract(&mut self, contract_id: &str) -> Result<types::FulfillContractData> {
+        let contract = self.contract_mut(contract_id)?;
+        if contract.fulfilled {
+            return Err(error(CONTRACT_ALREADY_FULFILLED, format!("contract {} has already been fulfilled", contract_id)));
+        }
+        if !contract.accepted || !contract.is_delivered() {
+            return Err(error(CONTRACT_TERMS_NOT_MET, format!("contract {} hasn't been delivered", contract_id)));
+        }
+
+        contract.fulfilled = true;
+        let contract = contract.clone();
+        self.agent.credits += contract.terms.payment.on_fulfilled;
+        Ok(types::FulfillContractData {
+            agent: self.agent.clone(),
+            contract,
+        })
+    }
+
+    /// A new contract from the faction, as long as no other is still on offer or under way.
+    pub(crate) fn negotiate_contract(&mut self, ship_symbol: &types::ShipSymbol, now: DateTime<Utc>) -> Result<types::Contract> {
+        docked(self.ship(ship_symbol)?)?;
+        let active = self.contracts.iter().any(|contract| {
+            let deadline = match contract.accepted {
+                true => Some(contract.terms.deadline),
+                false => contract.deadline_to_accept,
+            };
+            !contract.fulfilled && deadline.map(|deadline| deadline > now).unwrap_or(false)
+        });
+        if active {
+            return Err(error(CONTRACT_ACTIVE, "an open contract has to be finished first"));
+        }
+
+        self.offer_contract(now)
+            .ok_or_else(|| error(NOT_FOUND, "no faction has work on offer"))
+    }
+}
@@ -0,0 +1,285 @@
+use chrono::{DateTime, Duration, Utc};
+
+use space_traders_api::error::Result;
+use space_traders_api::types;
+
+use crate::rules::*;
+use crate::ships::ShipTemplate;
+use crate::world::{SimShip, SimSurvey, World};
+
+impl World {
+    pub(crate) fn orbit(&mut self, ship_symbol: &types::ShipSymbol) -> Result<types::ShipNav> {
+        let ship = &mut self.ship_mut(ship_symbol)?.ship;
+        if ship.nav.status == types::ShipNavStatus::InTransit {
+            return Err(error(SHIP_IN_TRANSIT, format!("ship {} is in transit", ship_symbol)));
+        }
+        ship.nav.status = types::ShipNavStatus::InOrbit;
+        Ok(ship.nav.clone())
+    }
+
+    pub(crate) fn dock(&mut self, ship_symbol: &types::ShipSymbol) -> Result<types::ShipNav> {
+        let ship = &mut self.ship_mut(ship_symbol)?.ship;
+        if ship.nav.status == types::ShipNavStatus::InTransit {
+            return Err(error(SHIP_IN_TRANSIT, format!("ship {} is in transit", ship_symbol)));
+        }
+        ship.nav.status = types::ShipNavStatus::Docked;
+        Ok(ship.nav.clone())
+    }
+
+    pub(crate) fn navigate(&mut self, ship_symbol: &types::ShipSymbol, destination: &types::WaypointSymbol, now: DateTime<Utc>) -> Result<types::NavigateData> {
+        let sim = self.ship(ship_symbol)?;
+        in_orbit(sim)?;
+        let nav = &sim.ship.nav;
+        if nav.waypoint_symbol == *destination {
+            return Err(error(SAME_DESTINATION, format!("ship {} is already at {}", ship_symbol, destination)));
+        }
+        if destination.system_symbol() != nav.system_symbol {
+            return Err(error(NOT_FOUND, format!("waypoint {} is not in system {}", destination, nav.system_symbol)));
+        }
+
+        let departure = self.route_waypoint(&nav.waypoint_symbol);
+        let arrival = self.waypoint(destination)?;
+        let distance = arrival.reference.distance_to(departure.x, departure.y);
+        let fuel = match sim.ship.fuel.capacity {
+            // probes run on solar power
+            0 => 0,
+            _ => fuel_cost(distance, &nav.flight_mode),
+        };
+        if fuel > sim.ship.fuel.current {
+            return Err(error(INSUFFICIENT_FUEL, format!("ship {} needs {} fuel to reach {}", ship_symbol, fuel, destination)));
+        }
+
+        let duration = travel_time(distance, sim.template.speed, &nav.flight_mode);
+        let destination = self.route_waypoint(destination);
+        let ship = &mut self.ship_mut(ship_symbol)?.ship;
+        ship.fuel.current -= fuel;
+        ship.fuel.consumed = Some(types::ShipFuelConsumed {
+            amount: fuel,
+            timestamp: now,
+        });
+        ship.nav.waypoint_symbol = destination.symbol.clone();
+        ship.nav.status = types::ShipNavStatus::InTransit;
+        ship.nav.route = types::ShipNavRoute {
+            destination,
+            departure,
+            departure_time: now,
+            arrival: now + duration,
+        };
+
+        Ok(types::NavigateData {
+            fuel: ship.fuel.clone(),
+            nav: ship.nav.clone(),
+        })
+    }
+
+    pub(crate) fn cooldown(&self, ship_symbol: &types::ShipSymbol, now: DateTime<Utc>) -> Result<Option<types::Cooldown>> {
+        let sim = self.ship(ship_symbol)?;
+        Ok(sim.cooldown_until.filter(|until| *until > now).map(|until| cooldown(ship_symbol, until - now, until, now)))
+    }
+
+    pub(crate) fn survey(&mut self, ship_symbol: &types::ShipSymbol, now: DateTime<Utc>) -> Result<types::CreateSurveyData> {
+        let sim = self.ship(ship_symbol)?;
+        in_orbit(sim)?;
+        if !sim.template.can_survey {
+            return Err(error(MISSING_MOUNT, format!("ship {} has no surveyor", ship_symbol)));
+        }
+        cooled_down(sim, now)?;
+        let waypoint_symbol = sim.ship.nav.waypoint_symbol.clone();
+        let deposits = self.deposits.get(waypoint_symbol.waypoint())
+            .ok_or_else(|| error(NOT_AN_ASTEROID_FIELD, format!("{} has nothing to survey", waypoint_symbol)))?
+            .clone();
+
+        let mut surveys = Vec::new();
+        for _ in 0..self.rng.range(1, 3) {
+            let (size, extractions_left) = match self.rng.range(0, 9) {
+                0..=5 => (types::SurveySize::Small, 5),
+                6..=8 => (types::SurveySize::Moderate, 10),
+                _ => (types::SurveySize::Large, 20),
+            };
+            let found = (0..self.rng.range(3, 6))
+                .filter_map(|_| self.rng.weighted(&deposits).cloned())
+                .map(|symbol| types::SurveyDeposit { symbol })
+                .collect();
+            let survey = types::Survey {
+                signature: format!("{}-{}", waypoint_symbol, self.next_id()),
+                symbol: waypoint_symbol.clone(),
+                deposits: found,
+                expiration: now + Duration::minutes(self.rng.range(15, 60) as i64),
+                size,
+            };
+
+            self.surveys.insert(survey.signature.clone(), SimSurvey {
+                survey: survey.clone(),
+                extractions_left,
+            });
+            surveys.push(survey);
+        }
+
+        let until = now + Duration::seconds(SURVEY_COOLDOWN);
+        self.ship_mut(ship_symbol)?.cooldown_until = Some(until);
+        Ok(types::CreateSurveyData {
+            cooldown: cooldown(ship_symbol, Duration::seconds(SURVEY_COOLDOWN), until, now),
+            surveys,
+        })
+    }
+
+    pub(crate) fn extract(&mut self, ship_symbol: &types::ShipSymbol, survey: Option<types::Survey>, now: DateTime<Utc>) -> Result<types::ExtractData> {
+        let sim = self.ship(ship_symbol)?;
+        in_orbit(sim)?;
+        let power = sim.template.extract_power;
+        if power == 0 {
+            return Err(error(MISSING_MOUNT, format!("ship {} has no mining laser", ship_symbol)));
+        }
+        cooled_down(sim, now)?;
+        let room = sim.ship.cargo.capacity - sim.ship.cargo.units;
+        if room == 0 {
+            return Err(error(CARGO_FULL, format!("ship {} has a full hold", ship_symbol)));
+        }
+        let waypoint_symbol = sim.ship.nav.waypoint_symbol.clone();
+        let deposits = self.deposits.get(waypoint_symbol.waypoint())
+            .ok_or_else(|| error(NOT_AN_ASTEROID_FIELD, format!("{} has nothing to extract", waypoint_symbol)))?;
+
+        let symbol = match survey {
+            None => self.rng.weighted(deposits).cloned(),
+            Some(survey) => {
+                let Some(known) = self.surveys.get_mut(&survey.signature).filter(|known| known.survey.symbol == waypoint_symbol) else {
+                    return Err(error(SURVEY_UNKNOWN, format!("survey {} is not valid at {}", survey.signature, waypoint_symbol)));
+                };
+                if known.survey.expiration <= now {
+                    self.surveys.remove(&survey.signature);
+                    return Err(error(SURVEY_EXPIRED, format!("survey {} has expired", survey.signature)));
+                }
+                if known.extractions_left == 0 {
+                    self.surveys.remove(&survey.signature);
+                    return Err(error(SURVEY_EXHAUSTED, format!("survey {} has been exhausted", survey.signature)));
+                }
+                known.extractions_left -= 1;
+                let deposits = &known.survey.deposits;
+                let index = self.rng.range(0, deposits.len().saturating_sub(1) as u64) as usize;
+                deposits.get(index).map(|deposit| deposit.symbol.clone())
+            }
+        };
+        let Some(symbol) = symbol else {
+            return Err(error(NOT_AN_ASTEROID_FIELD, format!("{} has nothing to extract", waypoint_symbol)));
+        };
+        let units = self.rng.range(power / 2, power + power / 2).clamp(1, room);
+
+        let until = now + Duration::seconds(EXTRACT_COOLDOWN);
+        let sim = self.ship_mut(ship_symbol)?;
+        add_cargo(&mut sim.ship.cargo, &symbol, units);
+        sim.cooldown_until = Some(until);
+
+        Ok(types::ExtractData {
+            cooldown: cooldown(ship_symbol, Duration::seconds(EXTRACT_COOLDOWN), until, now),
+            extraction: types::Extraction {
+                ship_symbol: ship_symbol.clone(),
+                extraction_yield: types::ExtractionYield { symbol, units },
+            },
+            cargo: sim.ship.cargo.clone(),
+        })
+    }
+
+    pub(crate) fn jettison(&mut self, ship_symbol: &types::ShipSymbol, symbol: &types::MarketGoodSymbol, units: u64) -> Result<types::ShipCargo> {
+        let cargo = &mut self.ship_mut(ship_symbol)?.ship.cargo;
+        remove_cargo(cargo, ship_symbol, symbol, units)?;
+        Ok(cargo.clone())
+    }
+
+    pub(crate) fn purchase_ship(&mut self, ship_type: types::ShipType, waypoint_symbol: &types::WaypointSymbol, now: DateTime<Utc>) -> Result<types::PurchaseShipData> {
+        let shipyard = self.shipyards.get(waypoint_symbol.waypoint())
+            .ok_or_else(|| error(NOT_FOUND, format!("no shipyard at {}", waypoint_symbol)))?;
+        if !shipyard.ship_types.contains(&ship_type) {
+            return Err(error(SHIP_NOT_SOLD, format!("{} doesn't sell {:?}", waypoint_symbol, ship_type)));
+        }
+        if !self.ship_present(waypoint_symbol) {
+            return Err(error(SHIP_NOT_SOLD, format!("no ship at {} to make the purchase", waypoint_symbol)));
+        }
+        let price = ShipTemplate::of(ship_type.clone()).price;
+        if price > self.agent.credits {
+            return Err(error(INSUFFICIENT_CREDITS, format!("{} credits needed, {} available", price, self.agent.credits)));
+        }
+
+        self.agent.credits -= price;
+        let symbol = self.add_ship(ship_type.clone(), waypoint_symbol);
+        let transaction = types::ShipTransaction {
+            waypoint_symbol: waypoint_symbol.clone(),
+            // like the server, record the type sold rather than the new ship
+            ship_symbol: ship_type.to_string(),
+            price,
+            agent_symbol: self.agent.symbol.clone(),
+            timestamp: now,
+        };
+        self.shipyards.get_mut(waypoint_symbol.waypoint()).unwrap().transactions.push(transaction.clone());
+
+        let ship = &mut self.ship_mut(&symbol)?.ship;
+        ship.nav.route.departure_time = now;
+        ship.nav.route.arrival = now;
+        Ok(types::PurchaseShipData {
+            agent: self.agent.clone(),
+            ship: self.ship(&symbol)?.ship.clone(),
+            transaction,
+        })
+    }
+}
+
+fn in_orbit(sim: &SimShip) -> Result<()> {
+    match sim.ship.nav.status {
+        types::ShipNavStatus::InOrbit => Ok(()),
+        types::ShipNavStatus::InTransit => Err(error(SHIP_IN_TRANSIT, format!("ship {} is in transit", sim.ship.symbol))),
+        types::ShipNavStatus::Docked | types::ShipNavStatus::Unknown(_) => Err(error(NOT_IN_ORBIT, format!("ship {} is not in orbit", sim.ship.symbol))),
+    }
+}
+
+pub(crate) fn docked(sim: &SimShip) -> Result<()> {
+    match sim.ship.nav.status {
+        types::ShipNavStatus::Docked => Ok(()),
+        types::ShipNavStatus::InTransit => Err(error(SHIP_IN_TRANSIT, format!("ship {} is in transit", sim.ship.symbol))),
+        types::ShipNavStatus::InOrbit | types::ShipNavStatus::Unknown(_) => Err(error(NOT_DOCKED, format!("ship {} is not docked", sim.ship.symbol))),
+    }
+}
+
+fn cooled_down(sim: &SimShip, now: DateTime<Utc>) -> Result<()> {
+    match sim.cooldown_until {
+        Some(until) if until > now => Err(error(COOLDOWN_CONFLICT, format!(
+            "ship {} is on cooldown for {} more seconds", sim.ship.symbol, (until - now).num_seconds()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn cooldown(ship_symbol: &types::ShipSymbol, total: Duration, until: DateTime<Utc>, now: DateTime<Utc>) -> types::Cooldown {
+    types::Cooldown {
+        ship_symbol: ship_symbol.clone(),
+        total_seconds: total.num_seconds() as u64,
+        // a part second still left counts as a second, like the server rounds it
+        remaining_seconds: ((until - now).num_milliseconds().max(0) as u64).div_ceil(1000),
+        expiration: Some(until),
+    }
+}
+
+pub(crate) fn add_cargo(cargo: &mut types::ShipCargo, symbol: &types::MarketGoodSymbol, units: u64) {
+    match cargo.inventory.iter_mut().find(|item| item.symbol == *symbol) {
+        Some(item) => item.units += units,
+        None => cargo.inventory.push(types::ShipCargoItem {
+            symbol: symbol.clone(),
+            name: display_name(symbol.as_str()),
+            description: String::new(),
+            units,
+        }),
+    }
+    cargo.units += units;
+}
+
+pub(crate) fn remove_cargo(cargo: &mut types::ShipCargo, ship_symbol: &types::ShipSymbol, symbol: &types::MarketGoodSymbol, units: u64) -> Result<()> {
+    let held = cargo.units_of(symbol);
+    if units > held {
+        return Err(error(CARGO_MISSING, format!("ship {} holds {} of {}, not {}", ship_symbol, held, symbol, units)));
+    }
+
+    cargo.units -= units;
+    for item in cargo.inventory.iter_mut().filter(|item| item.symbol == *symbol) {
+        item.units -= units;
+    }
+    cargo.inventory.retain(|item| item.units > 0);
+    Ok(())
+}
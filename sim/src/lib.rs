@@ -0,0 +1,114 @@
+//! An offline stand-in for the SpaceTraders server, for trying out behaviors
+//! without the network. It plays by the game's rules for travel, fuel,
+//! cooldowns, extraction, markets and contracts, on a clock that skips ahead
+//! whenever every ship is waiting, so a simulated week takes seconds.
+
+use std::time::Duration;
+
+use space_traders_api::behaviors::BehaviorRuntime;
+use space_traders_api::{error, types};
+
+pub use crate::client::SimClient;
+pub use crate::clock::VirtualClock;
+pub use crate::market::{GoodKind, SimGood, SimMarket};
+pub use crate::ships::ShipTemplate;
+pub use crate::world::{ContractOffer, World};
+
+pub mod rules;
+mod client;
+mod clock;
+mod contracts;
+mod fleet;
+mod market;
+mod rng;
+mod ships;
+mod trade;
+mod world;
+
+/// A single threaded runtime whose clock only moves when every task is
+/// waiting on it. Build the `SimClient`, scheduler and behaviors inside it.
+pub fn runtime() -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+}
+
+/// Runs the fleet for `duration` of game time, returning the ships that
+/// finished within it. The rest are stopped where they are.
+pub async fn run_for(mut runtime: BehaviorRuntime, duration: Duration) -> Vec<(types::ShipSymbol, error::Result<()>)> {
+    let mut finished = Vec::new();
+    let _ = tokio::time::timeout(duration, async {
+        while let Some(result) = runtime.join_next().await {
+            finished.push(result);
+        }
+    }).await;
+    finished
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use space_traders_api::behaviors::{BehaviorRuntime, MarketCache, MiningBehavior, MiningConfig, StateStore, SurveyStore};
+    use space_traders_api::events::{self, Event, EventClient};
+    use space_traders_api::scheduler::{RateLimit, Scheduler};
+    use space_traders_api::SpaceTradersClient;
+    use space_traders_api::types::{ShipType, WaypointSymbol};
+
+    use crate::{run_for, SimClient, World};
+
+    #[test]
+    fn test_mining_day() {
+        crate::runtime().unwrap().block_on(async {
+            let epoch = "2023-07-01T00:00:00Z".parse().unwrap();
+            let mut world = World::starter("SIM", epoch, 7);
+            let drone = world.add_ship(ShipType::ShipMiningDrone, &WaypointSymbol::new("X1-SIM1-A1").unwrap());
+            let api = Arc::new(SimClient::new(world));
+
+            let mut runtime = BehaviorRuntime::new(api.clone(), Scheduler::new(RateLimit::default()), StateStore::in_memory());
+            let behavior = MiningBehavior::new(MiningConfig::default(), Arc::new(MarketCache::default()), Arc::new(SurveyStore::default()));
+            let stats = behavior.stats();
+            runtime.spawn(drone, Box::new(behavior));
+
+            let finished = run_for(runtime, Duration::from_secs(24 * 3600)).await;
+            assert!(finished.is_empty(), "{:?}", finished);
+            assert!(api.now() >= epoch + chrono::Duration::days(1));
+
+            let stats = stats.lock().unwrap();
+            assert!(stats.units_sold > 0);
+            assert!(api.world().credits() > 150_000);
+        });
+    }
+
+    #[test]
+    fn test_mining_events() {
+        crate::runtime().unwrap().block_on(async {
+            let epoch = "2023-07-01T00:00:00Z".parse().unwrap();
+            let mut world = World::starter("SIM", epoch, 7);
+            let drone = world.add_ship(ShipType::ShipMiningDrone, &WaypointSymbol::new("X1-SIM1-A1").unwrap());
+            let api = Arc::new(EventClient::new(Arc::new(SimClient::new(world)), events::channel()));
+
+            let mut events = api.subscribe();
+            let seen = tokio::spawn(async move {
+                let mut seen = Vec::new();
+                while let Ok(event) = events.recv().await {
+                    seen.push(event);
+                }
+                seen
+            });
+
+            let mut runtime = BehaviorRuntime::new(api.clone(), Scheduler::new(RateLimit::default()), StateStore::in_memory());
+            runtime.spawn(drone, Box::new(MiningBehavior::new(MiningConfig::default(), Arc::new(MarketCache::default()), Arc::new(SurveyStore::default()))));
+            run_for(runtime, Duration::from_secs(6 * 3600)).await;
+            drop(api);
+
+            // the last cooldown is still pending, so give it time to go out before the channel closes
+            let seen = tokio::time::timeout(Duration::from_secs(3600), seen).await.unwrap().unwrap();
+            assert!(seen.iter().any(|event| matches!(event, Event::CargoSold(_))));
+            assert!(seen.iter().any(|event| matches!(event, Event::CooldownExpired { .. })));
+            assert!(seen.iter().any(|event| matches!(event, Event::CreditsChanged { change, .. } if *change > 0)));
+        });
+    }
+}
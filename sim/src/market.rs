@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+
+use space_traders_api::types;
+
+use crate::rules::{display_name, price_recovery_half_life, PRICE_ELASTICITY};
+
+// transactions a market remembers, like the server's recent history
+const TRANSACTION_HISTORY: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoodKind {
+    Import,
+    Export,
+    Exchange,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimGood {
+    pub symbol: types::MarketGoodSymbol,
+    pub kind: GoodKind,
+    /// The midpoint of the buy and sell prices when nobody has traded lately.
+    pub base_price: u64,
+    pub trade_volume: u64,
+    /// Above zero after buying has drained supply, below after selling has flooded it.
+    pressure: f64,
+    settled_at: Option<DateTime<Utc>>,
+}
+
+impl SimGood {
+    fn settle(&mut self, now: DateTime<Utc>) {
+        if let Some(settled_at) = self.settled_at {
+            let half_lives = (now - settled_at).num_milliseconds() as f64 / price_recovery_half_life().num_milliseconds() as f64;
+            self.pressure *= 0.5f64.powf(half_lives.max(0.0));
+        }
+        self.settled_at = Some(now);
+    }
+
+    fn price(&self, spread: f64) -> u64 {
+        let factor = (1.0 + PRICE_ELASTICITY * self.pressure).clamp(0.1, 10.0);
+        ((self.base_price as f64 * factor * spread).round() as u64).max(1)
+    }
+
+    /// What the market charges per unit.
+    pub fn purchase_price(&self) -> u64 {
+        self.price(1.04)
+    }
+
+    /// What the market pays per unit.
+    pub fn sell_price(&self) -> u64 {
+        self.price(0.96)
+    }
+
+    pub fn supply(&self) -> types::SupplyType {
+        match self.pressure {
+            p if p >= 4.0 => types::SupplyType::Scarce,
+            p if p >= 1.0 => types::SupplyType::Limited,
+            p if p > -4.0 => types::SupplyType::Moderate,
+            _ => types::SupplyType::Abundant,
+        }
+    }
+}
+
+/// A market whose prices move with what's traded there and recover over time.
+#[derive(Debug, Clone)]
+pub struct SimMarket {
+    pub symbol: types::WaypointSymbol,
+    pub goods: Vec<SimGood>,
+    transactions: Vec<types::MarketTransaction>,
+}
+
+impl SimMarket {
+    pub fn new(symbol: types::WaypointSymbol) -> Self {
+        SimMarket {
+            symbol,
+            goods: Vec::new(),
+            transactions: Vec::new(),
+        }
+    }
+
+    pub fn import(self, symbol: &str, base_price: u64, trade_volume: u64) -> Self {
+        self.with_good(GoodKind::Import, symbol, base_price, trade_volume)
+    }
+
+    pub fn export(self, symbol: &str, base_price: u64, trade_volume: u64) -> Self {
+        self.with_good(GoodKind::Export, symbol, base_price, trade_volume)
+    }
+
+    pub fn exchange(self, symbol: &str, base_price: u64, trade_volume: u64) -> Self {
+        self.with_good(GoodKind::Exchange, symbol, base_price, trade_volume)
+    }
+
+    fn with_good(mut self, kind: GoodKind, symbol: &str, base_price: u64, trade_volume: u64) -> Self {
+        self.goods.push(SimGood {
+            symbol: symbol.into(),
+            kind,
+            base_price,
+            trade_volume,
+            pressure: 0.0,
+            settled_at: None,
+        });
+        self
+    }
+
+    pub fn good(&self, symbol: &types::MarketGoodSymbol) -> Option<&SimGood> {
+        self.goods.iter().find(|good| good.symbol == *symbol)
+    }
+
+    /// Lets prices recover for however long it's been since the last look.
+    pub fn settle(&mut self, now: DateTime<Utc>) {
+        for good in self.goods.iter_mut() {
+            good.settle(now);
+        }
+    }
+
+    /// Records a trade the caller has already checked and priced, and moves
+    /// the price by how much of a trade volume it was.
+    pub fn trade(&mut self, transaction: types::MarketTransaction) {
+        if let Some(good) = self.goods.iter_mut().find(|good| good.symbol == transaction.trade_symbol) {
+            let lots = transaction.units as f64 / good.trade_volume.max(1) as f64;
+            match transaction.trade_type {
+                types::TradeType::Purchase => good.pressure += lots,
+                types::TradeType::Sell => good.pressure -= lots,
+                types::TradeType::Unknown(_) => {}
+            }
+        }
+
+        self.transactions.push(transaction);
+        if self.transactions.len() > TRANSACTION_HISTORY {
+            self.transactions.remove(0);
+        }
+    }
+
+    /// The market as the API shows it. Prices and history are only visible
+    /// with a ship present.
+    pub fn to_market(&self, ship_present: bool) -> types::Market {
+        let listed = |kind: GoodKind| -> Vec<types::MarketGood> {
+            self.goods.iter()
+                .filter(|good| good.kind == kind)
+                .map(|good| types::MarketGood {
+                    symbol: good.symbol.clone(),
+                    name: display_name(good.symbol.as_str()),
+                    description: String::new(),
+                })
+                .collect()
+        };
+
+        let (transactions, trade_goods) = if ship_present {
+            let trade_goods = self.goods.iter()
+                .map(|good| types::MarketTradeGood {
+                    symbol: good.symbol.clone(),
+                    trade_volume: good.trade_volume,
+                    supply: good.supply(),
+                    purchase_price: good.purchase_price(),
+                    sell_price: good.sell_price(),
+                })
+                .collect();
+            (Some(self.transactions.clone()), Some(trade_goods))
+        } else {
+            (None, None)
+        };
+
+        types::Market {
+            symbol: self.symbol.clone(),
+            imports: listed(GoodKind::Import),
+            exports: listed(GoodKind::Export),
+            exchange: listed(GoodKind::Exchange),
+            transactions,
+            trade_goods,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use space_traders_api::types::{MarketGoodSymbol, MarketTransaction, ShipSymbol, TradeType, WaypointSymbol};
+    use crate::market::SimMarket;
+
+    #[test]
+    fn test_price_response() {
+        let symbol = WaypointSymbol::new("X1-SIM1-A2").unwrap();
+        let mut market = SimMarket::new(symbol.clone()).import("IRON_ORE", 100, 50);
+        let now = "2023-07-01T00:00:00Z".parse().unwrap();
+        market.settle(now);
+        assert_eq!(market.good(&MarketGoodSymbol::IronOre).unwrap().sell_price(), 96);
+
+        // two trade volumes sold knocks 8% off
+        market.trade(MarketTransaction {
+            waypoint_symbol: symbol,
+            ship_symbol: ShipSymbol::new("SIM-1").unwrap(),
+            trade_symbol: MarketGoodSymbol::IronOre,
+            trade_type: TradeType::Sell,
+            units: 100,
+            price_per_unit: 96,
+            total_price: 9600,
+            timestamp: now,
+        });
+        assert_eq!(market.good(&MarketGoodSymbol::IronOre).unwrap().sell_price(), 88);
+
+        // and an hour later the market has made back half of it
+        market.settle(now + Duration::hours(1));
+        assert_eq!(market.good(&MarketGoodSymbol::IronOre).unwrap().sell_price(), 92);
+    }
+}
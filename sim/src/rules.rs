@@ -0,0 +1,91 @@
+use chrono::Duration;
+
+use space_traders_api::error::Error;
+use space_traders_api::types;
+
+// error codes the behaviors look for match the server's; the rest only need
+// to be distinct
+pub const NOT_FOUND: u32 = 404;
+pub const COOLDOWN_CONFLICT: u32 = 4000;
+pub const INSUFFICIENT_FUEL: u32 = 4203;
+pub const SAME_DESTINATION: u32 = 4204;
+pub const NOT_AN_ASTEROID_FIELD: u32 = 4205;
+pub const SHIP_IN_TRANSIT: u32 = 4214;
+pub const CARGO_EXCEEDS_LIMIT: u32 = 4217;
+pub const CARGO_MISSING: u32 = 4219;
+pub const SURVEY_UNKNOWN: u32 = 4220;
+pub const SURVEY_EXPIRED: u32 = 4221;
+pub const SURVEY_EXHAUSTED: u32 = 4224;
+pub const CARGO_FULL: u32 = 4228;
+pub const NOT_IN_ORBIT: u32 = 4236;
+pub const MISSING_MOUNT: u32 = 4240;
+pub const NOT_DOCKED: u32 = 4244;
+pub const CONTRACT_NOT_ACCEPTED: u32 = 4501;
+pub const CONTRACT_DEADLINE: u32 = 4502;
+pub const CONTRACT_TERMS_NOT_MET: u32 = 4503;
+pub const CONTRACT_ALREADY_ACCEPTED: u32 = 4504;
+pub const CONTRACT_ALREADY_FULFILLED: u32 = 4505;
+pub const CONTRACT_INVALID_DELIVERY: u32 = 4509;
+pub const CONTRACT_ACTIVE: u32 = 4511;
+pub const INSUFFICIENT_CREDITS: u32 = 4600;
+pub const GOOD_NOT_TRADED: u32 = 4602;
+pub const TRADE_LIMIT: u32 = 4604;
+pub const SHIP_NOT_SOLD: u32 = 4700;
+
+pub const EXTRACT_COOLDOWN: i64 = 70;
+pub const SURVEY_COOLDOWN: i64 = 60;
+/// Fuel is sold by the unit, and each unit fills this much of a tank.
+pub const FUEL_PER_UNIT: u64 = 100;
+/// How far each trade volume's worth of buying or selling moves a price.
+pub const PRICE_ELASTICITY: f64 = 0.04;
+
+/// Prices drift back to normal, halving their distance from it this often.
+pub fn price_recovery_half_life() -> Duration {
+    Duration::hours(1)
+}
+
+fn speed_multiplier(flight_mode: &types::ShipNavFlightMode) -> f64 {
+    match flight_mode {
+        // a mode the simulator doesn't know flies like cruise
+        types::ShipNavFlightMode::Cruise | types::ShipNavFlightMode::Unknown(_) => 25.0,
+        types::ShipNavFlightMode::Drift => 250.0,
+        types::ShipNavFlightMode::Burn => 7.5,
+        types::ShipNavFlightMode::Stealth => 30.0,
+    }
+}
+
+pub fn travel_time(distance: f64, speed: u64, flight_mode: &types::ShipNavFlightMode) -> Duration {
+    let seconds = (distance.max(1.0) * speed_multiplier(flight_mode) / speed.max(1) as f64).round();
+    Duration::seconds(seconds as i64 + 15)
+}
+
+pub fn fuel_cost(distance: f64, flight_mode: &types::ShipNavFlightMode) -> u64 {
+    let distance = distance.round().max(1.0) as u64;
+    match flight_mode {
+        types::ShipNavFlightMode::Drift => 1,
+        types::ShipNavFlightMode::Burn => distance * 2,
+        types::ShipNavFlightMode::Cruise | types::ShipNavFlightMode::Stealth | types::ShipNavFlightMode::Unknown(_) => distance,
+    }
+}
+
+pub fn error<M: Into<String>>(code: u32, message: M) -> Error {
+    Error::ApiError(types::ApiError {
+        message: message.into(),
+        code,
+        data: None,
+    })
+}
+
+/// Turns `IRON_ORE` into `Iron Ore` for names the game would have written out.
+pub fn display_name(symbol: &str) -> String {
+    symbol.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
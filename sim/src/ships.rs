@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+
+use space_traders_api::types;
+
+use crate::rules::display_name;
+
+/// The parts of a ship type the simulator plays by.
+#[derive(Debug, Clone)]
+pub struct ShipTemplate {
+    pub ship_type: types::ShipType,
+    pub role: types::ShipRole,
+    pub speed: u64,
+    pub fuel_capacity: u64,
+    pub cargo_capacity: u64,
+    /// Typical units per extraction; zero if the ship has no mining laser.
+    pub extract_power: u64,
+    pub can_survey: bool,
+    pub price: u64,
+}
+
+impl ShipTemplate {
+    /// Panics for a type the simulator has no template for.
+    pub fn of(ship_type: types::ShipType) -> Self {
+        use types::ShipType::*;
+        let (role, speed, fuel_capacity, cargo_capacity, extract_power, can_survey, price) = match ship_type {
+            ShipProbe => (types::ShipRole::Satellite, 3, 0, 0, 0, false, 25_000),
+            ShipMiningDrone => (types::ShipRole::Excavator, 2, 100, 15, 5, false, 45_000),
+            ShipOreHound => (types::ShipRole::Excavator, 10, 400, 30, 10, true, 160_000),
+            ShipLightHauler => (types::ShipRole::Hauler, 10, 600, 80, 0, false, 250_000),
+            ShipHeavyFreighter => (types::ShipRole::Hauler, 5, 1200, 240, 0, false, 800_000),
+            ShipCommandFrigate => (types::ShipRole::Command, 30, 1200, 60, 7, true, 500_000),
+            ShipRefiningFreighter => (types::ShipRole::Refinery, 5, 1200, 120, 0, false, 900_000),
+            ShipInterceptor => (types::ShipRole::Interceptor, 30, 300, 10, 0, false, 120_000),
+            ShipExplorer => (types::ShipRole::Explorer, 20, 1800, 40, 0, true, 300_000),
+            ShipLightShuttle => (types::ShipRole::Transport, 15, 400, 40, 0, false, 90_000),
+            Unknown(ref symbol) => panic!("no template for {}", symbol),
+        };
+
+        ShipTemplate {
+            ship_type,
+            role,
+            speed,
+            fuel_capacity,
+            cargo_capacity,
+            extract_power,
+            can_survey,
+            price,
+        }
+    }
+
+    /// The type's symbol without the `SHIP_` prefix, e.g. `MINING_DRONE`.
+    fn model(&self) -> String {
+        self.ship_type.as_str().trim_start_matches("SHIP_").to_string()
+    }
+
+    fn name(&self) -> String {
+        display_name(&self.model())
+    }
+
+    fn frame(&self) -> types::ShipFrame {
+        types::ShipFrame {
+            symbol: format!("FRAME_{}", self.model()),
+            name: self.name(),
+            description: String::new(),
+            condition: Some(100),
+            module_slots: 0,
+            mounting_points: 0,
+            fuel_capacity: self.fuel_capacity,
+            requirements: requirements(),
+        }
+    }
+
+    fn engine(&self) -> types::ShipEngine {
+        types::ShipEngine {
+            symbol: "ENGINE_IMPULSE_DRIVE_I".to_string(),
+            name: "Impulse Drive I".to_string(),
+            description: String::new(),
+            condition: Some(100),
+            speed: self.speed,
+            requirements: requirements(),
+        }
+    }
+
+    fn reactor(&self) -> types::ShipReactor {
+        types::ShipReactor {
+            symbol: "REACTOR_FISSION_I".to_string(),
+            name: "Fission Reactor I".to_string(),
+            description: String::new(),
+            condition: Some(100),
+            power_output: 31,
+            requirements: requirements(),
+        }
+    }
+
+    pub fn listing(&self) -> types::ShipyardShip {
+        types::ShipyardShip {
+            ship_type: self.ship_type.clone(),
+            name: self.name(),
+            description: String::new(),
+            purchase_price: self.price,
+            frame: self.frame(),
+            reactor: self.reactor(),
+            engine: self.engine(),
+            modules: Vec::new(),
+            mounts: Vec::new(),
+        }
+    }
+
+    /// A new ship docked at `at`, with an empty hold and a full tank.
+    pub fn build(&self, symbol: types::ShipSymbol, faction_symbol: types::FactionSymbol, at: types::ShipNavRouteWaypoint, now: DateTime<Utc>) -> types::Ship {
+        types::Ship {
+            registration: types::ShipRegistration {
+                name: symbol.to_string(),
+                faction_symbol,
+                role: self.role.clone(),
+            },
+            symbol,
+            nav: types::ShipNav {
+                system_symbol: at.system_symbol.clone(),
+                waypoint_symbol: at.symbol.clone(),
+                route: types::ShipNavRoute {
+                    destination: at.clone(),
+                    departure: at,
+                    departure_time: now,
+                    arrival: now,
+                },
+                status: types::ShipNavStatus::Docked,
+                flight_mode: types::ShipNavFlightMode::Cruise,
+            },
+            crew: types::ShipCrew {
+                current: 0,
+                required: 0,
+                capacity: 0,
+                rotation: types::ShipCrewRotation::Strict,
+                morale: 100,
+                wages: 0,
+            },
+            frame: self.frame(),
+            reactor: self.reactor(),
+            engine: self.engine(),
+            modules: Vec::new(),
+            mounts: Vec::new(),
+            cargo: types::ShipCargo {
+                capacity: self.cargo_capacity,
+                units: 0,
+                inventory: Vec::new(),
+            },
+            fuel: types::ShipFuel {
+                current: self.fuel_capacity,
+                capacity: self.fuel_capacity,
+                consumed: None,
+            },
+        }
+    }
+}
+
+fn requirements() -> types::ShipRequirements {
+    types::ShipRequirements {
+        power: None,
+        crew: None,
+        slots: None,
+    }
+}
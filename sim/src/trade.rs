@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+
+use space_traders_api::error::Result;
+use space_traders_api::types;
+
+use crate::fleet::{add_cargo, docked, remove_cargo};
+use crate::rules::*;
+use crate::world::World;
+
+impl World {
+    pub(crate) fn get_market(&self, waypoint_symbol: &types::WaypointSymbol) -> Result<types::Market> {
+        let market = self.markets.get(waypoint_symbol.waypoint())
+            .ok_or_else(|| error(NOT_FOUND, format!("no market at {}", waypoint_symbol)))?;
+        Ok(market.to_market(self.ship_present(waypoint_symbol)))
+    }
+
+    pub(crate) fn get_shipyard(&self, waypoint_symbol: &types::WaypointSymbol) -> Result<types::Shipyard> {
+        let shipyard = self.shipyards.get(waypoint_symbol.waypoint())
+            .ok_or_else(|| error(NOT_FOUND, format!("no shipyard at {}", waypoint_symbol)))?;
+        Ok(shipyard.to_shipyard(self.ship_present(waypoint_symbol)))
+    }
+
+    pub(crate) fn sell(&mut self, ship_symbol: &types::ShipSymbol, symbol: &types::MarketGoodSymbol, units: u64, now: DateTime<Utc>) -> Result<types::SellCargoData> {
+        let (waypoint_symbol, price) = self.quote(ship_symbol, symbol, units, types::TradeType::Sell)?;
+        remove_cargo(&mut self.ship_mut(ship_symbol)?.ship.cargo, ship_symbol, symbol, units)?;
+        self.agent.credits += price * units;
+        let transaction = self.record(ship_symbol, &waypoint_symbol, symbol, types::TradeType::Sell, units, price, now);
+
+        Ok(types::SellCargoData {
+            agent: self.agent.clone(),
+            cargo: self.ship(ship_symbol)?.ship.cargo.clone(),
+            transaction,
+        })
+    }
+
+    pub(crate) fn purchase(&mut self, ship_symbol: &types::ShipSymbol, symbol: &types::MarketGoodSymbol, units: u64, now: DateTime<Utc>) -> Result<types::PurchaseCargoData> {
+        let (waypoint_symbol, price) = self.quote(ship_symbol, symbol, units, types::TradeType::Purchase)?;
+        let cargo = &self.ship(ship_symbol)?.ship.cargo;
+        if cargo.units + units > cargo.capacity {
+            return Err(error(CARGO_EXCEEDS_LIMIT, format!("ship {} has room for {} units, not {}", ship_symbol, cargo.capacity - cargo.units, units)));
+        }
+        self.charge(price * units)?;
+
+        add_cargo(&mut self.ship_mut(ship_symbol)?.ship.cargo, symbol, units);
+        let transaction = self.record(ship_symbol, &waypoint_symbol, symbol, types::TradeType::Purchase, units, price, now);
+
+        Ok(types::PurchaseCargoData {
+            agent: self.agent.clone(),
+            cargo: self.ship(ship_symbol)?.ship.cargo.clone(),
+            transaction,
+        })
+    }
+
+    /// Fills the tank, buying fuel by the market unit.
+    pub(crate) fn refuel(&mut self, ship_symbol: &types::ShipSymbol, now: DateTime<Utc>) -> Result<types::RefuelData> {
+        let fuel = &self.ship(ship_symbol)?.ship.fuel;
+        let units = (fuel.capacity - fuel.current).div_ceil(FUEL_PER_UNIT);
+        if units > 0 {
+            let (waypoint_symbol, price) = self.quote(ship_symbol, &types::MarketGoodSymbol::Fuel, units, types::TradeType::Purchase)?;
+            self.charge(price * units)?;
+            self.record(ship_symbol, &waypoint_symbol, &types::MarketGoodSymbol::Fuel, types::TradeType::Purchase, units, price, now);
+        }
+
+        let fuel = &mut self.ship_mut(ship_symbol)?.ship.fuel;
+        fuel.current = fuel.capacity;
+        let fuel = fuel.clone();
+        Ok(types::RefuelData {
+            agent: self.agent.clone(),
+            fuel,
+        })
+    }
+
+    /// Checks the ship can make the trade where it's docked, and prices it per unit.
+    fn quote(&self, ship_symbol: &types::ShipSymbol, symbol: &types::MarketGoodSymbol, units: u64, trade_type: types::TradeType) -> Result<(types::WaypointSymbol, u64)> {
+        let sim = self.ship(ship_symbol)?;
+        docked(sim)?;
+        let waypoint_symbol = sim.ship.nav.waypoint_symbol.clone();
+        let good = self.markets.get(waypoint_symbol.waypoint())
+            .and_then(|market| market.good(symbol))
+            .ok_or_else(|| error(GOOD_NOT_TRADED, format!("{} is not traded at {}", symbol, waypoint_symbol)))?;
+        if units > good.trade_volume {
+            return Err(error(TRADE_LIMIT, format!("{} trades at most {} units of {} at a time", waypoint_symbol, good.trade_volume, symbol)));
+        }
+
+        let price = match trade_type {
+            types::TradeType::Purchase => good.purchase_price(),
+            types::TradeType::Sell => good.sell_price(),
+            types::TradeType::Unknown(trade_type) => unreachable!("the sim only quotes purchases and sales, not {}", trade_type),
+        };
+        Ok((waypoint_symbol, price))
+    }
+
+    fn charge(&mut self, credits: u64) -> Result<()> {
+        if credits > self.agent.credits {
+            return Err(error(INSUFFICIENT_CREDITS, format!("{} credits needed, {} available", credits, self.agent.credits)));
+        }
+        self.agent.credits -= credits;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record(&mut self, ship_symbol: &types::ShipSymbol, waypoint_symbol: &types::WaypointSymbol, symbol: &types::MarketGoodSymbol, trade_type: types::TradeType, units: u64, price: u64, now: DateTime<Utc>) -> types::MarketTransaction {
+        let transaction = types::MarketTransaction {
+            waypoint_symbol: waypoint_symbol.clone(),
+            ship_symbol: ship_symbol.clone(),
+            trade_symbol: symbol.clone(),
+            trade_type,
+            units,
+            price_per_unit: price,
+            total_price: price * units,
+            timestamp: now,
+        };
+        if let Some(market) = self.markets.get_mut(waypoint_symbol.waypoint()) {
+            market.trade(transaction.clone());
+        }
+        transaction
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+
+use space_traders_api::error::Result;
+use space_traders_api::types;
+
+use crate::market::SimMarket;
+use crate::rng::Rng;
+use crate::rules::{display_name, error, NOT_FOUND};
+use crate::ships::ShipTemplate;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimShip {
+    pub ship: types::Ship,
+    pub template: ShipTemplate,
+    pub cooldown_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimShipyard {
+    pub symbol: types::WaypointSymbol,
+    pub ship_types: Vec<types::ShipType>,
+    pub transactions: Vec<types::ShipTransaction>,
+}
+
+impl SimShipyard {
+    /// The shipyard as the API shows it. Listings and history are only
+    /// visible with a ship present.
+    pub fn to_shipyard(&self, ship_present: bool) -> types::Shipyard {
+        types::Shipyard {
+            symbol: self.symbol.clone(),
+            ship_types: self.ship_types.iter()
+                .map(|ship_type| types::ShipTypeReference { ship_type: ship_type.clone() })
+                .collect(),
+            transactions: ship_present.then(|| self.transactions.clone()),
+            ships: ship_present.then(|| {
+                self.ship_types.iter().map(|ship_type| ShipTemplate::of(ship_type.clone()).listing()).collect()
+            }),
+            modifications_fee: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimSurvey {
+    pub survey: types::Survey,
+    pub extractions_left: u64,
+}
+
+/// A good a faction may ask for in a procurement contract, and where it wants it.
+#[derive(Debug, Clone)]
+pub struct ContractOffer {
+    pub trade_symbol: types::MarketGoodSymbol,
+    pub destination_symbol: types::WaypointSymbol,
+    /// What the faction pays per unit, split between acceptance and fulfillment.
+    pub unit_payment: u64,
+}
+
+/// Everything the simulated game knows about. Add to it to build a scenario,
+/// then hand it to a `SimClient` to play.
+#[derive(Debug)]
+pub struct World {
+    pub(crate) epoch: DateTime<Utc>,
+    pub(crate) agent: types::Agent,
+    pub(crate) faction_symbol: types::FactionSymbol,
+    pub(crate) waypoints: Vec<types::Waypoint>,
+    pub(crate) deposits: HashMap<types::WaypointSymbol, Vec<(types::MarketGoodSymbol, u64)>>,
+    pub(crate) markets: HashMap<types::WaypointSymbol, SimMarket>,
+    pub(crate) shipyards: HashMap<types::WaypointSymbol, SimShipyard>,
+    pub(crate) ships: BTreeMap<types::ShipSymbol, SimShip>,
+    pub(crate) contracts: Vec<types::Contract>,
+    pub(crate) contract_offers: Vec<ContractOffer>,
+    pub(crate) surveys: HashMap<String, SimSurvey>,
+    pub(crate) rng: Rng,
+    next_id: u64,
+}
+
+impl World {
+    /// An empty galaxy as of `epoch`. The same seed plays out the same game.
+    pub fn new(agent_symbol: &str, faction_symbol: &str, headquarters: types::WaypointSymbol, credits: u64, epoch: DateTime<Utc>, seed: u64) -> Self {
+        World {
+            epoch,
+            agent: types::Agent {
+                account_id: "sim".to_string(),
+                symbol: agent_symbol.to_string(),
+                headquarters: headquarters.to_string(),
+                credits,
+                starting_faction: faction_symbol.to_string(),
+            },
+            faction_symbol: faction_symbol.into(),
+            waypoints: Vec::new(),
+            deposits: HashMap::new(),
+            markets: HashMap::new(),
+            shipyards: HashMap::new(),
+            ships: BTreeMap::new(),
+            contracts: Vec::new(),
+            contract_offers: Vec::new(),
+            surveys: HashMap::new(),
+            rng: Rng::new(seed),
+            next_id: 0,
+        }
+    }
+
+    /// One system with a headquarters, an asteroid field, four markets and a
+    /// shipyard, starting out with a command frigate, a probe and a contract
+    /// on offer.
+    pub fn starter(agent_symbol: &str, epoch: DateTime<Utc>, seed: u64) -> Self {
+        let waypoint = |symbol: &str| types::WaypointSymbol::new(&format!("X1-SIM1-{}", symbol)).unwrap();
+        let mut world = World::new(agent_symbol, "COSMIC", waypoint("A1"), 150_000, epoch, seed);
+
+        world.add_waypoint(waypoint("A1"), types::WaypointType::Planet, 0, 0, &[]);
+        world.add_waypoint(waypoint("A2"), types::WaypointType::Moon, 5, 8, &[]);
+        world.add_waypoint(waypoint("B7"), types::WaypointType::AsteroidField, -40, 25, &["MINERAL_DEPOSITS"]);
+        world.add_waypoint(waypoint("C3"), types::WaypointType::OrbitalStation, 20, -20, &[]);
+        world.add_waypoint(waypoint("D4"), types::WaypointType::GasGiant, -70, -40, &[]);
+        world.add_waypoint(waypoint("J9"), types::WaypointType::JumpGate, 120, 100, &[]);
+
+        world.add_deposits(&waypoint("B7"), &[
+            ("QUARTZ_SAND", 30),
+            ("ICE_WATER", 25),
+            ("SILICON_CRYSTALS", 20),
+            ("IRON_ORE", 12),
+            ("COPPER_ORE", 8),
+            ("ALUMINUM_ORE", 4),
+            ("PRECIOUS_STONES", 1),
+        ]);
+
+        world.add_market(SimMarket::new(waypoint("A1"))
+            .exchange("FUEL", 70, 100)
+            .import("QUARTZ_SAND", 24, 60)
+            .import("SILICON_CRYSTALS", 38, 60)
+            .import("ICE_WATER", 30, 60)
+            .export("AMMONIA_ICE", 30, 60));
+        world.add_market(SimMarket::new(waypoint("A2"))
+            .exchange("FUEL", 74, 100)
+            .import("IRON_ORE", 60, 40)
+            .import("COPPER_ORE", 70, 40)
+            .import("ALUMINUM_ORE", 55, 40));
+        world.add_market(SimMarket::new(waypoint("C3"))
+            .exchange("FUEL", 68, 100)
+            .import("SILICON_CRYSTALS", 44, 60)
+            .import("PRECIOUS_STONES", 280, 10)
+            .export("IRON_ORE", 45, 40));
+        world.add_market(SimMarket::new(waypoint("D4"))
+            .exchange("FUEL", 80, 100)
+            .import("ICE_WATER", 34, 60)
+            .export("AMMONIA_ICE", 22, 60));
+
+        world.add_shipyard(waypoint("C3"), &[
+            types::ShipType::ShipProbe,
+            types::ShipType::ShipMiningDrone,
+            types::ShipType::ShipOreHound,
+            types::ShipType::ShipLightHauler,
+        ]);
+
+        world.add_contract_offer(ContractOffer {
+            trade_symbol: types::MarketGoodSymbol::IronOre,
+            destination_symbol: waypoint("A2"),
+            unit_payment: 90,
+        });
+        world.add_contract_offer(ContractOffer {
+            trade_symbol: types::MarketGoodSymbol::CopperOre,
+            destination_symbol: waypoint("A2"),
+            unit_payment: 105,
+        });
+        world.add_contract_offer(ContractOffer {
+            trade_symbol: types::MarketGoodSymbol::SiliconCrystals,
+            destination_symbol: waypoint("C3"),
+            unit_payment: 60,
+        });
+
+        world.add_ship(types::ShipType::ShipCommandFrigate, &waypoint("A1"));
+        world.add_ship(types::ShipType::ShipProbe, &waypoint("A1"));
+        world.offer_contract(epoch);
+        world
+    }
+
+    pub fn add_waypoint(&mut self, symbol: types::WaypointSymbol, waypoint_type: types::WaypointType, x: i64, y: i64, traits: &[&str]) {
+        self.waypoints.push(types::Waypoint {
+            system_symbol: symbol.system_symbol(),
+            reference: types::WaypointReference {
+                symbol,
+                waypoint_type,
+                x,
+                y,
+            },
+            orbitals: Vec::new(),
+            faction: Some(types::FactionReference { symbol: self.faction_symbol.clone() }),
+            traits: traits.iter().map(|symbol| waypoint_trait(symbol)).collect(),
+            chart: None,
+        });
+    }
+
+    /// What extracting at an asteroid field can yield, weighted by how common each good is.
+    pub fn add_deposits(&mut self, field: &types::WaypointSymbol, deposits: &[(&str, u64)]) {
+        self.deposits.insert(
+            field.clone(),
+            deposits.iter().map(|(symbol, weight)| ((*symbol).into(), *weight)).collect(),
+        );
+    }
+
+    pub fn add_market(&mut self, market: SimMarket) {
+        self.add_trait(&market.symbol, "MARKETPLACE");
+        self.markets.insert(market.symbol.clone(), market);
+    }
+
+    pub fn add_shipyard(&mut self, symbol: types::WaypointSymbol, ship_types: &[types::ShipType]) {
+        self.add_trait(&symbol, "SHIPYARD");
+        self.shipyards.insert(symbol.clone(), SimShipyard {
+            symbol,
+            ship_types: ship_types.to_vec(),
+            transactions: Vec::new(),
+        });
+    }
+
+    pub fn add_contract_offer(&mut self, offer: ContractOffer) {
+        self.contract_offers.push(offer);
+    }
+
+    /// Gives the agent a new ship, docked at `at`.
+    pub fn add_ship(&mut self, ship_type: types::ShipType, at: &types::WaypointSymbol) -> types::ShipSymbol {
+        let symbol = types::ShipSymbol::new(&format!("{}-{:X}", self.agent.symbol, self.ships.len() + 1)).unwrap();
+        let template = ShipTemplate::of(ship_type);
+        let ship = template.build(symbol.clone(), self.faction_symbol.clone(), self.route_waypoint(at), self.epoch);
+        self.ships.insert(symbol.clone(), SimShip {
+            ship,
+            template,
+            cooldown_until: None,
+        });
+        symbol
+    }
+
+    fn add_trait(&mut self, symbol: &types::WaypointSymbol, trait_symbol: &str) {
+        if let Some(waypoint) = self.waypoints.iter_mut().find(|waypoint| waypoint.reference.symbol == *symbol) {
+            if !waypoint.traits.iter().any(|t| t.symbol == trait_symbol) {
+                waypoint.traits.push(waypoint_trait(trait_symbol));
+            }
+        }
+    }
+
+    pub fn epoch(&self) -> DateTime<Utc> {
+        self.epoch
+    }
+
+    pub fn credits(&self) -> u64 {
+        self.agent.credits
+    }
+
+    pub fn ship_symbols(&self) -> Vec<types::ShipSymbol> {
+        self.ships.keys().cloned().collect()
+    }
+
+    pub fn market(&self, symbol: &types::WaypointSymbol) -> Option<&SimMarket> {
+        self.markets.get(symbol.waypoint())
+    }
+
+    pub fn contracts(&self) -> &[types::Contract] {
+        &self.contracts
+    }
+
+    /// Lands ships whose arrival time has passed and lets prices recover.
+    pub(crate) fn settle(&mut self, now: DateTime<Utc>) {
+        for ship in self.ships.values_mut() {
+            let nav = &mut ship.ship.nav;
+            if nav.status == types::ShipNavStatus::InTransit && nav.route.arrival <= now {
+                nav.status = types::ShipNavStatus::InOrbit;
+            }
+        }
+        for market in self.markets.values_mut() {
+            market.settle(now);
+        }
+    }
+
+    pub(crate) fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub(crate) fn waypoint(&self, symbol: &types::WaypointSymbol) -> Result<&types::Waypoint> {
+        self.waypoints.iter()
+            .find(|waypoint| waypoint.reference.symbol == *symbol)
+            .ok_or_else(|| error(NOT_FOUND, format!("waypoint {} not found", symbol)))
+    }
+
+    pub(crate) fn route_waypoint(&self, symbol: &types::WaypointSymbol) -> types::ShipNavRouteWaypoint {
+        let waypoint = self.waypoint(symbol).unwrap();
+        types::ShipNavRouteWaypoint {
+            symbol: symbol.clone(),
+            waypoint_type: waypoint.reference.waypoint_type.clone(),
+            system_symbol: waypoint.system_symbol.clone(),
+            x: waypoint.reference.x,
+            y: waypoint.reference.y,
+        }
+    }
+
+    pub(crate) fn ship(&self, symbol: &types::ShipSymbol) -> Result<&SimShip> {
+        self.ships.get(symbol).ok_or_else(|| error(NOT_FOUND, format!("ship {} not found", symbol)))
+    }
+
+    pub(crate) fn ship_mut(&mut self, symbol: &types::ShipSymbol) -> Result<&mut SimShip> {
+        self.ships.get_mut(symbol).ok_or_else(|| error(NOT_FOUND, format!("ship {} not found", symbol)))
+    }
+
+    /// Whether one of the agent's ships is at the waypoint, and not just passing through.
+    pub(crate) fn ship_present(&self, symbol: &types::WaypointSymbol) -> bool {
+        self.ships.values().any(|ship| {
+            ship.ship.nav.waypoint_symbol == *symbol && ship.ship.nav.status != types::ShipNavStatus::InTransit
+        })
+    }
+
+    /// Each system that has waypoints, with the faction that claims it.
+    pub(crate) fn list_systems(&self) -> Vec<types::System> {
+        let mut systems: Vec<types::System> = Vec::new();
+        for waypoint in self.waypoints.iter() {
+            let system = match systems.iter_mut().find(|system| system.symbol == waypoint.system_symbol) {
+                Some(system) => system,
+                None => {
+                    systems.push(types::System {
+                        symbol: waypoint.system_symbol.clone(),
+                        sector_symbol: waypoint.system_symbol.sector_symbol(),
+                        system_type: types::SystemType::OrangeStar,
+                        x: 0,
+                        y: 0,
+                        waypoints: Vec::new(),
+                        factions: vec![types::FactionReference { symbol: self.faction_symbol.clone() }],
+                    });
+                    systems.last_mut().unwrap()
+                }
+            };
+            system.waypoints.push(waypoint.reference.clone());
+        }
+        systems
+    }
+
+    pub(crate) fn faction(&self) -> types::Faction {
+        types::Faction {
+            symbol: self.faction_symbol.clone(),
+            name: display_name(self.faction_symbol.as_str()),
+            description: String::new(),
+            headquarters: types::WaypointSymbol::new(&self.agent.headquarters).unwrap(),
+            traits: Vec::new(),
+            is_recruiting: true,
+        }
+    }
+
+    /// Jump gates lead nowhere, since the simulator only plays out single systems.
+    pub(crate) fn jump_gate(&self, symbol: &types::WaypointSymbol) -> Result<types::JumpGate> {
+        if !self.waypoint(symbol)?.is_jump_gate() {
+            return Err(error(NOT_FOUND, format!("{} is not a jump gate", symbol)));
+        }
+        Ok(types::JumpGate {
+            jump_range: 0.0,
+            faction_symbol: Some(self.faction_symbol.clone()),
+            connected_systems: Vec::new(),
+        })
+    }
+
+    pub(crate) fn list_waypoints(&self, system_symbol: &types::SystemSymbol) -> Vec<types::Waypoint> {
+        self.waypoints.iter()
+            .filter(|waypoint| waypoint.system_symbol == *system_symbol)
+            .cloned()
+            .collect()
+    }
+}
+
+fn waypoint_trait(symbol: &str) -> types::WaypointTrait {
+    types::WaypointTrait {
+        symbol: symbol.into(),
+        name: display_name(symbol),
+        description: String::new(),
+    }
+}